@@ -15,6 +15,7 @@ const PROGRAM_ID = new PublicKey(req("SOLANA_PROGRAM_ID"));
 const PAYER_JSON = req("SOLANA_PAYER_KEYPAIR");
 const PORTAL_ADDRESS = req("PORTAL_ADDRESS");
 const EVM_CHAIN = Number(process.env.RECEIPT_EVM_CHAIN || "2");
+const CHAIN_LABEL = process.env.RECEIPT_CHAIN_LABEL || "";
 
 function evmAddressToEmitter32(address: string): Buffer {
   const a = ethers.utils.getAddress(address);
@@ -29,13 +30,32 @@ function deriveCfgPda(programId: PublicKey): PublicKey {
   return pda;
 }
 
-// data = disc(8) + evm_chain(u16 LE) + emitter([u8;32])
-function buildIxDataInit(evmChain: number, emitter32: Buffer): Buffer {
+function deriveEventCounterPda(programId: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync([Buffer.from("event_counter")], programId);
+  return pda;
+}
+
+function deriveStatsPda(programId: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync([Buffer.from("stats")], programId);
+  return pda;
+}
+
+// Printable ASCII, zero-padded to 16 bytes; empty leaves it unset.
+function chainLabel16(label: string): Buffer {
+  if (!/^[\x20-\x7e]{0,16}$/.test(label)) throw new Error("chain label must be <=16 printable ASCII chars");
+  const out = Buffer.alloc(16);
+  Buffer.from(label, "ascii").copy(out);
+  return out;
+}
+
+// data = disc(8) + evm_chain(u16 LE) + emitter([u8;32]) + chain_label([u8;16]) + evm_emitters(bool)
+function buildIxDataInit(evmChain: number, emitter32: Buffer, label16: Buffer): Buffer {
   const discHex = ethers.utils.sha256(ethers.utils.toUtf8Bytes("global:init_receipt_config")).slice(2);
   const disc = Buffer.from(discHex, "hex").subarray(0, 8);
   const chainLe = Buffer.alloc(2); chainLe.writeUInt16LE(evmChain);
   if (emitter32.length !== 32) throw new Error("emitter32 length");
-  return Buffer.concat([disc, chainLe, emitter32]);
+  // The emitter is always a left-padded EVM address here, so enforce it on-chain.
+  return Buffer.concat([disc, chainLe, emitter32, label16, Buffer.from([1])]);
 }
 
 function loadPayer(): Keypair {
@@ -49,10 +69,12 @@ function loadPayer(): Keypair {
   const payer = loadPayer();
   const cfg = deriveCfgPda(PROGRAM_ID);
   const emitter32 = evmAddressToEmitter32(PORTAL_ADDRESS);
-  const data = buildIxDataInit(EVM_CHAIN, emitter32);
+  const data = buildIxDataInit(EVM_CHAIN, emitter32, chainLabel16(CHAIN_LABEL));
 
   const keys = [
     { pubkey: cfg,            isSigner: false, isWritable: true  },
+    { pubkey: deriveEventCounterPda(PROGRAM_ID), isSigner: false, isWritable: true },
+    { pubkey: deriveStatsPda(PROGRAM_ID), isSigner: false, isWritable: true },
     { pubkey: payer.publicKey,isSigner: true,  isWritable: true  },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
   ];
@@ -5,7 +5,7 @@
 import "dotenv/config";
 import axios from "axios";
 import { ethers } from "ethers";
-import { Connection, PublicKey, ConfirmedSignatureInfo, VersionedTransactionResponse, Transaction, SystemProgram, Keypair, TransactionInstruction, sendAndConfirmTransaction } from "@solana/web3.js";
+import { Connection, PublicKey, ConfirmedSignatureInfo, VersionedTransactionResponse, Transaction, SystemProgram, Keypair, TransactionInstruction, sendAndConfirmTransaction, SYSVAR_CLOCK_PUBKEY } from "@solana/web3.js";
 import fs from "fs";
 import path from "path";
 import { spawn } from "child_process";
@@ -302,9 +302,15 @@ export async function runDrain() {
 function evmAddressToEmitter32(address: string): string { const a = ethers.utils.getAddress(address); const bytes = ethers.utils.arrayify(a); if (bytes.length !== 20) throw new Error("bad evm addr"); const padded = new Uint8Array(32); padded.set(bytes, 12); return ethers.utils.hexlify(padded); }
 const EVM_EMITTER_32 = evmAddressToEmitter32(PORTAL_ADDRESS).replace(/^0x/i, "").toLowerCase();
 function deriveCfgPda(programId: PublicKey): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("cfg")], programId); return pda; }
+function deriveEventCounterPda(programId: PublicKey): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("event_counter")], programId); return pda; }
+function deriveStatsPda(programId: PublicKey): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("stats")], programId); return pda; }
+function deriveEmitterStatePda(programId: PublicKey, emitter32: Uint8Array): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("emitter_state"), Buffer.from(emitter32)], programId); return pda; }
 function deriveReceiptPda(programId: PublicKey, emitter32: Uint8Array, origSeq: bigint): PublicKey { const seqBe = Buffer.alloc(8); seqBe.writeBigUInt64BE(origSeq); const [pda] = PublicKey.findProgramAddressSync([Buffer.from("receipt"), Buffer.from(emitter32), seqBe], programId); return pda; }
-function buildRecordReceiptFromVaaIxData(emitter32Hex: string, origSeq: bigint): Buffer { const discHex = ethers.utils.sha256(ethers.utils.toUtf8Bytes("global:record_receipt_from_vaa")).slice(2); const disc = Buffer.from(discHex, "hex").subarray(0, 8); const emitter = Buffer.from(emitter32Hex.replace(/^0x/i, ""), "hex"); if (emitter.length !== 32) throw new Error("emitter32 bad length"); const seqLe = Buffer.alloc(8); seqLe.writeBigUInt64LE(origSeq); return Buffer.concat([disc, emitter, seqLe]); }
-function buildRecordReceiptDirectIxData(emitter32Hex: string, origSeq: bigint): Buffer { const discHex = ethers.utils.sha256(ethers.utils.toUtf8Bytes("global:record_receipt_direct")).slice(2); const disc = Buffer.from(discHex, "hex").subarray(0, 8); const emitter = Buffer.from(emitter32Hex.replace(/^0x/i, ""), "hex"); if (emitter.length !== 32) throw new Error("emitter32 bad length"); const seqLe = Buffer.alloc(8); seqLe.writeBigUInt64LE(origSeq); return Buffer.concat([disc, emitter, seqLe]); }
+function buildRecordReceiptFromVaaIxData(emitter32Hex: string, origSeq: bigint, category = 0): Buffer { const discHex = ethers.utils.sha256(ethers.utils.toUtf8Bytes("global:record_receipt_from_vaa")).slice(2); const disc = Buffer.from(discHex, "hex").subarray(0, 8); const emitter = Buffer.from(emitter32Hex.replace(/^0x/i, ""), "hex"); if (emitter.length !== 32) throw new Error("emitter32 bad length"); const seqLe = Buffer.alloc(8); seqLe.writeBigUInt64LE(origSeq); const catLe = Buffer.alloc(2); catLe.writeUInt16LE(category); const externalRef = Buffer.from([0]); /* external_ref: None */ const reportedCost = Buffer.from([0]); /* reported_cost: None */ const calldataHash = Buffer.from([0]); /* calldata_hash: None */ return Buffer.concat([disc, emitter, seqLe, catLe, externalRef, reportedCost, calldataHash]); }
+function buildRecordReceiptDirectIxData(emitter32Hex: string, origSeq: bigint): Buffer { const discHex = ethers.utils.sha256(ethers.utils.toUtf8Bytes("global:record_receipt_direct")).slice(2); const disc = Buffer.from(discHex, "hex").subarray(0, 8); const emitter = Buffer.from(emitter32Hex.replace(/^0x/i, ""), "hex"); if (emitter.length !== 32) throw new Error("emitter32 bad length"); const seqLe = Buffer.alloc(8); seqLe.writeBigUInt64LE(origSeq); const externalRef = Buffer.from([0]); /* external_ref: None */ return Buffer.concat([disc, emitter, seqLe, externalRef]); }
+function deriveEmitterCursorPda(programId: PublicKey, emitter32: Uint8Array): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("cursor"), Buffer.from(emitter32)], programId); return pda; } // per-emitter (no per_category_cursors)
+function deriveEventRingPda(programId: PublicKey): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("event_ring")], programId); return pda; }
+async function eventRingMeta() { const pda = deriveEventRingPda(SOLANA_PROGRAM_ID); const info = await conn.getAccountInfo(pda, { commitment: "finalized" }); return info ? { pubkey: pda, isSigner: false, isWritable: true } : { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }; }
 function derivePostedVaaPda(wormholeProgramId: PublicKey, vaaHash: Uint8Array): PublicKey { const [pda] = PublicKey.findProgramAddressSync([Buffer.from("PostedVAA"), Buffer.from(vaaHash)], wormholeProgramId); return pda; }
 function loadSolanaPayer(): Keypair { const file = SOLANA_PAYER_KEYPAIR; const raw = fs.readFileSync(file, "utf8"); let arr: number[] | undefined; try { arr = JSON.parse(raw); } catch { throw new Error(`SOLANA_PAYER_KEYPAIR must be a JSON array of secret key: ${file}`); } if (!Array.isArray(arr)) throw new Error("bad keypair json"); return Keypair.fromSecretKey(Uint8Array.from(arr)); }
 async function isReceiptRecordedOnSolana(origSeq: bigint): Promise<boolean> { const emitter32 = Buffer.from(EVM_EMITTER_32, "hex"); const rPda = deriveReceiptPda(SOLANA_PROGRAM_ID, emitter32, origSeq); const info = await conn.getAccountInfo(rPda, { commitment: "finalized" }); return !!info; }
@@ -328,11 +334,25 @@ async function recordReceiptFromVaaOnSolana(postedVaa: PublicKey, wormholeProgra
   const ixData = buildRecordReceiptFromVaaIxData("0x" + EVM_EMITTER_32, origSeq);
   const keys = [
     { pubkey: cfgPda, isSigner: false, isWritable: true },
+    { pubkey: deriveEventCounterPda(SOLANA_PROGRAM_ID), isSigner: false, isWritable: true },
+    { pubkey: deriveStatsPda(SOLANA_PROGRAM_ID), isSigner: false, isWritable: true },
     { pubkey: postedVaa, isSigner: false, isWritable: false },
     { pubkey: wormholeProgramId, isSigner: false, isWritable: false },
     { pubkey: receiptPda, isSigner: false, isWritable: true },
     { pubkey: payer.publicKey, isSigner: true, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: deriveEmitterStatePda(SOLANA_PROGRAM_ID, emitter32), isSigner: false, isWritable: false },
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // batch_marker: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // hash_index: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // emitter_index: None
+    { pubkey: deriveEmitterCursorPda(SOLANA_PROGRAM_ID, emitter32), isSigner: false, isWritable: true }, // cursor: may not exist yet
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // emitter_count: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // expected_digest: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // verifying_key: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // trusted_guardian_set: None
+    { pubkey: SOLANA_PROGRAM_ID, isSigner: false, isWritable: false }, // instructions: None (top-level call)
+    await eventRingMeta(), // event_ring: required once initialised
   ];
   const ix = new TransactionInstruction({ programId: SOLANA_PROGRAM_ID, keys, data: ixData });
   const tx = new Transaction().add(ix);
@@ -344,9 +364,13 @@ async function recordReceiptDirectOnSolana(origSeq: bigint, payer: Keypair) {
   const ixData = buildRecordReceiptDirectIxData("0x" + EVM_EMITTER_32, origSeq);
   const keys = [
     { pubkey: cfgPda, isSigner: false, isWritable: true },
+    { pubkey: deriveEventCounterPda(SOLANA_PROGRAM_ID), isSigner: false, isWritable: true },
+    { pubkey: deriveStatsPda(SOLANA_PROGRAM_ID), isSigner: false, isWritable: true },
     { pubkey: receiptPda, isSigner: false, isWritable: true },
     { pubkey: payer.publicKey, isSigner: true, isWritable: true },
     { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+    await eventRingMeta(), // event_ring: required once initialised
   ];
   const ix = new TransactionInstruction({ programId: SOLANA_PROGRAM_ID, keys, data: ixData });
   const tx = new Transaction().add(ix);
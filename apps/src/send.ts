@@ -45,6 +45,37 @@ export async function sendOnce(payloadStr: string) {
     programId
   );
 
+  const [cfg] = PublicKey.findProgramAddressSync(
+    [Buffer.from("cfg")],
+    programId
+  );
+
+  const [eventCounter] = PublicKey.findProgramAddressSync(
+    [Buffer.from("event_counter")],
+    programId
+  );
+
+  const [posterConfig] = PublicKey.findProgramAddressSync(
+    [Buffer.from("poster_cfg")],
+    programId
+  );
+
+  // Protocol fee goes to the configured treasury (if any)
+  const cfgAccount = await (program as any).account.receiptConfig.fetch(cfg);
+  const treasury: PublicKey | null = cfgAccount.treasury.equals(PublicKey.default)
+    ? null
+    : cfgAccount.treasury;
+
+  // cfg.max_in_flight requires a batch marker on every primary post
+  const batchId = 0;
+  const batchMarker: PublicKey | null =
+    cfgAccount.maxInFlight > 0
+      ? PublicKey.findProgramAddressSync(
+          [Buffer.from("batch"), new BN(batchId).toArrayLike(Buffer, "le", 4)],
+          programId
+        )[0]
+      : null;
+
   const bridge = PublicKey.findProgramAddressSync(
     [Buffer.from("Bridge")],
     CORE_PID
@@ -81,11 +112,20 @@ export async function sendOnce(payloadStr: string) {
   // No pre-transfer of fee; paid on-chain
   const sig = await (program as any).methods
     .postWormholeMessage(
-      new BN(0), // batch_id: u32
+      batchId, // batch_id: u32
       Buffer.from(payloadStr), // payload: Vec<u8>
-      1 // finality_flag: u8 (1=Finalized)
+      1, // finality_flag: Option<u8> (1=Finalized, null = cfg.default_finality)
+      null, // destination: Option<[u8; 32]>
+      false, // dest_is_evm
+      false, // waive_protocol_fee
+      0, // emitter_role: 0 = primary, 1 = shadow
+      null, // expected_fee: Option<u64> (null = no slippage check)
+      null // payload_hash: Option<[u8; 32]> (null = hashed on-chain)
     )
     .accounts({
+      cfg, // receipt config (post policy)
+      eventCounter,
+      posterConfig,
       config: bridge,
       message: message.publicKey, // signed externally
       emitter,
@@ -96,6 +136,14 @@ export async function sendOnce(payloadStr: string) {
       rent: SYSVAR_RENT_PUBKEY,
       systemProgram: SystemProgram.programId,
       wormholeProgram: CORE_PID,
+      treasury, // null when no protocol fee
+      batchMarker, // null unless cfg.max_in_flight is set
+      attemptCounter: null, // cfg.track_attempts off
+      spendBudget: null, // cfg.budget_per_window off
+      guardianSet: null, // cfg.avoid_expiring_guardian_set off
+      outboundCursor: null, // cfg.enforce_batch_monotonic off
+      expectedDigest: null, // only post_and_register_expected registers one
+      outboundRate: null, // cfg.min_post_interval_slots off
     })
     .signers([message]) // message signs (Core requirement)
     .rpc();
@@ -0,0 +1,224 @@
+//! Finds a signature verified by the Ed25519 or Secp256k1 native program in
+//! the current transaction. Those programs fail the whole transaction on a bad
+//! signature, so a matching instruction means the signature is valid; we only
+//! check that it covers the expected signer and message.
+//!
+//! `secp256k1_signers` applies the same rules to count guardian signatures
+//! over a VAA for `verify_signatures_independently`.
+//!
+//! Only self-contained instructions are accepted: every offset must point into
+//! the verifying instruction's own data (its own index, or `u16::MAX` for
+//! Ed25519's "this instruction").
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
+use anchor_lang::solana_program::{ed25519_program, secp256k1_program};
+
+/// Ed25519 header: `num_signatures u8 | padding u8`, then 14-byte offset records.
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+/// Secp256k1 header: `num_signatures u8`, then 11-byte offset records.
+const SECP256K1_HEADER_LEN: usize = 1;
+const SECP256K1_OFFSETS_LEN: usize = 11;
+
+/// True if an instruction in this transaction verifies `signature` by
+/// `signer` over `message`. `signer` is an Ed25519 pubkey, or a 20-byte
+/// Ethereum address left-padded to 32 bytes for Secp256k1 (whose 65-byte
+/// signature must start with `signature`).
+pub fn has_verified_signature(
+    instructions: &AccountInfo,
+    signer: &[u8; 32],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> bool {
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        let found = if ix.program_id == ed25519_program::ID {
+            ed25519_matches(&ix.data, index as u16, signer, signature, message)
+        } else if ix.program_id == secp256k1_program::ID {
+            signer[..12].iter().all(|b| *b == 0)
+                && index <= u8::MAX as usize
+                && secp256k1_matches(&ix.data, index as u8, &signer[12..], signature, message)
+        } else {
+            false
+        };
+        if found {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+fn ed25519_matches(
+    data: &[u8],
+    own_index: u16,
+    signer: &[u8; 32],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> bool {
+    let count = data.first().copied().unwrap_or(0) as usize;
+    (0..count).any(|i| {
+        let at = ED25519_HEADER_LEN + i * ED25519_OFFSETS_LEN;
+        let Some(rec) = data.get(at..at + ED25519_OFFSETS_LEN) else {
+            return false;
+        };
+        let u = |k: usize| u16::from_le_bytes([rec[k], rec[k + 1]]);
+        let (sig_off, sig_ix, key_off, key_ix, msg_off, msg_len, msg_ix) =
+            (u(0), u(2), u(4), u(6), u(8), u(10), u(12));
+        if [sig_ix, key_ix, msg_ix].iter().any(|ix| *ix != u16::MAX && *ix != own_index) {
+            return false;
+        }
+        slice(data, key_off as usize, 32) == Some(&signer[..])
+            && slice(data, sig_off as usize, 64) == Some(&signature[..])
+            && slice(data, msg_off as usize, msg_len as usize) == Some(message)
+    })
+}
+
+/// Bitmask of the `guardians` (by position, at most 32) whose Secp256k1
+/// signature over `message` some instruction in this transaction verifies.
+/// Each guardian counts once however many records carry it.
+pub fn secp256k1_signers(
+    instructions: &AccountInfo,
+    guardians: &[[u8; 20]],
+    message: &[u8],
+) -> u32 {
+    let mut signed = 0u32;
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == secp256k1_program::ID && index <= u8::MAX as usize {
+            let count = ix.data.first().copied().unwrap_or(0) as usize;
+            for i in 0..count {
+                let Some((_, addr, msg)) = secp256k1_record(&ix.data, index as u8, i) else {
+                    continue;
+                };
+                if msg != message {
+                    continue;
+                }
+                if let Some(pos) = guardians.iter().take(32).position(|g| g[..] == *addr) {
+                    signed |= 1 << pos;
+                }
+            }
+        }
+        index += 1;
+    }
+    signed
+}
+
+fn secp256k1_matches(
+    data: &[u8],
+    own_index: u8,
+    eth_address: &[u8],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> bool {
+    let count = data.first().copied().unwrap_or(0) as usize;
+    (0..count).any(|i| {
+        secp256k1_record(data, own_index, i).map_or(false, |(sig, addr, msg)| {
+            addr == eth_address && sig == &signature[..] && msg == message
+        })
+    })
+}
+
+/// `(signature, eth_address, message)` of record `i` of a Secp256k1
+/// instruction, or `None` if it is out of range or not self-contained.
+fn secp256k1_record(data: &[u8], own_index: u8, i: usize) -> Option<(&[u8], &[u8], &[u8])> {
+    let at = SECP256K1_HEADER_LEN + i * SECP256K1_OFFSETS_LEN;
+    let rec = data.get(at..at + SECP256K1_OFFSETS_LEN)?;
+    let u = |k: usize| u16::from_le_bytes([rec[k], rec[k + 1]]) as usize;
+    let (sig_off, sig_ix, addr_off, addr_ix, msg_off, msg_len, msg_ix) =
+        (u(0), rec[2], u(3), rec[5], u(6), u(8), rec[10]);
+    if [sig_ix, addr_ix, msg_ix].iter().any(|ix| *ix != own_index) {
+        return None;
+    }
+    Some((
+        slice(data, sig_off, 64)?,
+        slice(data, addr_off, 20)?,
+        slice(data, msg_off, msg_len)?,
+    ))
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(len)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One-signature Ed25519 instruction data whose offsets point at
+    /// `pubkey | signature | message` right after the record, all in
+    /// instruction `ix`.
+    fn ed25519_data(ix: u16, key: &[u8; 32], sig: &[u8; 64], msg: &[u8]) -> Vec<u8> {
+        let key_off = (ED25519_HEADER_LEN + ED25519_OFFSETS_LEN) as u16;
+        let sig_off = key_off + 32;
+        let msg_off = sig_off + 64;
+        let mut data = vec![1, 0];
+        for v in [sig_off, ix, key_off, ix, msg_off, msg.len() as u16, ix] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(key);
+        data.extend_from_slice(sig);
+        data.extend_from_slice(msg);
+        data
+    }
+
+    /// Same for Secp256k1: `signature (64) | recovery_id | eth_address | message`.
+    fn secp256k1_data(ix: u8, addr: &[u8; 20], sig: &[u8; 64], msg: &[u8]) -> Vec<u8> {
+        let sig_off = (SECP256K1_HEADER_LEN + SECP256K1_OFFSETS_LEN) as u16;
+        let addr_off = sig_off + 65;
+        let msg_off = addr_off + 20;
+        let mut data = vec![1];
+        data.extend_from_slice(&sig_off.to_le_bytes());
+        data.push(ix);
+        data.extend_from_slice(&addr_off.to_le_bytes());
+        data.push(ix);
+        data.extend_from_slice(&msg_off.to_le_bytes());
+        data.extend_from_slice(&(msg.len() as u16).to_le_bytes());
+        data.push(ix);
+        data.extend_from_slice(sig);
+        data.push(0);
+        data.extend_from_slice(addr);
+        data.extend_from_slice(msg);
+        data
+    }
+
+    #[test]
+    fn ed25519_needs_signer_signature_and_message() {
+        let (key, sig, msg) = ([1u8; 32], [2u8; 64], b"receipt".as_slice());
+        let data = ed25519_data(u16::MAX, &key, &sig, msg);
+        assert!(ed25519_matches(&data, 3, &key, &sig, msg));
+        assert!(!ed25519_matches(&data, 3, &[9u8; 32], &sig, msg));
+        assert!(!ed25519_matches(&data, 3, &key, &[9u8; 64], msg));
+        assert!(!ed25519_matches(&data, 3, &key, &sig, b"other"));
+        // Truncated: the message offset runs past the data.
+        assert!(!ed25519_matches(&data[..data.len() - 1], 3, &key, &sig, msg));
+    }
+
+    #[test]
+    fn ed25519_offsets_must_stay_in_the_instruction() {
+        let (key, sig, msg) = ([1u8; 32], [2u8; 64], b"receipt".as_slice());
+        assert!(ed25519_matches(&ed25519_data(3, &key, &sig, msg), 3, &key, &sig, msg));
+        assert!(!ed25519_matches(&ed25519_data(2, &key, &sig, msg), 3, &key, &sig, msg));
+    }
+
+    #[test]
+    fn secp256k1_records_are_self_contained() {
+        let (addr, sig, msg) = ([4u8; 20], [5u8; 64], b"vaa body".as_slice());
+        let data = secp256k1_data(2, &addr, &sig, msg);
+        let (s, a, m) = secp256k1_record(&data, 2, 0).unwrap();
+        assert_eq!((s, a, m), (&sig[..], &addr[..], msg));
+        assert!(secp256k1_matches(&data, 2, &addr, &sig, msg));
+
+        assert!(secp256k1_record(&data, 1, 0).is_none());
+        assert!(secp256k1_record(&data, 2, 1).is_none());
+        assert!(!secp256k1_matches(&data, 2, &[6u8; 20], &sig, msg));
+    }
+
+    #[test]
+    fn slice_rejects_overflowing_offsets() {
+        assert_eq!(slice(&[1, 2, 3], 1, 2), Some(&[2u8, 3][..]));
+        assert_eq!(slice(&[1, 2, 3], 2, 2), None);
+        assert_eq!(slice(&[1, 2, 3], usize::MAX, 2), None);
+    }
+}
@@ -0,0 +1,116 @@
+//! Raw CPIs for Core Bridge's prepared-message flow.
+//! `init_message_v1` / `write_message_v1` / `finalize_message_v1` use Anchor
+//! discriminators; the final publish is the legacy `post_message` (tag 1) with an
+//! empty payload, which Core Bridge accepts for a finalized draft message.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Legacy Core Bridge instruction tag for `post_message`.
+const LEGACY_POST_MESSAGE_TAG: u8 = 1;
+
+pub struct PostAccounts<'info> {
+    pub config:         AccountInfo<'info>,
+    pub draft_message:  AccountInfo<'info>,
+    pub emitter:        AccountInfo<'info>,
+    pub sequence:       AccountInfo<'info>,
+    pub payer:          AccountInfo<'info>,
+    pub fee_collector:  AccountInfo<'info>,
+    pub clock:          AccountInfo<'info>,
+    pub rent:           AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub core_bridge:    AccountInfo<'info>,
+}
+
+/// Anchor instruction discriminator for a Core Bridge instruction.
+fn discriminator(name: &str) -> [u8; 8] {
+    let digest = hash(format!("global:{name}").as_bytes()).to_bytes();
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&digest[..8]);
+    out
+}
+
+/// Invokes a draft-message instruction signed by the emitter PDA.
+fn invoke_draft_ix(
+    accs: &PostAccounts,
+    signer_seeds: &[&[&[u8]]],
+    data: Vec<u8>,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: accs.core_bridge.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(accs.emitter.key(), true),
+            AccountMeta::new(accs.draft_message.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[accs.emitter.clone(), accs.draft_message.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Writes `payload` into the draft message, finalizes it, then publishes it.
+pub fn prepare_and_post(
+    accs: &PostAccounts,
+    signer_seeds: &[&[&[u8]]],
+    batch_id: u32,
+    payload: Vec<u8>,
+    finality_flag: u8,
+) -> Result<()> {
+    // init_message_v1 { cpi_program_id: None } -> emitter address is the PDA key, as in v1.
+    let mut init = discriminator("init_message_v1").to_vec();
+    init.push(0);
+    invoke_draft_ix(accs, signer_seeds, init)?;
+
+    // write_message_v1 { index: 0, data: payload }
+    let mut write = discriminator("write_message_v1").to_vec();
+    write.extend_from_slice(&0u32.to_le_bytes());
+    write.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    write.extend_from_slice(&payload);
+    invoke_draft_ix(accs, signer_seeds, write)?;
+
+    invoke_draft_ix(accs, signer_seeds, discriminator("finalize_message_v1").to_vec())?;
+
+    // Legacy post_message { nonce, payload: [], consistency }; the draft does not sign.
+    let mut post = vec![LEGACY_POST_MESSAGE_TAG];
+    post.extend_from_slice(&batch_id.to_le_bytes());
+    post.extend_from_slice(&0u32.to_le_bytes());
+    post.push(finality_flag);
+
+    let ix = Instruction {
+        program_id: accs.core_bridge.key(),
+        accounts: vec![
+            AccountMeta::new(accs.config.key(), false),
+            AccountMeta::new(accs.draft_message.key(), false),
+            AccountMeta::new_readonly(accs.emitter.key(), true),
+            AccountMeta::new(accs.sequence.key(), false),
+            AccountMeta::new(accs.payer.key(), true),
+            AccountMeta::new(accs.fee_collector.key(), false),
+            AccountMeta::new_readonly(accs.clock.key(), false),
+            AccountMeta::new_readonly(accs.rent.key(), false),
+            AccountMeta::new_readonly(accs.system_program.key(), false),
+        ],
+        data: post,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            accs.config.clone(),
+            accs.draft_message.clone(),
+            accs.emitter.clone(),
+            accs.sequence.clone(),
+            accs.payer.clone(),
+            accs.fee_collector.clone(),
+            accs.clock.clone(),
+            accs.rent.clone(),
+            accs.system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,344 @@
+//! Groth16 verification over BN254 with the alt_bn128 syscalls.
+//!
+//! Points use the EIP-197 big-endian encoding (G2 coordinates imaginary part
+//! first), the format Ethereum verifier contracts consume. The proof's `a` is
+//! passed as produced by the prover; it is negated here for the pairing check
+//! `e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1`.
+//!
+//! Inline proofs trail the VAA payload, led by the id of the circuit whose
+//! verifying key checks them:
+//! `... | circuit_id u32 BE | proof_a (64) | proof_b (128) | proof_c (64) |
+//! inputs (32 each) | input_count u8`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::alt_bn128::prelude::{
+    alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
+};
+use anchor_lang::solana_program::keccak;
+
+use crate::{VerifyingKey, ZkError};
+
+pub const G1_LEN: usize = 64;
+pub const G2_LEN: usize = 128;
+pub const PROOF_LEN: usize = G1_LEN + G2_LEN + G1_LEN;
+pub const INPUT_LEN: usize = 32;
+pub const CIRCUIT_ID_LEN: usize = 4;
+
+/// BN254 base field modulus `p`, big-endian.
+const FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+/// BN254 scalar field modulus `r`, big-endian; public inputs must be below it.
+const SCALAR_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+/// Domain separator of the `verify_batch` Fiat-Shamir transcript.
+const BATCH_DOMAIN: &[u8] = b"zk-coprocessor/groth16-batch/v1";
+/// Bytes of each batch weight; 128-bit weights bound the soundness error.
+const BATCH_WEIGHT_LEN: usize = 16;
+
+pub struct Proof {
+    pub a: [u8; G1_LEN],
+    pub b: [u8; G2_LEN],
+    pub c: [u8; G1_LEN],
+}
+
+/// Splits the trailing proof section off `payload`, returning its circuit id.
+pub fn split_proof(payload: &[u8]) -> Result<(u32, Proof, Vec<[u8; INPUT_LEN]>)> {
+    let (&count, rest) = payload.split_last().ok_or(error!(ZkError::ProofInvalid))?;
+    let inputs_len = count as usize * INPUT_LEN;
+    require!(
+        rest.len() >= CIRCUIT_ID_LEN + PROOF_LEN + inputs_len,
+        ZkError::ProofInvalid
+    );
+    let (head, input_bytes) = rest.split_at(rest.len() - inputs_len);
+    let proof_bytes = &head[head.len() - PROOF_LEN..];
+    let id_bytes = &head[head.len() - PROOF_LEN - CIRCUIT_ID_LEN..head.len() - PROOF_LEN];
+    let circuit_id = u32::from_be_bytes(id_bytes.try_into().expect("CIRCUIT_ID_LEN bytes"));
+
+    let mut proof = Proof { a: [0; G1_LEN], b: [0; G2_LEN], c: [0; G1_LEN] };
+    proof.a.copy_from_slice(&proof_bytes[..G1_LEN]);
+    proof.b.copy_from_slice(&proof_bytes[G1_LEN..G1_LEN + G2_LEN]);
+    proof.c.copy_from_slice(&proof_bytes[G1_LEN + G2_LEN..]);
+    let inputs = input_bytes
+        .chunks_exact(INPUT_LEN)
+        .map(|chunk| chunk.try_into().expect("chunks_exact yields INPUT_LEN bytes"))
+        .collect();
+    Ok((circuit_id, proof, inputs))
+}
+
+/// Fails with `ProofInvalid` unless `proof` verifies against `vk` for `inputs`.
+/// A wrong input count (`vk.ic_len - 1` expected) fails first with
+/// `PublicInputCountMismatch`, before any curve operation.
+pub fn verify(vk: &VerifyingKey, proof: &Proof, inputs: &[[u8; INPUT_LEN]]) -> Result<()> {
+    let vk_x = compute_vk_x(vk, inputs, ZkError::ProofInvalid)?;
+    let neg_a = negate_g1(&proof.a)?;
+    let pairing_input = [
+        &neg_a[..],
+        &proof.b[..],
+        &vk.alpha_g1[..],
+        &vk.beta_g2[..],
+        &vk_x[..],
+        &vk.gamma_g2[..],
+        &proof.c[..],
+        &vk.delta_g2[..],
+    ]
+    .concat();
+    let result =
+        alt_bn128_pairing(&pairing_input).map_err(|_| error!(ZkError::ProofInvalid))?;
+    require!(
+        result.len() == 32 && result[..31].iter().all(|b| *b == 0) && result[31] == 1,
+        ZkError::ProofInvalid
+    );
+    Ok(())
+}
+
+/// Verifies every `(proof, inputs)` in `batch` against `vk` with one pairing
+/// check of `n + 3` pairings instead of `4n`, failing with `BatchProofInvalid`
+/// unless all of them verify.
+///
+/// Soundness: proof `i` is weighted by a 128-bit scalar `r_i` and the `n`
+/// Groth16 equations are multiplied together:
+/// `prod e(-r_i A_i, B_i) * e(sum(r_i) alpha, beta) * e(sum(r_i vk_x_i), gamma)
+/// * e(sum(r_i C_i), delta) == 1`. If any single equation fails, the product
+/// holds only when the weights hit a root of a nonzero linear relation, which
+/// independent uniform weights do with probability at most 2^-128. The
+/// weights are Fiat-Shamir challenges, keccak over every proof and input, so
+/// they are fixed only once the whole batch is; steering them towards a bad
+/// batch costs about 2^128 hashes.
+pub fn verify_batch(vk: &VerifyingKey, batch: &[(Proof, Vec<[u8; INPUT_LEN]>)]) -> Result<()> {
+    require!(!batch.is_empty(), ZkError::BatchProofInvalid);
+    let mut transcript = BATCH_DOMAIN.to_vec();
+    for (proof, inputs) in batch {
+        transcript.extend_from_slice(&proof.a);
+        transcript.extend_from_slice(&proof.b);
+        transcript.extend_from_slice(&proof.c);
+        transcript.push(inputs.len() as u8);
+        for input in inputs {
+            transcript.extend_from_slice(input);
+        }
+    }
+    let seed = keccak::hash(&transcript).to_bytes();
+
+    let err = ZkError::BatchProofInvalid;
+    let mut pairing_input = Vec::with_capacity((batch.len() + 3) * (G1_LEN + G2_LEN));
+    let mut weight_sum = [0u8; 32];
+    let mut acc_vk_x = [0u8; G1_LEN];
+    let mut acc_c = [0u8; G1_LEN];
+    for (i, (proof, inputs)) in batch.iter().enumerate() {
+        let weight = batch_weight(&seed, i as u32);
+        let vk_x = compute_vk_x(vk, inputs, err)?;
+        let neg_weighted_a = negate_g1(&g1_mul(&proof.a, &weight, err)?)?;
+        pairing_input.extend_from_slice(&neg_weighted_a);
+        pairing_input.extend_from_slice(&proof.b);
+        acc_vk_x = g1_add(&acc_vk_x, &g1_mul(&vk_x, &weight, err)?, err)?;
+        acc_c = g1_add(&acc_c, &g1_mul(&proof.c, &weight, err)?, err)?;
+        add_scalar(&mut weight_sum, &weight);
+    }
+    for (g1, g2) in [
+        (g1_mul(&vk.alpha_g1, &weight_sum, err)?, &vk.beta_g2),
+        (acc_vk_x, &vk.gamma_g2),
+        (acc_c, &vk.delta_g2),
+    ] {
+        pairing_input.extend_from_slice(&g1);
+        pairing_input.extend_from_slice(g2);
+    }
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| error!(err))?;
+    require!(
+        result.len() == 32 && result[..31].iter().all(|b| *b == 0) && result[31] == 1,
+        ZkError::BatchProofInvalid
+    );
+    Ok(())
+}
+
+/// `vk.ic[0] + sum(inputs[j] * vk.ic[j + 1])`, the public-input commitment.
+fn compute_vk_x(
+    vk: &VerifyingKey,
+    inputs: &[[u8; INPUT_LEN]],
+    err: ZkError,
+) -> Result<[u8; G1_LEN]> {
+    require!(
+        inputs.len() + 1 == vk.ic_len as usize,
+        ZkError::PublicInputCountMismatch
+    );
+    let mut vk_x = vk.ic[0];
+    for (input, ic) in inputs.iter().zip(&vk.ic[1..]) {
+        require!(*input < SCALAR_MODULUS, err);
+        vk_x = g1_add(&vk_x, &g1_mul(ic, input, err)?, err)?;
+    }
+    Ok(vk_x)
+}
+
+fn g1_add(a: &[u8; G1_LEN], b: &[u8; G1_LEN], err: ZkError) -> Result<[u8; G1_LEN]> {
+    let sum = alt_bn128_addition(&[&a[..], &b[..]].concat()).map_err(|_| error!(err))?;
+    sum.get(..G1_LEN)
+        .and_then(|s| s.try_into().ok())
+        .ok_or(error!(err))
+}
+
+fn g1_mul(point: &[u8; G1_LEN], scalar: &[u8; 32], err: ZkError) -> Result<[u8; G1_LEN]> {
+    let product =
+        alt_bn128_multiplication(&[&point[..], &scalar[..]].concat()).map_err(|_| error!(err))?;
+    product
+        .get(..G1_LEN)
+        .and_then(|p| p.try_into().ok())
+        .ok_or(error!(err))
+}
+
+/// Weight of batch entry `index`: the low 128 bits of `keccak(seed || index_le)`,
+/// as a big-endian scalar.
+fn batch_weight(seed: &[u8; 32], index: u32) -> [u8; 32] {
+    let digest = keccak::hashv(&[&seed[..], &index.to_le_bytes()[..]]).to_bytes();
+    let mut weight = [0u8; 32];
+    weight[32 - BATCH_WEIGHT_LEN..].copy_from_slice(&digest[32 - BATCH_WEIGHT_LEN..]);
+    weight
+}
+
+/// Big-endian `acc += value`. Sums of at most 2^32 128-bit weights stay far
+/// below the scalar modulus, so no reduction is needed.
+fn add_scalar(acc: &mut [u8; 32], value: &[u8; 32]) {
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = acc[i] as u16 + value[i] as u16 + carry;
+        acc[i] = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+/// `(x, y) -> (x, p - y)`; the point at infinity (all zeros) is its own negation.
+fn negate_g1(point: &[u8; G1_LEN]) -> Result<[u8; G1_LEN]> {
+    if point.iter().all(|b| *b == 0) {
+        return Ok(*point);
+    }
+    let y: [u8; 32] = point[32..].try_into().expect("G1 point is 64 bytes");
+    require!(y < FIELD_MODULUS, ZkError::ProofInvalid);
+
+    let mut out = *point;
+    let mut borrow = 0u16;
+    for i in (0..32).rev() {
+        let diff = FIELD_MODULUS[i] as u16 + 256 - y[i] as u16 - borrow;
+        out[32 + i] = diff as u8;
+        borrow = u16::from(diff < 256);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The G1 generator `(1, 2)`.
+    fn g1_generator() -> [u8; G1_LEN] {
+        let mut point = [0u8; G1_LEN];
+        point[31] = 1;
+        point[63] = 2;
+        point
+    }
+
+    fn vk_with_inputs(count: usize) -> VerifyingKey {
+        VerifyingKey {
+            circuit_id: 7,
+            alpha_g1: [0; 64],
+            beta_g2: [0; 128],
+            gamma_g2: [0; 128],
+            delta_g2: [0; 128],
+            ic_len: count as u8 + 1,
+            ic: [g1_generator(); VerifyingKey::MAX_PUBLIC_INPUTS + 1],
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn split_proof_reads_the_trailing_section() {
+        let mut payload = vec![0xaa, 0xbb];
+        payload.extend_from_slice(&7u32.to_be_bytes());
+        payload.extend_from_slice(&[1u8; G1_LEN]);
+        payload.extend_from_slice(&[2u8; G2_LEN]);
+        payload.extend_from_slice(&[3u8; G1_LEN]);
+        payload.extend_from_slice(&[4u8; INPUT_LEN]);
+        payload.extend_from_slice(&[5u8; INPUT_LEN]);
+        payload.push(2);
+
+        let (circuit_id, proof, inputs) = split_proof(&payload).unwrap();
+        assert_eq!(circuit_id, 7);
+        assert_eq!((proof.a, proof.b, proof.c), ([1u8; G1_LEN], [2u8; G2_LEN], [3u8; G1_LEN]));
+        assert_eq!(inputs, vec![[4u8; INPUT_LEN], [5u8; INPUT_LEN]]);
+
+        // One byte short of the circuit id, and a count claiming more inputs.
+        assert_eq!(split_proof(&payload[3..]).err(), Some(ZkError::ProofInvalid.into()));
+        let len = payload.len();
+        payload[len - 1] = 3;
+        assert_eq!(split_proof(&payload).err(), Some(ZkError::ProofInvalid.into()));
+        assert_eq!(split_proof(&[]).err(), Some(ZkError::ProofInvalid.into()));
+    }
+
+    #[test]
+    fn vk_x_checks_count_then_range() {
+        let vk = vk_with_inputs(1);
+        assert_eq!(
+            compute_vk_x(&vk, &[], ZkError::ProofInvalid).err(),
+            Some(ZkError::PublicInputCountMismatch.into())
+        );
+        assert_eq!(
+            compute_vk_x(&vk, &[SCALAR_MODULUS], ZkError::BatchProofInvalid).err(),
+            Some(ZkError::BatchProofInvalid.into())
+        );
+        // ic[0] + 0 * ic[1] is ic[0].
+        assert_eq!(
+            compute_vk_x(&vk, &[[0u8; 32]], ZkError::ProofInvalid).unwrap(),
+            g1_generator()
+        );
+    }
+
+    #[test]
+    fn negate_g1_mirrors_y() {
+        assert_eq!(negate_g1(&[0u8; G1_LEN]).unwrap(), [0u8; G1_LEN]);
+
+        let neg = negate_g1(&g1_generator()).unwrap();
+        assert_eq!(neg[..32], g1_generator()[..32]);
+        let mut p_minus_two = FIELD_MODULUS;
+        p_minus_two[31] -= 2;
+        assert_eq!(neg[32..], p_minus_two);
+        assert_eq!(
+            g1_add(&g1_generator(), &neg, ZkError::ProofInvalid).unwrap(),
+            [0u8; G1_LEN]
+        );
+
+        let mut off_field = g1_generator();
+        off_field[32..].copy_from_slice(&FIELD_MODULUS);
+        assert_eq!(negate_g1(&off_field).err(), Some(ZkError::ProofInvalid.into()));
+    }
+
+    #[test]
+    fn add_scalar_carries_across_bytes() {
+        let mut acc = [0u8; 32];
+        acc[31] = 0xff;
+        acc[30] = 0xff;
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        add_scalar(&mut acc, &one);
+        let mut expected = [0u8; 32];
+        expected[29] = 1;
+        assert_eq!(acc, expected);
+    }
+
+    #[test]
+    fn batch_weights_are_128_bit_and_index_bound() {
+        let seed = [9u8; 32];
+        let w0 = batch_weight(&seed, 0);
+        assert!(w0[..32 - BATCH_WEIGHT_LEN].iter().all(|b| *b == 0));
+        assert!(w0 < SCALAR_MODULUS);
+        assert_ne!(w0, batch_weight(&seed, 1));
+        assert_eq!(w0, batch_weight(&seed, 0));
+    }
+
+    #[test]
+    fn empty_batch_is_rejected() {
+        assert_eq!(
+            verify_batch(&vk_with_inputs(0), &[]).err(),
+            Some(ZkError::BatchProofInvalid.into())
+        );
+    }
+}
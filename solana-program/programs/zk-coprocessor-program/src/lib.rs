@@ -4,56 +4,381 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::AccountDeserialize; // Manual BridgeData decode
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::sysvar::instructions::get_instruction_relative;
+use anchor_lang::solana_program::program::{set_return_data, MAX_RETURN_DATA};
 use wormhole_anchor_sdk::wormhole;
 use wormhole_anchor_sdk::wormhole::program::Wormhole;
 
+#[cfg(feature = "core-bridge-v2")]
+mod core_bridge_v2;
+mod attest;
+mod groth16;
+mod vaa;
+
 declare_id!("A6BL2woTfWSHHYULjqB9craU67WWPPkF8GnoJR8vG8E3");
 
+/// Emits an event through program logs (`emit!`, default) or, with the
+/// `event-cpi` feature, through a self-CPI signed by the event authority PDA
+/// (same wire format as `emit_cpi!`).
+///
+/// With `event-cpi`, every instruction that emits events takes two extra
+/// trailing accounts: `event_authority` (PDA `[b"__event_authority"]`) and
+/// `program` (this program id), both read-only.
+///
+/// An event emitted just before an instruction fails (`PayloadConflictDetected`,
+/// `PostRejectedMaintenance`, ...) persists nothing on chain but is still in
+/// the failed transaction's logs. Monitoring that reads failed transactions
+/// sees it; indexers that follow only successful transactions do not.
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {{
+        let event = $event;
+        #[cfg(not(feature = "event-cpi"))]
+        emit!(event);
+        #[cfg(feature = "event-cpi")]
+        {
+            let authority = $ctx.accounts.event_authority.to_account_info();
+            let bump = [$ctx.bumps.event_authority];
+            let data = [anchor_lang::event::EVENT_IX_TAG_LE, anchor_lang::Event::data(&event).as_slice()].concat();
+            let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
+                crate::ID,
+                &data,
+                vec![AccountMeta::new_readonly(authority.key(), true)],
+            );
+            anchor_lang::solana_program::program::invoke_signed(
+                &ix,
+                &[authority],
+                &[&[b"__event_authority", &bump]],
+            )?;
+        }
+    }};
+}
+
+/// Writes a new receipt's HashIndex entry through `write_hash_index`, paid by
+/// `$payer`. `$index` is the optional HashIndex account, required with
+/// `cfg.require_hash_index`. A payload hash already recorded under another
+/// sequence emits `DuplicatePayloadDetected` and fails with
+/// `DuplicatePayloadAcrossSequences`. Needs `cfg`, `event_counter` and
+/// `system_program`.
+macro_rules! index_payload_hash {
+    ($ctx:ident, $payer:ident, $index:expr, $payload_hash:expr, $emitter:expr, $sequence:expr) => {{
+        let (payload_hash, emitter, sequence): ([u8; 32], [u8; 32], u64) =
+            ($payload_hash, $emitter, $sequence);
+        match $index {
+            Some(index) => {
+                let duplicate = write_hash_index(
+                    index,
+                    &$ctx.accounts.$payer.to_account_info(),
+                    &$ctx.accounts.system_program.to_account_info(),
+                    &payload_hash,
+                    &emitter,
+                    sequence,
+                )?;
+                if let Some(existing) = duplicate {
+                    msg!("payload hash already recorded under sequence {}", existing.sequence);
+                    emit_event!($ctx, DuplicatePayloadDetected {
+                        payload_hash,
+                        emitter,
+                        sequence,
+                        existing_emitter: existing.emitter,
+                        existing_sequence: existing.sequence,
+                        event_seq: $ctx.accounts.event_counter.next()?,
+                    });
+                    return err!(ZkError::DuplicatePayloadAcrossSequences);
+                }
+            }
+            None => require!(!$ctx.accounts.cfg.require_hash_index, ZkError::HashIndexRequired),
+        }
+    }};
+}
+
+/// Emits a `ReceiptRecorded`, preceded by its compact `sol_log_data` form
+/// (`ReceiptRecorded::to_compact_bytes`) when `cfg.compact_receipt_logs` is
+/// set. Needs `cfg` and `event_counter`.
+macro_rules! emit_receipt_recorded {
+    ($ctx:ident, $event:expr) => {{
+        let event: ReceiptRecorded = $event;
+        if $ctx.accounts.cfg.compact_receipt_logs {
+            anchor_lang::solana_program::log::sol_log_data(&[&event.to_compact_bytes()]);
+        }
+        emit_event!($ctx, event);
+    }};
+}
+
+/// Enforces `cfg.config_change_cooldown_slots` between config-mutating admin
+/// instructions: emits `ConfigChangeThrottled` and fails while the cooldown
+/// runs, else stamps `last_config_change_slot`. Needs `cfg` (mut) and
+/// `event_counter` accounts.
+macro_rules! throttle_config_change {
+    ($ctx:ident) => {{
+        let slot = Clock::get()?.slot;
+        if let Some(next_allowed_slot) = $ctx.accounts.cfg.config_change_blocked_until(slot) {
+            emit_event!($ctx, ConfigChangeThrottled {
+                last_change_slot: $ctx.accounts.cfg.last_config_change_slot,
+                next_allowed_slot,
+                slot,
+                event_seq: $ctx.accounts.event_counter.next()?,
+            });
+            return err!(ZkError::ConfigChangeCooldown);
+        }
+        $ctx.accounts.cfg.last_config_change_slot = slot;
+    }};
+}
+
+/// Gates a timelocked admin setter while `cfg.timelock_seconds > 0`: the
+/// optional `pending_action` must hold `admin_action_hash(name, args)`,
+/// queued by `$signer` and past its `effective_at`. It is then closed to
+/// `$signer`, emitting `ActionApplied`. Needs `cfg`, `event_counter` and
+/// `pending_action`.
+macro_rules! timelock_gate {
+    ($ctx:ident, $signer:ident, $name:literal, $args:expr) => {{
+        if $ctx.accounts.cfg.timelock_seconds > 0 {
+            let action_hash = admin_action_hash($name, &$args)?;
+            let pending = $ctx
+                .accounts
+                .pending_action
+                .as_ref()
+                .ok_or(error!(ZkError::TimelockActive))?;
+            require!(pending.action_hash == action_hash, ZkError::PendingActionMismatch);
+            require_keys_eq!(
+                pending.queued_by,
+                $ctx.accounts.$signer.key(),
+                ZkError::PendingActionMismatch
+            );
+            let effective_at = pending.effective_at;
+            require!(
+                Clock::get()?.unix_timestamp >= effective_at,
+                ZkError::TimelockNotElapsed
+            );
+            pending.close($ctx.accounts.$signer.to_account_info())?;
+            emit_event!($ctx, ActionApplied {
+                action_hash,
+                effective_at,
+                event_seq: $ctx.accounts.event_counter.next()?,
+            });
+        }
+    }};
+}
+
+/// Rejects a post while `cfg.maintenance_until_slot` is ahead of the current
+/// slot, emitting `PostRejectedMaintenance`. Needs `cfg` and `event_counter`.
+macro_rules! reject_during_maintenance {
+    ($ctx:ident) => {{
+        let slot = Clock::get()?.slot;
+        let until_slot = $ctx.accounts.cfg.maintenance_until_slot;
+        if slot < until_slot {
+            emit_event!($ctx, PostRejectedMaintenance {
+                slot,
+                until_slot,
+                payer: $ctx.accounts.payer.key(),
+                event_seq: $ctx.accounts.event_counter.next()?,
+            });
+            return err!(ZkError::MaintenanceWindow);
+        }
+    }};
+}
+
+/// Draws `$amount` from the emitter's SpendBudget while
+/// `cfg.budget_per_window` is set, emitting `SpendBudgetDrawn`; fails with
+/// `BudgetExceeded` once the window's cap is spent. Needs `cfg`,
+/// `event_counter` and `spend_budget`.
+macro_rules! draw_spend_budget {
+    ($ctx:ident, $amount:expr) => {{
+        let amount: u64 = $amount;
+        if $ctx.accounts.cfg.budget_per_window > 0 {
+            let cap = $ctx.accounts.cfg.budget_per_window;
+            let window = $ctx.accounts.cfg.budget_window_seconds;
+            let bump = $ctx.bumps.spend_budget;
+            let budget = $ctx
+                .accounts
+                .spend_budget
+                .as_mut()
+                .ok_or(error!(ZkError::SpendBudgetRequired))?;
+            budget.bump = bump.ok_or(error!(ZkError::SpendBudgetRequired))?;
+            let remaining = budget.draw(cap, window, amount, Clock::get()?.unix_timestamp)?;
+            emit_event!($ctx, SpendBudgetDrawn {
+                spent: budget.spent,
+                remaining,
+                window_start: budget.window_start,
+                event_seq: $ctx.accounts.event_counter.next()?,
+            });
+        }
+    }};
+}
+
 #[program]
 pub mod zk_coprocessor_program {
     use super::*;
 
     /// Posts a message to Wormhole Core and pays the bridge fee.
-    pub fn post_wormhole_message(
-        ctx: Context<PostWormholeMessage>,
+    ///
+    /// `finality_flag` `None` uses `cfg.default_finality` (see
+    /// `set_default_finality`), as in every post path that takes the flag.
+    ///
+    /// A `destination` is prepended as the first 32 payload bytes, or with
+    /// `cfg.enforce_framing` as the first 32 bytes of the frame body; with
+    /// `dest_is_evm` it must be a left-padded 20-byte address.
+    ///
+    /// `message` is either a fresh signing keypair or the program-signed PDA
+    /// `[MESSAGE_SEED, next_sequence_le]`; `cfg.require_pda_message` allows
+    /// only the PDA.
+    ///
+    /// `waive_protocol_fee` skips the protocol fee for payers in
+    /// `cfg.allowed_waivers`; the bridge fee is always paid.
+    ///
+    /// `emitter_role` picks the emitter: `EMITTER_ROLE_PRIMARY` (`[b"emitter"]`)
+    /// or `EMITTER_ROLE_SHADOW` (`[SHADOW_EMITTER_SEED]`) for canary posts,
+    /// which run on their own Core Bridge sequence. Shadow message PDAs use
+    /// `SHADOW_MESSAGE_SEED`, and shadow posts take no batch marker.
+    ///
+    /// `expected_fee` is the bridge fee the caller read when building the
+    /// transaction; the post fails with `FeeAboveSlippage` if the on-chain fee
+    /// exceeds it by more than `cfg.fee_slippage`. The on-chain fee is paid
+    /// either way and reported as `MessagePosted.bridge_fee`.
+    ///
+    /// `payload_hash` skips hashing large payloads on-chain: it is emitted
+    /// as-is as `MessagePosted.payload_hash`, flagged `payload_hash_asserted`.
+    /// It is client-asserted and not checked against the payload (the payload
+    /// as posted, destination included), so consumers that rely on the hash
+    /// must recompute it from the VAA. `None` computes it as usual.
+    pub fn post_wormhole_message<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostWormholeMessage<'info>>,
         batch_id: u32,
         payload: Vec<u8>,
-        finality_flag: u8,
+        finality_flag: Option<u8>,
+        destination: Option<[u8; 32]>,
+        dest_is_evm: bool,
+        waive_protocol_fee: bool,
+        emitter_role: u8,
+        expected_fee: Option<u64>,
+        payload_hash: Option<[u8; 32]>,
     ) -> Result<()> {
-        let fin = if finality_flag == 0 {
-            wormhole::types::Finality::Confirmed
-        } else {
-            wormhole::types::Finality::Finalized
-        };
-
-        require_keys_eq!(
-            *ctx.accounts.config.owner,
-            ctx.accounts.wormhole_program.key(),
-            ZkError::ConfigOwnerMismatch
+        let finality_flag = ctx.accounts.cfg.resolve_finality(finality_flag)?;
+        require!(!ctx.accounts.cfg.posting_paused, ZkError::PostingPaused);
+        reject_during_maintenance!(ctx);
+        advance_outbound_cursor(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_cursor.as_mut(),
+            ctx.bumps.outbound_cursor,
+            batch_id,
+        )?;
+        check_post_interval(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_rate.as_mut(),
+            ctx.bumps.outbound_rate,
+            ctx.accounts.clock.slot,
+        )?;
+        require!(emitter_role <= EMITTER_ROLE_SHADOW, ZkError::UnknownEmitterRole);
+        let shadow = emitter_role == EMITTER_ROLE_SHADOW;
+        require!(!shadow || ctx.accounts.batch_marker.is_none(), ZkError::ShadowBatchMarker);
+        require!(
+            shadow || ctx.accounts.cfg.max_in_flight == 0 || ctx.accounts.batch_marker.is_some(),
+            ZkError::BatchMarkerRequired
         );
+        if let Some(marker) = ctx.accounts.batch_marker.as_ref() {
+            check_marker_reusable(marker)?;
+        }
+        require!(ctx.accounts.expected_digest.is_none(), ZkError::ExpectedDigestRequired);
+        check_framing(&ctx.accounts.cfg, &payload)?;
+        let payload = prepend_destination(
+            payload,
+            destination,
+            dest_is_evm,
+            ctx.accounts.cfg.enforce_framing,
+        )?;
+        let fin = finality_from_flag(finality_flag)?;
 
-        let fee: u64 = {
-            let data_ref = ctx.accounts.config.try_borrow_data()?;
-            let mut data_slice: &[u8] = &*data_ref;
-            let bridge_data = wormhole::accounts::BridgeData::try_deserialize(&mut data_slice)
-                .map_err(|_| error!(ZkError::BridgeDeserialize))?;
-            bridge_data.fee()
-        };
+        let min_len = ctx.accounts.cfg.finalized_min_payload_len;
+        if finality_flag != 0 && min_len > 0 {
+            require!(
+                payload.len() >= min_len as usize,
+                ZkError::PayloadTooSmallForFinalized
+            );
+        }
 
-        if fee > 0 {
-            let ix = anchor_lang::solana_program::system_instruction::transfer(
-                &ctx.accounts.payer.key(),
-                &ctx.accounts.fee_collector.key(),
-                fee,
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let core = CorePdas::derive(wormhole_program, ctx.accounts.cfg.strict_fee_collector);
+        check_fee_collector(&ctx.accounts.fee_collector, &core)?;
+        check_bridge_accounts(&ctx.accounts.config, &ctx.accounts.fee_collector, &core)?;
+        check_poster(&ctx.accounts.poster_config, &ctx.accounts.payer.key())?;
+        check_guardian_set_expiry(
+            &ctx.accounts.cfg,
+            &ctx.accounts.config,
+            ctx.accounts.guardian_set.as_ref().map(|g| g.as_ref()),
+            &core,
+        )?;
+        check_sequence_for_emitter(
+            &ctx.accounts.sequence,
+            &ctx.accounts.emitter,
+            &wormhole_program,
+        )?;
+        if !shadow {
+            check_cached_sequence(&ctx.accounts.cfg, &ctx.accounts.sequence)?;
+        }
+
+        check_fresh_message(&ctx.accounts.message)?;
+        let next_sequence =
+            read_sequence_tracker(&ctx.accounts.sequence, &wormhole_program)?.unwrap_or(0);
+        let next_sequence_le = next_sequence.to_le_bytes();
+        let message_seed = if shadow { SHADOW_MESSAGE_SEED } else { MESSAGE_SEED };
+        let (message_pda, message_bump) = derive_message_pda(shadow, next_sequence);
+        let pda_message = ctx.accounts.message.key() == message_pda;
+        if !pda_message {
+            require!(!ctx.accounts.cfg.require_pda_message, ZkError::PdaMessageRequired);
+            require!(ctx.accounts.message.is_signer, ZkError::MessageNotSigner);
+        }
+
+        let fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        check_fee_slippage(&ctx.accounts.cfg, expected_fee, fee)?;
+        if ctx.accounts.cfg.verbose {
+            msg!(
+                "post: batch_id={} finality={} payload_len={} bridge_fee={}",
+                batch_id,
+                finality_flag,
+                payload.len(),
+                fee
+            );
+            msg!(
+                "post: payer={} message={} sequence={} fee_collector={}",
+                ctx.accounts.payer.key(),
+                ctx.accounts.message.key(),
+                ctx.accounts.sequence.key(),
+                ctx.accounts.fee_collector.key()
             );
-            anchor_lang::solana_program::program::invoke(
-                &ix,
-                &[
-                    ctx.accounts.payer.to_account_info(),
-                    ctx.accounts.fee_collector.to_account_info(),
-                ],
-            )?;
         }
+        pay_bridge_fee(
+            &ctx.accounts.cfg.fee_asset,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.fee_collector.to_account_info(),
+            ctx.remaining_accounts,
+            fee,
+        )?;
+
+        let protocol_fee = if waive_protocol_fee {
+            require!(
+                ctx.accounts.cfg.waiver_allowed(&ctx.accounts.payer.key()),
+                ZkError::WaiverNotAllowed
+            );
+            0
+        } else {
+            let treasury = ctx.accounts.treasury.as_ref().map(|t| t.to_account_info());
+            charge_protocol_fee(
+                &ctx.accounts.cfg,
+                finality_flag,
+                &ctx.accounts.payer.to_account_info(),
+                treasury.as_ref(),
+            )?
+        };
+        emit_event!(ctx, ProtocolFeeApplied {
+            payer: ctx.accounts.payer.key(),
+            tier: finality_flag,
+            amount: protocol_fee,
+            waived: waive_protocol_fee,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+
+        draw_spend_budget!(ctx, fee.saturating_add(protocol_fee));
 
         let cpi_accounts = wormhole::instructions::PostMessage {
             config:         ctx.accounts.config.to_account_info(),
@@ -67,220 +392,9445 @@ pub mod zk_coprocessor_program {
             system_program: ctx.accounts.system_program.to_account_info(),
         };
 
+        if let Some(marker) = ctx.accounts.batch_marker.as_mut() {
+            ctx.accounts.cfg.note_post_in_flight()?;
+            marker.batch_id = batch_id;
+            marker.sequence =
+                read_sequence_tracker(&ctx.accounts.sequence, &wormhole_program)?.unwrap_or(0);
+            marker.posted_slot = ctx.accounts.clock.slot;
+            marker.round_tripped = false;
+            marker.bump = ctx.bumps.batch_marker.ok_or(error!(ZkError::BatchMarkerMismatch))?;
+        }
+
+        if ctx.accounts.cfg.track_attempts {
+            let slot = ctx.accounts.clock.slot;
+            let bump = ctx.bumps.attempt_counter;
+            let counter = ctx
+                .accounts
+                .attempt_counter
+                .as_mut()
+                .ok_or(error!(ZkError::AttemptCounterRequired))?;
+            counter.attempts = counter.attempts.saturating_add(1);
+            counter.last_attempt_slot = slot;
+            counter.bump = bump.ok_or(error!(ZkError::AttemptCounterRequired))?;
+            emit_event!(ctx, PostAttempted {
+                batch_id,
+                attempt: counter.attempts,
+                slot,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+        }
+
+        let payload_hash_asserted = payload_hash.is_some();
+        let payload_hash =
+            payload_hash.unwrap_or_else(|| post_payload_hash(&ctx.accounts.cfg, &payload));
+
         let bump = ctx.bumps.emitter;
         let bump_arr = [bump];
-        let emitter_seeds: [&[u8]; 2] = [b"emitter", &bump_arr];
-        let signer_seeds: [&[&[u8]]; 1] = [&emitter_seeds];
+        let emitter_seeds: [&[u8]; 2] = [emitter_seed(emitter_role), &bump_arr];
+        let message_bump_arr = [message_bump];
+        let message_seeds: [&[u8]; 3] = [message_seed, &next_sequence_le, &message_bump_arr];
+        let all_seeds: [&[&[u8]]; 2] = [&emitter_seeds, &message_seeds];
+        let signer_seeds = if pda_message { &all_seeds[..] } else { &all_seeds[..1] };
 
         let cpi_ctx = CpiContext::new_with_signer(
             ctx.accounts.wormhole_program.to_account_info(),
             cpi_accounts,
-            &signer_seeds,
+            signer_seeds,
         );
 
-        wormhole::instructions::post_message(cpi_ctx, batch_id, payload, fin)
+        wormhole::instructions::post_message(cpi_ctx, batch_id, payload, fin)?;
+
+        let emitter = ctx.accounts.emitter.key().to_bytes();
+        let sequence = posted_sequence(&ctx.accounts.sequence, &wormhole_program)?;
+        emit_event!(ctx, MessagePosted {
+            emitter,
+            sequence,
+            batch_id,
+            payload_hash,
+            payload_hash_asserted,
+            finality: finality_flag,
+            destination,
+            emitter_role,
+            min_post_interval_slots: ctx.accounts.cfg.min_post_interval_slots,
+            bridge_fee: fee,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        emit_event!(ctx, SequenceAdvanced {
+            emitter,
+            new_sequence: sequence,
+            slot: ctx.accounts.clock.slot,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
     }
 
-    /// Initializes receipt config.
-    pub fn init_receipt_config(
-        ctx: Context<InitReceiptConfig>,
-        evm_chain: u16,
-        emitter: [u8; 32],
+    /// `post_wormhole_message` that also registers, in the same instruction,
+    /// the `ExpectedDigest` for `batch_id`: the payload as posted (destination
+    /// included) hashed like receipt payloads (`cfg.hash_algo` past
+    /// `cfg.hash_prefix_skip`). `record_receipt_from_vaa` given that account
+    /// then rejects a round trip whose payload differs from what was sent.
+    /// The payer must be the admin or operator, as for
+    /// `register_expected_digest`. The payload is hashed on chain here, so a
+    /// client-asserted `payload_hash` fails with `PayloadHashAssertionRejected`.
+    pub fn post_and_register_expected<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostWormholeMessage<'info>>,
+        batch_id: u32,
+        payload: Vec<u8>,
+        finality_flag: Option<u8>,
+        destination: Option<[u8; 32]>,
+        dest_is_evm: bool,
+        waive_protocol_fee: bool,
+        emitter_role: u8,
+        expected_fee: Option<u64>,
+        payload_hash: Option<[u8; 32]>,
     ) -> Result<()> {
-        let cfg = &mut ctx.accounts.cfg;
-        cfg.admin = ctx.accounts.admin.key();
-        cfg.evm_chain = evm_chain;
-        cfg.emitter = emitter;
-        cfg.bump = ctx.bumps.cfg;
-        Ok(())
+        require!(payload_hash.is_none(), ZkError::PayloadHashAssertionRejected);
+        let cfg = &ctx.accounts.cfg;
+        let posted =
+            prepend_destination(payload.clone(), destination, dest_is_evm, cfg.enforce_framing)?;
+        let digest = vaa::hash_payload(&posted, cfg.hash_algo, cfg.hash_prefix_skip)?;
+        let signer = ctx.accounts.payer.key();
+        require!(
+            signer == cfg.admin || (cfg.operator != Pubkey::default() && signer == cfg.operator),
+            ZkError::NotOperator
+        );
+        let bump = ctx.bumps.expected_digest.ok_or(error!(ZkError::ExpectedDigestRequired))?;
+        // Taken out of the accounts, and written here, so the post below
+        // sees none and `post_wormhole_message` can reject any it is given.
+        let mut expected = ctx
+            .accounts
+            .expected_digest
+            .take()
+            .ok_or(error!(ZkError::ExpectedDigestRequired))?;
+        expected.batch_id = batch_id;
+        expected.digest = digest;
+        expected.registered_by = signer;
+        expected.bump = bump;
+        expected.exit(ctx.program_id)?;
+
+        post_wormhole_message(
+            ctx,
+            batch_id,
+            payload,
+            finality_flag,
+            destination,
+            dest_is_evm,
+            waive_protocol_fee,
+            emitter_role,
+            expected_fee,
+            payload_hash,
+        )
     }
 
-    /// Records a receipt from a PostedVAA.
-    pub fn record_receipt_from_vaa(
-        ctx: Context<RecordReceiptFromVaa>,
-        emitter: [u8; 32],
-        sequence: u64,
+    /// Posts `payloads` in order at `finality_flag`, one message PDA
+    /// `[MESSAGE_SEED, sequence_le]` per post in `remaining_accounts` (after the
+    /// SPL fee accounts when `fee_asset` is SPL). Returns the number posted
+    /// (u32 LE).
+    ///
+    /// With `best_effort`, posting stops once the payer can no longer cover the
+    /// next post's lamport costs (SOL bridge fee, protocol fee, message rent)
+    /// and the posted prefix is kept; otherwise the batch is all-or-nothing.
+    /// Batch markers, attempt tracking and destinations are not supported here.
+    /// The batch counts as one post for `cfg.min_post_interval_slots`.
+    pub fn post_messages_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostMessagesBatch<'info>>,
+        batch_id: u32,
+        payloads: Vec<Vec<u8>>,
+        finality_flag: Option<u8>,
+        best_effort: bool,
     ) -> Result<()> {
-        require_keys_eq!(
-            *ctx.accounts.posted_vaa.owner,
-            ctx.accounts.wormhole_program.key(),
-            ZkError::InvalidPostedVaaOwner
+        let finality_flag = ctx.accounts.cfg.resolve_finality(finality_flag)?;
+        require!(!ctx.accounts.cfg.posting_paused, ZkError::PostingPaused);
+        require!(ctx.accounts.cfg.max_in_flight == 0, ZkError::BatchMarkerRequired);
+        reject_during_maintenance!(ctx);
+        advance_outbound_cursor(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_cursor.as_mut(),
+            ctx.bumps.outbound_cursor,
+            batch_id,
+        )?;
+        check_post_interval(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_rate.as_mut(),
+            ctx.bumps.outbound_rate,
+            ctx.accounts.clock.slot,
+        )?;
+        require!(!payloads.is_empty(), ZkError::NoMessagesProvided);
+        require!(
+            payloads.len() <= ctx.accounts.cfg.max_batch_size as usize,
+            ZkError::BatchTooLarge
         );
+        for payload in &payloads {
+            check_framing(&ctx.accounts.cfg, payload)?;
+        }
+        let fin = finality_from_flag(finality_flag)?;
+        let min_len = ctx.accounts.cfg.finalized_min_payload_len;
+        if finality_flag != 0 && min_len > 0 {
+            require!(
+                payloads.iter().all(|p| p.len() >= min_len as usize),
+                ZkError::PayloadTooSmallForFinalized
+            );
+        }
 
-        let cfg = &ctx.accounts.cfg;
-        require!(emitter == cfg.emitter, ZkError::EmitterAddressMismatch);
+        let fee_accounts = match ctx.accounts.cfg.fee_asset {
+            FeeAsset::Sol => 0,
+            FeeAsset::Spl { .. } => 3,
+        };
+        require!(
+            ctx.remaining_accounts.len() == fee_accounts + payloads.len(),
+            ZkError::BatchAccountsMismatch
+        );
+        let (fee_remaining, messages) = ctx.remaining_accounts.split_at(fee_accounts);
 
-        let receipt = &mut ctx.accounts.receipt;
-        receipt.emitter = emitter;
-        receipt.sequence = sequence;
-        receipt.vaa_account = ctx.accounts.posted_vaa.key();
-        receipt.posted_timestamp = Clock::get()?.unix_timestamp;
-        receipt.bump = ctx.bumps.receipt;
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let core = CorePdas::derive(wormhole_program, ctx.accounts.cfg.strict_fee_collector);
+        check_fee_collector(&ctx.accounts.fee_collector, &core)?;
+        check_bridge_accounts(&ctx.accounts.config, &ctx.accounts.fee_collector, &core)?;
+        check_poster(&ctx.accounts.poster_config, &ctx.accounts.payer.key())?;
+        check_guardian_set_expiry(
+            &ctx.accounts.cfg,
+            &ctx.accounts.config,
+            ctx.accounts.guardian_set.as_ref().map(|g| g.as_ref()),
+            &core,
+        )?;
+        check_sequence_for_emitter(
+            &ctx.accounts.sequence,
+            &ctx.accounts.emitter,
+            &wormhole_program,
+        )?;
+        check_cached_sequence(&ctx.accounts.cfg, &ctx.accounts.sequence)?;
 
-        emit!(ReceiptRecorded {
-            emitter,
-            sequence,
-            vaa: receipt.vaa_account,
-        });
+        let fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        let protocol_fee = ctx.accounts.cfg.protocol_fee_for(finality_flag);
+        let sol_fee = match ctx.accounts.cfg.fee_asset {
+            FeeAsset::Sol => fee,
+            FeeAsset::Spl { .. } => 0,
+        };
+        let rent = Rent::get()?;
+        let payer_reserve = rent.minimum_balance(0);
+        let treasury = ctx.accounts.treasury.as_ref().map(|t| t.to_account_info());
+        let first_sequence =
+            read_sequence_tracker(&ctx.accounts.sequence, &wormhole_program)?.unwrap_or(0);
+
+        let bump_arr = [ctx.bumps.emitter];
+        let emitter_seeds: [&[u8]; 2] = [b"emitter", &bump_arr];
+
+        let mut posted = 0u32;
+        for (i, (payload, message)) in payloads.into_iter().zip(messages).enumerate() {
+            let sequence = first_sequence + i as u64;
+            let sequence_le = sequence.to_le_bytes();
+            let (expected, message_bump) = derive_message_pda(false, sequence);
+            require_keys_eq!(message.key(), expected, ZkError::MessagePdaMismatch);
+            check_fresh_message(message)?;
+
+            if best_effort {
+                let needed = sol_fee
+                    .saturating_add(protocol_fee)
+                    .saturating_add(rent.minimum_balance(POSTED_MESSAGE_HEADER_LEN + payload.len()))
+                    .saturating_add(payer_reserve);
+                if ctx.accounts.payer.lamports() < needed {
+                    msg!("best effort: stopping after {} posts", posted);
+                    break;
+                }
+            }
+
+            pay_bridge_fee(
+                &ctx.accounts.cfg.fee_asset,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.fee_collector.to_account_info(),
+                fee_remaining,
+                fee,
+            )?;
+            charge_protocol_fee(
+                &ctx.accounts.cfg,
+                finality_flag,
+                &ctx.accounts.payer.to_account_info(),
+                treasury.as_ref(),
+            )?;
+            emit_event!(ctx, ProtocolFeeApplied {
+                payer: ctx.accounts.payer.key(),
+                tier: finality_flag,
+                amount: protocol_fee,
+                waived: false,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+
+            draw_spend_budget!(ctx, fee.saturating_add(protocol_fee));
+
+            let payload_hash = post_payload_hash(&ctx.accounts.cfg, &payload);
+            let message_bump_arr = [message_bump];
+            let message_seeds: [&[u8]; 3] = [MESSAGE_SEED, &sequence_le, &message_bump_arr];
+            let signer_seeds: [&[&[u8]]; 2] = [&emitter_seeds, &message_seeds];
+            let cpi_accounts = wormhole::instructions::PostMessage {
+                config:         ctx.accounts.config.to_account_info(),
+                message:        message.clone(),
+                emitter:        ctx.accounts.emitter.to_account_info(),
+                sequence:       ctx.accounts.sequence.to_account_info(),
+                payer:          ctx.accounts.payer.to_account_info(),
+                fee_collector:  ctx.accounts.fee_collector.to_account_info(),
+                clock:          ctx.accounts.clock.to_account_info(),
+                rent:           ctx.accounts.rent.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.wormhole_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            );
+            wormhole::instructions::post_message(cpi_ctx, batch_id, payload, fin)?;
 
+            emit_event!(ctx, MessagePosted {
+                emitter: ctx.accounts.emitter.key().to_bytes(),
+                sequence,
+                batch_id,
+                payload_hash,
+                payload_hash_asserted: false,
+                finality: finality_flag,
+                destination: None,
+                emitter_role: EMITTER_ROLE_PRIMARY,
+                min_post_interval_slots: ctx.accounts.cfg.min_post_interval_slots,
+                bridge_fee: fee,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+            posted += 1;
+        }
+
+        if posted > 0 {
+            emit_event!(ctx, SequenceAdvanced {
+                emitter: ctx.accounts.emitter.key().to_bytes(),
+                new_sequence: posted_sequence(&ctx.accounts.sequence, &wormhole_program)?,
+                slot: ctx.accounts.clock.slot,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+        }
+        set_return_bytes(&posted.to_le_bytes())?;
         Ok(())
     }
 
-    /// Records a receipt without VAA (admin only).
-    pub fn record_receipt_direct(
-        ctx: Context<RecordReceiptDirect>,
-        emitter: [u8; 32],
-        sequence: u64,
+    /// Posts `payload` twice in one transaction, Confirmed then Finalized, each
+    /// with its own fresh message account, paying the bridge and protocol fee
+    /// per post. Emits `DualPosted` with both sequences. Batch markers and
+    /// attempt tracking are not supported here. The pair counts as one post
+    /// for `cfg.min_post_interval_slots`.
+    pub fn post_dual_finality<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostDualFinality<'info>>,
+        batch_id: u32,
+        payload: Vec<u8>,
     ) -> Result<()> {
-        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        require!(!ctx.accounts.cfg.posting_paused, ZkError::PostingPaused);
+        require!(ctx.accounts.cfg.max_in_flight == 0, ZkError::BatchMarkerRequired);
+        reject_during_maintenance!(ctx);
+        advance_outbound_cursor(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_cursor.as_mut(),
+            ctx.bumps.outbound_cursor,
+            batch_id,
+        )?;
+        check_post_interval(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_rate.as_mut(),
+            ctx.bumps.outbound_rate,
+            ctx.accounts.clock.slot,
+        )?;
+        require!(!ctx.accounts.cfg.require_pda_message, ZkError::PdaMessageRequired);
+        check_framing(&ctx.accounts.cfg, &payload)?;
+        let min_len = ctx.accounts.cfg.finalized_min_payload_len;
+        if min_len > 0 {
+            require!(
+                payload.len() >= min_len as usize,
+                ZkError::PayloadTooSmallForFinalized
+            );
+        }
 
-        let r = &mut ctx.accounts.receipt;
-        r.emitter = emitter;
-        r.sequence = sequence;
-        r.vaa_account = Pubkey::default();
-        r.posted_timestamp = Clock::get()?.unix_timestamp;
-        r.bump = ctx.bumps.receipt;
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let core = CorePdas::derive(wormhole_program, ctx.accounts.cfg.strict_fee_collector);
+        check_fee_collector(&ctx.accounts.fee_collector, &core)?;
+        check_bridge_accounts(&ctx.accounts.config, &ctx.accounts.fee_collector, &core)?;
+        check_poster(&ctx.accounts.poster_config, &ctx.accounts.payer.key())?;
+        check_guardian_set_expiry(
+            &ctx.accounts.cfg,
+            &ctx.accounts.config,
+            ctx.accounts.guardian_set.as_ref().map(|g| g.as_ref()),
+            &core,
+        )?;
+        check_sequence_for_emitter(
+            &ctx.accounts.sequence,
+            &ctx.accounts.emitter,
+            &wormhole_program,
+        )?;
+        check_cached_sequence(&ctx.accounts.cfg, &ctx.accounts.sequence)?;
 
-        emit!(ReceiptRecorded {
+        require_keys_neq!(
+            ctx.accounts.message_confirmed.key(),
+            ctx.accounts.message_finalized.key(),
+            ZkError::DuplicateMessageAccount
+        );
+        check_fresh_message(&ctx.accounts.message_confirmed)?;
+        check_fresh_message(&ctx.accounts.message_finalized)?;
+
+        let fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        let treasury = ctx.accounts.treasury.as_ref().map(|t| t.to_account_info());
+        let mut total_fee = 0u64;
+        for finality_flag in [0u8, 1u8] {
+            pay_bridge_fee(
+                &ctx.accounts.cfg.fee_asset,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.fee_collector.to_account_info(),
+                ctx.remaining_accounts,
+                fee,
+            )?;
+            let protocol_fee = charge_protocol_fee(
+                &ctx.accounts.cfg,
+                finality_flag,
+                &ctx.accounts.payer.to_account_info(),
+                treasury.as_ref(),
+            )?;
+            emit_event!(ctx, ProtocolFeeApplied {
+                payer: ctx.accounts.payer.key(),
+                tier: finality_flag,
+                amount: protocol_fee,
+                waived: false,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+            total_fee = total_fee.saturating_add(fee).saturating_add(protocol_fee);
+        }
+
+        draw_spend_budget!(ctx, total_fee);
+
+        let payload_hash = post_payload_hash(&ctx.accounts.cfg, &payload);
+
+        let bump_arr = [ctx.bumps.emitter];
+        let emitter_seeds: [&[u8]; 2] = [b"emitter", &bump_arr];
+        let signer_seeds: [&[&[u8]]; 1] = [&emitter_seeds];
+
+        let posts = [
+            (ctx.accounts.message_confirmed.to_account_info(), wormhole::types::Finality::Confirmed),
+            (ctx.accounts.message_finalized.to_account_info(), wormhole::types::Finality::Finalized),
+        ];
+        for (message, fin) in posts {
+            let cpi_accounts = wormhole::instructions::PostMessage {
+                config:         ctx.accounts.config.to_account_info(),
+                message,
+                emitter:        ctx.accounts.emitter.to_account_info(),
+                sequence:       ctx.accounts.sequence.to_account_info(),
+                payer:          ctx.accounts.payer.to_account_info(),
+                fee_collector:  ctx.accounts.fee_collector.to_account_info(),
+                clock:          ctx.accounts.clock.to_account_info(),
+                rent:           ctx.accounts.rent.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.wormhole_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            );
+            wormhole::instructions::post_message(cpi_ctx, batch_id, payload.clone(), fin)?;
+        }
+
+        let emitter = ctx.accounts.emitter.key().to_bytes();
+        let finalized_sequence = posted_sequence(&ctx.accounts.sequence, &wormhole_program)?;
+        emit_event!(ctx, DualPosted {
             emitter,
-            sequence,
-            vaa: r.vaa_account,
+            batch_id,
+            confirmed_sequence: finalized_sequence.saturating_sub(1),
+            finalized_sequence,
+            payload_hash,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        emit_event!(ctx, SequenceAdvanced {
+            emitter,
+            new_sequence: finalized_sequence,
+            slot: ctx.accounts.clock.slot,
+            event_seq: ctx.accounts.event_counter.next()?,
         });
-
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct PostWormholeMessage<'info> {
-    /// CHECK: Points to Wormhole Core Bridge(Config).
-    #[account(mut)]
-    pub config: AccountInfo<'info>,
+    /// Posts via Core Bridge's prepared-message flow (feature `core-bridge-v2`).
+    ///
+    /// Differences from `post_wormhole_message`:
+    /// - `draft_message` replaces the `message` signer: the client pre-allocates it
+    ///   (owned by Core Bridge, sized for the payload, rent-exempt) and it does
+    ///   not sign. Underfunding fails early with `MessageAccountUnderfunded`.
+    /// - The payload is written with `init_message_v1` / `write_message_v1` /
+    ///   `finalize_message_v1`, then published by the legacy `post_message` with an
+    ///   empty payload; the emitter PDA signs every step.
+    /// - Shares v1's fee handling (bridge + protocol fee, spend budget), the
+    ///   fee_collector and poster checks, the Finalized min-length rule and
+    ///   `MessagePosted`; batch markers and attempt tracking are v1-only.
+    #[cfg(feature = "core-bridge-v2")]
+    pub fn post_wormhole_message_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostWormholeMessageV2<'info>>,
+        batch_id: u32,
+        payload: Vec<u8>,
+        finality_flag: Option<u8>,
+        destination: Option<[u8; 32]>,
+        dest_is_evm: bool,
+    ) -> Result<()> {
+        let finality_flag = ctx.accounts.cfg.resolve_finality(finality_flag)?;
+        require!(!ctx.accounts.cfg.posting_paused, ZkError::PostingPaused);
+        require!(ctx.accounts.cfg.max_in_flight == 0, ZkError::BatchMarkerRequired);
+        reject_during_maintenance!(ctx);
+        advance_outbound_cursor(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_cursor.as_mut(),
+            ctx.bumps.outbound_cursor,
+            batch_id,
+        )?;
+        check_post_interval(
+            &ctx.accounts.cfg,
+            ctx.accounts.outbound_rate.as_mut(),
+            ctx.bumps.outbound_rate,
+            ctx.accounts.clock.slot,
+        )?;
+        check_framing(&ctx.accounts.cfg, &payload)?;
+        let payload = prepend_destination(
+            payload,
+            destination,
+            dest_is_evm,
+            ctx.accounts.cfg.enforce_framing,
+        )?;
+        finality_from_flag(finality_flag)?;
 
-    #[account(mut)]
-    pub message: Signer<'info>,
+        let min_len = ctx.accounts.cfg.finalized_min_payload_len;
+        if finality_flag != 0 && min_len > 0 {
+            require!(
+                payload.len() >= min_len as usize,
+                ZkError::PayloadTooSmallForFinalized
+            );
+        }
 
-    /// CHECK: Verified by Wormhole Core.
-    #[account(seeds = [b"emitter"], bump)]
-    pub emitter: AccountInfo<'info>,
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let core = CorePdas::derive(wormhole_program, ctx.accounts.cfg.strict_fee_collector);
+        check_fee_collector(&ctx.accounts.fee_collector, &core)?;
+        check_bridge_accounts(&ctx.accounts.config, &ctx.accounts.fee_collector, &core)?;
+        check_poster(&ctx.accounts.poster_config, &ctx.accounts.payer.key())?;
+        check_guardian_set_expiry(
+            &ctx.accounts.cfg,
+            &ctx.accounts.config,
+            ctx.accounts.guardian_set.as_ref().map(|g| g.as_ref()),
+            &core,
+        )?;
+        check_sequence_for_emitter(
+            &ctx.accounts.sequence,
+            &ctx.accounts.emitter,
+            &wormhole_program,
+        )?;
+        check_cached_sequence(&ctx.accounts.cfg, &ctx.accounts.sequence)?;
+        check_draft_message_rent(&ctx.accounts.draft_message, payload.len())?;
 
-    /// CHECK: Verified by Wormhole Core.
-    #[account(mut)]
-    pub sequence: AccountInfo<'info>,
+        let fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        pay_bridge_fee(
+            &ctx.accounts.cfg.fee_asset,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.fee_collector.to_account_info(),
+            ctx.remaining_accounts,
+            fee,
+        )?;
+        let treasury = ctx.accounts.treasury.as_ref().map(|t| t.to_account_info());
+        let protocol_fee = charge_protocol_fee(
+            &ctx.accounts.cfg,
+            finality_flag,
+            &ctx.accounts.payer.to_account_info(),
+            treasury.as_ref(),
+        )?;
+        emit_event!(ctx, ProtocolFeeApplied {
+            payer: ctx.accounts.payer.key(),
+            tier: finality_flag,
+            amount: protocol_fee,
+            waived: false,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        draw_spend_budget!(ctx, fee.saturating_add(protocol_fee));
 
-    /// CHECK: Derives from Bridge(Config).
-    #[account(mut)]
-    pub fee_collector: AccountInfo<'info>,
+        let bump_arr = [ctx.bumps.emitter];
+        let emitter_seeds: [&[u8]; 2] = [b"emitter", &bump_arr];
+        let signer_seeds: [&[&[u8]]; 1] = [&emitter_seeds];
 
-    pub clock: Sysvar<'info, Clock>,
-    pub rent:  Sysvar<'info, Rent>,
-    pub system_program: Program<'info, System>,
+        let payload_hash = post_payload_hash(&ctx.accounts.cfg, &payload);
+        core_bridge_v2::prepare_and_post(
+            &ctx.accounts.to_account_infos_v2(),
+            &signer_seeds,
+            batch_id,
+            payload,
+            finality_flag,
+        )?;
 
-    pub wormhole_program: Program<'info, Wormhole>,
-}
+        let emitter = ctx.accounts.emitter.key().to_bytes();
+        let sequence = posted_sequence(&ctx.accounts.sequence, &wormhole_program)?;
+        emit_event!(ctx, MessagePosted {
+            emitter,
+            sequence,
+            batch_id,
+            payload_hash,
+            payload_hash_asserted: false,
+            finality: finality_flag,
+            destination,
+            emitter_role: EMITTER_ROLE_PRIMARY,
+            min_post_interval_slots: ctx.accounts.cfg.min_post_interval_slots,
+            bridge_fee: fee,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        emit_event!(ctx, SequenceAdvanced {
+            emitter,
+            new_sequence: sequence,
+            slot: ctx.accounts.clock.slot,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitReceiptConfig<'info> {
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + ReceiptConfig::SIZE,
-        seeds = [b"cfg"],
-        bump
-    )]
-    pub cfg: Account<'info, ReceiptConfig>,
+    /// Initializes receipt config. With `evm_emitters`, the emitter and every
+    /// later allowlist entry must be a left-padded 20-byte EVM address.
+    pub fn init_receipt_config(
+        ctx: Context<InitReceiptConfig>,
+        evm_chain: u16,
+        emitter: [u8; 32],
+        chain_label: [u8; 16],
+        evm_emitters: bool,
+    ) -> Result<()> {
+        init_config(
+            &mut ctx.accounts.cfg,
+            &mut ctx.accounts.event_counter,
+            &mut ctx.accounts.stats,
+            ctx.accounts.admin.key(),
+            (evm_chain, chain_label, evm_emitters),
+            RemoteEmitter(emitter),
+            (ctx.bumps.cfg, ctx.bumps.event_counter, ctx.bumps.stats),
+        )
+    }
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Initializes receipt config for a Solana-origin emitter from its PDA:
+    /// stores the account's pubkey bytes, which is the address Wormhole records.
+    /// EVM emitters keep using `init_receipt_config` with raw bytes.
+    pub fn init_receipt_config_from_pda(
+        ctx: Context<InitReceiptConfigFromPda>,
+        evm_chain: u16,
+        chain_label: [u8; 16],
+    ) -> Result<()> {
+        init_config(
+            &mut ctx.accounts.cfg,
+            &mut ctx.accounts.event_counter,
+            &mut ctx.accounts.stats,
+            ctx.accounts.admin.key(),
+            (evm_chain, chain_label, false),
+            RemoteEmitter(ctx.accounts.emitter_pda.key().to_bytes()),
+            (ctx.bumps.cfg, ctx.bumps.event_counter, ctx.bumps.stats),
+        )
+    }
 
-#[derive(Accounts)]
-#[instruction(emitter: [u8; 32], sequence: u64)]
-pub struct RecordReceiptFromVaa<'info> {
-    #[account(
-        mut,
-        seeds = [b"cfg"],
-        bump = cfg.bump
-    )]
-    pub cfg: Account<'info, ReceiptConfig>,
+    /// Updates receipt config policy fields (admin only).
+    /// Rejected while `timelock_seconds > 0`; use `queue_change` instead.
+    pub fn update_receipt_config(
+        ctx: Context<UpdateReceiptConfig>,
+        update: ReceiptConfigUpdate,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(cfg.timelock_seconds == 0, ZkError::TimelockActive);
+        cfg.apply_update(&update)
+    }
 
-    /// CHECK: Owned by Wormhole Core.
-    pub posted_vaa: UncheckedAccount<'info>,
+    /// Queues a config update that `apply_change` may execute once
+    /// `now + timelock_seconds` has passed (admin only). One pending change at a time.
+    pub fn queue_change(ctx: Context<QueueChange>, update: ReceiptConfigUpdate) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        // Validate now rather than at apply time.
+        let mut preview = ReceiptConfig::clone(cfg);
+        preview.apply_update(&update)?;
 
-    pub wormhole_program: Program<'info, Wormhole>,
+        let effective_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(cfg.timelock_seconds)
+            .ok_or(error!(ZkError::InvalidTimelock))?;
+        let pending = &mut ctx.accounts.pending_change;
+        pending.update = update;
+        pending.effective_at = effective_at;
+        pending.bump = ctx.bumps.pending_change;
 
-    #[account(
-        init_if_needed,
-        payer = payer,
-        space = 8 + Receipt::SIZE,
-        seeds = [b"receipt", emitter.as_ref(), &sequence.to_be_bytes()],
-        bump
-    )]
-    pub receipt: Account<'info, Receipt>,
+        emit_event!(ctx, ChangeQueued {
+            effective_at,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Applies the pending change after its `effective_at` (admin only).
+    pub fn apply_change(ctx: Context<ApplyChange>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        let pending = &ctx.accounts.pending_change;
+        require!(
+            Clock::get()?.unix_timestamp >= pending.effective_at,
+            ZkError::TimelockNotElapsed
+        );
+        throttle_config_change!(ctx);
+        ctx.accounts.cfg.apply_update(&pending.update)?;
 
-#[derive(Accounts)]
-#[instruction(emitter: [u8; 32], sequence: u64)]
-pub struct RecordReceiptDirect<'info> {
-    #[account(
-        mut,
-        seeds = [b"cfg"],
-        bump = cfg.bump
-    )]
-    pub cfg: Account<'info, ReceiptConfig>,
+        emit_event!(ctx, ChangeApplied {
+            effective_at: ctx.accounts.pending_change.effective_at,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
 
-    #[account(
-        init_if_needed,
-        payer = admin,
-        space = 8 + Receipt::SIZE,
-        seeds = [b"receipt", emitter.as_ref(), &sequence.to_be_bytes()],
-        bump
+    /// Discards the pending change (admin only).
+    pub fn cancel_change(ctx: Context<ApplyChange>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        emit_event!(ctx, ChangeCancelled {
+            effective_at: ctx.accounts.pending_change.effective_at,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Queues the admin call hashing to `action_hash` (`admin_action_hash`),
+    /// executable once `now + timelock_seconds` has passed (admin, or the fee
+    /// authority for its setters). While `timelock_seconds > 0`, every admin
+    /// setter other than `update_receipt_config` takes the queued
+    /// PendingAction and consumes it; only its queuer may.
+    pub fn queue_action(ctx: Context<QueueAction>, action_hash: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let authority = ctx.accounts.authority.key();
+        require!(
+            authority == cfg.admin || authority == cfg.effective_fee_authority(),
+            ZkError::NotAdmin
+        );
+        let effective_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(cfg.timelock_seconds)
+            .ok_or(error!(ZkError::InvalidTimelock))?;
+        let pending = &mut ctx.accounts.pending_action;
+        pending.action_hash = action_hash;
+        pending.queued_by = authority;
+        pending.effective_at = effective_at;
+        pending.bump = ctx.bumps.pending_action;
+
+        emit_event!(ctx, ActionQueued {
+            action_hash,
+            queued_by: authority,
+            effective_at,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Discards a queued action (its queuer or the admin).
+    pub fn cancel_action(ctx: Context<CancelAction>, action_hash: [u8; 32]) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        require!(
+            authority == ctx.accounts.pending_action.queued_by || authority == ctx.accounts.cfg.admin,
+            ZkError::NotAdmin
+        );
+        emit_event!(ctx, ActionCancelled {
+            action_hash,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Sets the keeper allowed to close old receipts (admin only).
+    pub fn set_reaper(ctx: Context<UpdateReceiptConfig>, reaper: Pubkey) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_reaper", reaper);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        cfg.receipt_reaper = reaper;
+        Ok(())
+    }
+
+    /// Sets the close-only authority (admin only); `Pubkey::default()` disables it.
+    pub fn set_close_authority(
+        ctx: Context<UpdateReceiptConfig>,
+        close_authority: Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_close_authority", close_authority);
+        throttle_config_change!(ctx);
+        ctx.accounts.cfg.close_authority = close_authority;
+        Ok(())
+    }
+
+    /// Sets the finality flag used by posts that omit theirs (admin only);
+    /// `None` unsets it, so such posts fail with `NoDefaultFinality`.
+    pub fn set_default_finality(
+        ctx: Context<UpdateReceiptConfig>,
+        default_finality: Option<u8>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_default_finality", default_finality);
+        throttle_config_change!(ctx);
+        if let Some(flag) = default_finality {
+            finality_from_flag(flag)?;
+        }
+        let cfg = &mut ctx.accounts.cfg;
+        cfg.has_default_finality = default_finality.is_some();
+        cfg.default_finality = default_finality.unwrap_or(0);
+        Ok(())
+    }
+
+    /// Sets the off-chain verifier allowed to record external receipts (admin only).
+    pub fn set_operator(ctx: Context<UpdateReceiptConfig>, operator: Pubkey) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_operator", operator);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        cfg.operator = operator;
+        Ok(())
+    }
+
+    /// Replaces the remote emitter allowlist (admin only). `emitters[0]` becomes
+    /// `cfg.emitter`; the rest go to `extra_emitters`. Emitters not already
+    /// listed become active after `emitter_activation_delay_slots`, each
+    /// announced by `EmitterScheduled`.
+    pub fn set_allowed_emitters(
+        ctx: Context<UpdateReceiptConfig>,
+        emitters: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_allowed_emitters", &emitters);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(!emitters.is_empty(), ZkError::NoEmittersProvided);
+        require!(
+            emitters.len() <= ReceiptConfig::MAX_EMITTERS,
+            ZkError::TooManyEmitters
+        );
+        let first = RemoteEmitter(emitters[0]);
+        check_allowlist_emitter(&cfg.local_emitter(), &first)?;
+        check_emitter_format(cfg.evm_emitters, &first)?;
+        // Emitters already on the list keep their activation slot; new ones
+        // wait out the activation delay.
+        let previous = ReceiptConfig::clone(cfg);
+        let delayed = Clock::get()?
+            .slot
+            .saturating_add(previous.emitter_activation_delay_slots);
+        let active_at = |e: &[u8; 32]| previous.emitter_active_at(e).unwrap_or(delayed);
+        cfg.emitter = first;
+        cfg.emitter_active_at_slot = [0u64; ReceiptConfig::MAX_EMITTERS];
+        cfg.emitter_active_at_slot[0] = active_at(&first.0);
+        cfg.extra_emitters = [RemoteEmitter([0u8; 32]); ReceiptConfig::MAX_EMITTERS - 1];
+        cfg.extra_emitter_count = 0;
+        for emitter in &emitters[1..] {
+            cfg.add_emitter(RemoteEmitter(*emitter), active_at(emitter))?;
+        }
+        // Chain pins follow their emitter too.
+        cfg.emitter_chains = [0u16; ReceiptConfig::MAX_EMITTERS];
+        for (i, emitter) in emitters.iter().enumerate() {
+            cfg.emitter_chains[i] = previous.emitter_chain(emitter).unwrap_or(0);
+        }
+
+        for emitter in emitters.iter().filter(|e| previous.emitter_active_at(e).is_none()) {
+            emit_event!(ctx, EmitterScheduled {
+                emitter: *emitter,
+                active_at_slot: delayed,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds one remote emitter to the allowlist (admin only). The record paths
+    /// accept it from `emitter_activation_delay_slots` after now, the slot
+    /// `EmitterScheduled` reports.
+    pub fn add_emitter(ctx: Context<UpdateReceiptConfig>, emitter: [u8; 32]) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "add_emitter", emitter);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        let active_at_slot = Clock::get()?
+            .slot
+            .saturating_add(cfg.emitter_activation_delay_slots);
+        cfg.add_emitter(RemoteEmitter(emitter), active_at_slot)?;
+
+        emit_event!(ctx, EmitterScheduled {
+            emitter,
+            active_at_slot,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Removes a remote emitter from the allowlist (admin only), with its
+    /// entries for every chain; the last remaining entry cannot be removed.
+    pub fn remove_emitter(ctx: Context<UpdateReceiptConfig>, emitter: [u8; 32]) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "remove_emitter", emitter);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        cfg.remove_emitter(RemoteEmitter(emitter))
+    }
+
+    /// Registers the `(evm_chain, emitter)` pair (admin only): the record
+    /// paths then accept `emitter`'s VAAs from that `emitter_chain`. Pairs are
+    /// separate allowlist entries, so one address may be registered on
+    /// several chains. An unpinned entry for `emitter` is pinned in place;
+    /// otherwise a new entry is added, activating as `add_emitter`'s do.
+    /// Registering a pair already listed is a no-op.
+    pub fn register_chain_emitter(
+        ctx: Context<UpdateReceiptConfig>,
+        evm_chain: u16,
+        emitter: [u8; 32],
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "register_chain_emitter", (evm_chain, emitter));
+        require!(evm_chain != 0, ZkError::ChainNotConfigured);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        if cfg.chain_emitter_position(&emitter, evm_chain).is_some() {
+            return Ok(());
+        }
+        if let Some(i) = cfg.chain_emitter_position(&emitter, 0) {
+            cfg.emitter_chains[i] = evm_chain;
+            return Ok(());
+        }
+        let active_at_slot = Clock::get()?
+            .slot
+            .saturating_add(cfg.emitter_activation_delay_slots);
+        cfg.push_emitter(RemoteEmitter(emitter), active_at_slot, evm_chain)?;
+        emit_event!(ctx, EmitterScheduled {
+            emitter,
+            active_at_slot,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Removes the `(evm_chain, emitter)` allowlist entry (admin only),
+    /// leaving `emitter`'s entries for other chains. Fails with
+    /// `ChainEmitterPairMismatch` unless the pair is registered.
+    pub fn unregister_chain_emitter(
+        ctx: Context<UpdateReceiptConfig>,
+        evm_chain: u16,
+        emitter: [u8; 32],
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "unregister_chain_emitter", (evm_chain, emitter));
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        let i = cfg
+            .chain_emitter_position(&emitter, evm_chain)
+            .filter(|_| evm_chain != 0)
+            .ok_or(error!(ZkError::ChainEmitterPairMismatch))?;
+        cfg.remove_entry(i)
+    }
+
+    /// Replaces the receipt category allowlist (admin only); empty accepts any.
+    pub fn set_allowed_categories(
+        ctx: Context<UpdateReceiptConfig>,
+        categories: Vec<u16>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_allowed_categories", &categories);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(
+            categories.len() <= ReceiptConfig::MAX_CATEGORIES,
+            ZkError::TooManyCategories
+        );
+        cfg.allowed_categories = [0u16; ReceiptConfig::MAX_CATEGORIES];
+        cfg.allowed_categories[..categories.len()].copy_from_slice(&categories);
+        cfg.allowed_category_count = categories.len() as u8;
+        Ok(())
+    }
+
+    /// Pauses or resumes the outbound post path only (admin only); recording is unaffected.
+    /// Exempt from `config_change_cooldown_slots` so it stays usable in an emergency.
+    pub fn set_posting_paused(ctx: Context<SetPostingPaused>, paused: bool) -> Result<()> {
+        let cfg = &mut ctx.accounts.cfg;
+        require_keys_eq!(cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        cfg.posting_paused = paused;
+
+        emit_event!(ctx, PostingPauseChanged {
+            paused,
+            changed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Pauses or resumes recording for one `emitter` (admin only), leaving
+    /// other emitters and the post paths untouched. Every VAA record path
+    /// rejects the emitter's VAAs with `EmitterPaused` while paused. Exempt
+    /// from `config_change_cooldown_slots` like `set_posting_paused`.
+    pub fn set_emitter_paused(
+        ctx: Context<SetEmitterPaused>,
+        emitter: [u8; 32],
+        paused: bool,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        let state = &mut ctx.accounts.emitter_state;
+        state.emitter = emitter;
+        state.paused = paused;
+        state.bump = ctx.bumps.emitter_state;
+
+        emit_event!(ctx, EmitterPauseChanged {
+            emitter,
+            paused,
+            changed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Sets the payload actions `emitter` may send (admin only) as a bitmask
+    /// over `DecodedVaa::action`, bit `a % 8` of byte `a / 8`; all zero
+    /// allows every action. Every VAA record path rejects other actions with
+    /// `ActionNotAllowedForEmitter`.
+    pub fn set_emitter_actions(
+        ctx: Context<SetEmitterActions>,
+        emitter: [u8; 32],
+        allowed_actions: [u8; 32],
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_emitter_actions", (emitter, allowed_actions));
+        throttle_config_change!(ctx);
+        let state = &mut ctx.accounts.emitter_state;
+        state.emitter = emitter;
+        state.allowed_actions = allowed_actions;
+        state.bump = ctx.bumps.emitter_state;
+
+        emit_event!(ctx, EmitterActionsChanged {
+            emitter,
+            allowed_actions,
+            changed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Freezes every post path until `until_slot` for announced Wormhole
+    /// maintenance (admin only); 0 or a past slot lifts it. Recording is
+    /// unaffected. Unlike `set_posting_paused` it is scheduled ahead, so it
+    /// goes through the timelock and `config_change_cooldown_slots`.
+    pub fn set_maintenance_window(
+        ctx: Context<SetMaintenanceWindow>,
+        until_slot: u64,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_maintenance_window", until_slot);
+        throttle_config_change!(ctx);
+        ctx.accounts.cfg.maintenance_until_slot = until_slot;
+
+        emit_event!(ctx, MaintenanceWindowSet {
+            until_slot,
+            changed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Clears the OutboundCursor of the `emitter_role` emitter (admin only), so
+    /// the next post may use any batch_id; for recovering after an upstream
+    /// renumbering.
+    pub fn reset_outbound_cursor(
+        ctx: Context<ResetOutboundCursor>,
+        emitter_role: u8,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        require!(emitter_role <= EMITTER_ROLE_SHADOW, ZkError::UnknownEmitterRole);
+        timelock_gate!(ctx, admin, "reset_outbound_cursor", emitter_role);
+        throttle_config_change!(ctx);
+        let cursor = &mut ctx.accounts.outbound_cursor;
+        let previous_batch_id = cursor.last_batch_id;
+        cursor.last_batch_id = 0;
+        cursor.has_posted = false;
+
+        emit_event!(ctx, OutboundCursorReset {
+            emitter: ctx.accounts.emitter.key().to_bytes(),
+            previous_batch_id,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Creates the zero-copy EmitterIndex ring for `emitter` (admin only).
+    pub fn init_emitter_index(ctx: Context<InitEmitterIndex>, emitter: [u8; 32]) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "init_emitter_index", emitter);
+        throttle_config_change!(ctx);
+        let mut index = ctx.accounts.emitter_index.load_init()?;
+        index.emitter = emitter;
+        index.bump = ctx.bumps.emitter_index;
+        Ok(())
+    }
+
+    /// Creates the zero-copy append-only ReceiptLog for `emitter` (admin only).
+    pub fn init_receipt_log(ctx: Context<InitReceiptLog>, emitter: [u8; 32]) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "init_receipt_log", emitter);
+        throttle_config_change!(ctx);
+        let mut log = ctx.accounts.receipt_log.load_init()?;
+        log.emitter = emitter;
+        log.capacity = ReceiptLog::CAPACITY as u32;
+        log.bump = ctx.bumps.receipt_log;
+        Ok(())
+    }
+
+    /// Creates the zero-copy EventRing of recent `ReceiptRecorded` events
+    /// (admin only). Every record path requires it from then on.
+    pub fn init_event_ring(ctx: Context<InitEventRing>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "init_event_ring", ());
+        throttle_config_change!(ctx);
+        let mut ring = ctx.accounts.event_ring.load_init()?;
+        ring.bump = ctx.bumps.event_ring;
+        ctx.accounts.cfg.event_ring_enabled = true;
+        Ok(())
+    }
+
+    /// Creates the zeroed StatsAccount for configs initialised before it
+    /// existed (admin only); counts start from this point.
+    pub fn init_stats(ctx: Context<InitStats>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        let stats = &mut ctx.accounts.stats;
+        stats.total_recorded = 0;
+        stats.total_direct = 0;
+        stats.total_closed = 0;
+        stats.last_recorded_slot = 0;
+        stats.bump = ctx.bumps.stats;
+        Ok(())
+    }
+
+    /// Creates the EventCounter for configs initialised before it existed
+    /// (admin only); a no-op when it already exists. Every post, record and
+    /// admin instruction requires it, so such configs run this first.
+    pub fn init_event_counter(ctx: Context<InitEventCounter>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        let counter = &mut ctx.accounts.event_counter;
+        if counter.bump == 0 {
+            counter.global_event_seq = 0;
+            counter.bump = ctx.bumps.event_counter;
+        }
+        Ok(())
+    }
+
+    /// Sets an emitter's recording cursor (admin only). Moving it backward
+    /// requires `force_backward` and emits `CursorForcedBackward`. `category`
+    /// selects the cursor only with `cfg.per_category_cursors`.
+    pub fn set_cursor(
+        ctx: Context<SetCursor>,
+        emitter: [u8; 32],
+        sequence: u64,
+        force_backward: bool,
+        category: u16,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_cursor", (emitter, sequence, force_backward, category));
+        throttle_config_change!(ctx);
+
+        let cursor = &mut ctx.accounts.cursor;
+        let previous = cursor.last_sequence;
+        if sequence < previous {
+            require!(force_backward, ZkError::CursorRegression);
+            msg!(
+                "WARNING: cursor for {:?} forced backward {} -> {}",
+                emitter,
+                previous,
+                sequence
+            );
+        }
+        let category = cursor_category(&ctx.accounts.cfg, category);
+        cursor.emitter = emitter;
+        cursor.category = category;
+        cursor.last_sequence = sequence;
+        cursor.bump = ctx.bumps.cursor;
+
+        if sequence < previous {
+            emit_event!(ctx, CursorForcedBackward {
+                emitter,
+                category,
+                previous_sequence: previous,
+                sequence,
+                forced_by: ctx.accounts.admin.key(),
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replaces the poster allowlist and its enforcement flag (admin only).
+    pub fn set_poster_config(
+        ctx: Context<SetPosterConfig>,
+        enforce: bool,
+        allowed_posters: Vec<Pubkey>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_poster_config", (enforce, &allowed_posters));
+        throttle_config_change!(ctx);
+        require!(
+            allowed_posters.len() <= PosterConfig::MAX_POSTERS,
+            ZkError::TooManyPosters
+        );
+
+        let poster_cfg = &mut ctx.accounts.poster_config;
+        poster_cfg.enforce = enforce;
+        poster_cfg.allowed_posters = [Pubkey::default(); PosterConfig::MAX_POSTERS];
+        poster_cfg.allowed_posters[..allowed_posters.len()].copy_from_slice(&allowed_posters);
+        poster_cfg.bump = ctx.bumps.poster_config;
+        Ok(())
+    }
+
+    /// Hands fee management to `fee_authority` (admin only); `Pubkey::default()`
+    /// returns it to the admin. The treasury allowlist and every structural
+    /// setting stay with the admin.
+    pub fn set_fee_authority(
+        ctx: Context<UpdateReceiptConfig>,
+        fee_authority: Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_fee_authority", fee_authority);
+        throttle_config_change!(ctx);
+        ctx.accounts.cfg.fee_authority = fee_authority;
+        Ok(())
+    }
+
+    /// Sets the per-finality protocol fee and its treasury (fee authority only);
+    /// the treasury must be on the admin-managed allowlist.
+    pub fn set_protocol_fee(
+        ctx: Context<UpdateFeeConfig>,
+        fee_confirmed: u64,
+        fee_finalized: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.cfg.effective_fee_authority(),
+            ctx.accounts.fee_authority.key(),
+            ZkError::NotFeeAuthority
+        );
+        timelock_gate!(
+            ctx,
+            fee_authority,
+            "set_protocol_fee",
+            (fee_confirmed, fee_finalized, treasury)
+        );
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(cfg.treasury_allowed(&treasury), ZkError::TreasuryNotAllowed);
+        cfg.protocol_fee_confirmed = fee_confirmed;
+        cfg.protocol_fee_finalized = fee_finalized;
+        cfg.treasury = treasury;
+        Ok(())
+    }
+
+    /// Replaces the treasury allowlist (admin only); empty allows any treasury.
+    /// The current treasury must stay allowed.
+    pub fn set_treasury_allowlist(
+        ctx: Context<UpdateReceiptConfig>,
+        treasuries: Vec<Pubkey>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_treasury_allowlist", &treasuries);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(
+            treasuries.len() <= ReceiptConfig::MAX_TREASURIES,
+            ZkError::TooManyTreasuries
+        );
+        require!(
+            !treasuries.contains(&Pubkey::default()),
+            ZkError::TreasuryNotAllowed
+        );
+        cfg.treasury_allowlist = [Pubkey::default(); ReceiptConfig::MAX_TREASURIES];
+        cfg.treasury_allowlist[..treasuries.len()].copy_from_slice(&treasuries);
+        require!(
+            cfg.treasury == Pubkey::default() || cfg.treasury_allowed(&cfg.treasury),
+            ZkError::TreasuryNotAllowed
+        );
+        Ok(())
+    }
+
+    /// Sets the fee asset, the spend budget and the fee slippage (fee
+    /// authority only). These are fee settings, so they sit with the fee
+    /// authority rather than in `update_receipt_config`.
+    pub fn set_fee_policy(
+        ctx: Context<UpdateFeeConfig>,
+        fee_asset: FeeAsset,
+        budget_per_window: u64,
+        budget_window_seconds: i64,
+        fee_slippage: u64,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.cfg.effective_fee_authority(),
+            ctx.accounts.fee_authority.key(),
+            ZkError::NotFeeAuthority
+        );
+        timelock_gate!(
+            ctx,
+            fee_authority,
+            "set_fee_policy",
+            (fee_asset, budget_per_window, budget_window_seconds, fee_slippage)
+        );
+        throttle_config_change!(ctx);
+        require!(
+            budget_window_seconds > 0 || (budget_window_seconds == 0 && budget_per_window == 0),
+            ZkError::InvalidBudgetWindow
+        );
+        let cfg = &mut ctx.accounts.cfg;
+        cfg.fee_asset = fee_asset;
+        cfg.budget_per_window = budget_per_window;
+        cfg.budget_window_seconds = budget_window_seconds;
+        cfg.fee_slippage = fee_slippage;
+        Ok(())
+    }
+
+    /// Replaces the payers allowed to waive the protocol fee (fee authority only).
+    pub fn set_allowed_waivers(
+        ctx: Context<UpdateFeeConfig>,
+        waivers: Vec<Pubkey>,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.cfg.effective_fee_authority(),
+            ctx.accounts.fee_authority.key(),
+            ZkError::NotFeeAuthority
+        );
+        timelock_gate!(ctx, fee_authority, "set_allowed_waivers", &waivers);
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(waivers.len() <= ReceiptConfig::MAX_WAIVERS, ZkError::TooManyWaivers);
+        cfg.allowed_waivers = [Pubkey::default(); ReceiptConfig::MAX_WAIVERS];
+        cfg.allowed_waivers[..waivers.len()].copy_from_slice(&waivers);
+        Ok(())
+    }
+
+    /// Replaces the programs allowed to CPI into the VAA record paths and
+    /// toggles enforcement (admin only).
+    pub fn set_allowed_cpi_callers(
+        ctx: Context<UpdateReceiptConfig>,
+        enforce: bool,
+        callers: Vec<Pubkey>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_allowed_cpi_callers", (enforce, &callers));
+        throttle_config_change!(ctx);
+        let cfg = &mut ctx.accounts.cfg;
+        require!(
+            callers.len() <= ReceiptConfig::MAX_CPI_CALLERS,
+            ZkError::TooManyCpiCallers
+        );
+        cfg.enforce_cpi_callers = enforce;
+        cfg.allowed_cpi_callers = [Pubkey::default(); ReceiptConfig::MAX_CPI_CALLERS];
+        cfg.allowed_cpi_callers[..callers.len()].copy_from_slice(&callers);
+        Ok(())
+    }
+
+    /// Closes a receipt older than `min_age_seconds` (admin, reaper or close
+    /// authority). With `cfg.reorg_tolerance` set, `cursor` is required and a
+    /// receipt inside the reorg window fails with `ReceiptInReorgWindow`, as
+    /// its sequence could be recorded again once closed.
+    pub fn close_receipt(
+        ctx: Context<CloseReceipt>,
+        emitter: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let closer = ctx.accounts.authority.key();
+        require!(cfg.can_close_receipts(&closer), ZkError::NotCloseAuthorized);
+
+        let now = Clock::get()?.unix_timestamp;
+        let age = now.saturating_sub(ctx.accounts.receipt.posted_timestamp);
+        require!(age >= cfg.min_age_seconds, ZkError::ReceiptTooYoung);
+        let cursor = ctx.accounts.cursor.as_ref().map(|c| std::slice::from_ref(c.as_ref()));
+        check_close_outside_reorg_window(cfg, &ctx.accounts.receipt, cursor.unwrap_or(&[]))?;
+        ctx.accounts.stats.note_closed(1)?;
+        release_emitter_receipt(
+            ctx.accounts.emitter_count.as_mut(),
+            ctx.bumps.emitter_count,
+            &emitter,
+        )?;
+
+        emit_event!(ctx, ReceiptClosed {
+            emitter,
+            sequence,
+            closed_by: closer,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+
+        Ok(())
+    }
+
+    /// `close_receipt` for a hash-keyed receipt from `record_receipt_by_hash`.
+    pub fn close_receipt_by_hash(ctx: Context<CloseReceiptByHash>, _vaa_hash: [u8; 32]) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let closer = ctx.accounts.authority.key();
+        require!(cfg.can_close_receipts(&closer), ZkError::NotCloseAuthorized);
+
+        let now = Clock::get()?.unix_timestamp;
+        let age = now.saturating_sub(ctx.accounts.receipt.posted_timestamp);
+        require!(age >= cfg.min_age_seconds, ZkError::ReceiptTooYoung);
+        ctx.accounts.stats.note_closed(1)?;
+        release_emitter_receipt(
+            ctx.accounts.emitter_count.as_mut(),
+            ctx.bumps.emitter_count,
+            &ctx.accounts.receipt.emitter,
+        )?;
+
+        emit_event!(ctx, ReceiptClosed {
+            emitter: ctx.accounts.receipt.emitter,
+            sequence: ctx.accounts.receipt.sequence,
+            closed_by: closer,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+
+        Ok(())
+    }
+
+    /// Closes every receipt in `remaining_accounts` older than `min_age_seconds`
+    /// (admin, reaper or close authority), skipping younger ones. Rent goes to `authority`;
+    /// returns a bitmask of closed indices (bit `i % 8` of byte `i / 8`). The
+    /// receipts are followed by `counter_count` EmitterReceiptCounts, each
+    /// released by one per closed receipt of its emitter, then `cursor_count`
+    /// EmitterCursor PDAs (which may not exist). Every closed receipt's
+    /// emitter needs an existing counter, so emitters that have none close
+    /// through `close_receipt`; while `reorg_tolerance` is set it needs its
+    /// cursor, and receipts inside the reorg window fail as in `close_receipt`.
+    pub fn close_receipts_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseReceiptsBatch<'info>>,
+        counter_count: u8,
+        cursor_count: u8,
+    ) -> Result<()> {
+        let split = ctx
+            .remaining_accounts
+            .len()
+            .checked_sub(counter_count as usize + cursor_count as usize)
+            .ok_or(error!(ZkError::BatchAccountsMismatch))?;
+        let (receipts, trailing) = ctx.remaining_accounts.split_at(split);
+        let (counter_infos, cursor_infos) = trailing.split_at(counter_count as usize);
+        require!(!receipts.is_empty(), ZkError::NoReceiptsProvided);
+        let cfg = &ctx.accounts.cfg;
+        require!(receipts.len() <= cfg.max_batch_size as usize, ZkError::BatchTooLarge);
+        let mut counters = counter_infos
+            .iter()
+            .map(load_emitter_count)
+            .collect::<Result<Vec<_>>>()?;
+        let closer = ctx.accounts.authority.key();
+        require!(cfg.can_close_receipts(&closer), ZkError::NotCloseAuthorized);
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut closed = vec![0u8; (receipts.len() + 7) / 8];
+        let mut count = 0u32;
+        for (i, info) in receipts.iter().enumerate() {
+            let receipt = load_receipt(info)?;
+            if now.saturating_sub(receipt.posted_timestamp) < cfg.min_age_seconds {
+                continue;
+            }
+            check_close_outside_reorg_window(cfg, &receipt, cursor_infos)?;
+            match counters.iter_mut().find(|c| c.emitter == receipt.emitter) {
+                Some(counter) => counter.note_closed(),
+                None => return err!(ZkError::EmitterCountRequired),
+            }
+            anchor_lang::common::close(info.clone(), ctx.accounts.authority.to_account_info())?;
+            closed[i / 8] |= 1 << (i % 8);
+            count += 1;
+        }
+        for (info, counter) in counter_infos.iter().zip(&counters) {
+            let mut data = info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            counter.try_serialize(&mut writer)?;
+        }
+        ctx.accounts.stats.note_closed(u64::from(count))?;
+
+        emit_event!(ctx, ReceiptsClosed {
+            count,
+            closed_by: closer,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        set_return_bytes(&closed)?;
+        Ok(())
+    }
+
+    /// Closes a batch's AttemptCounter once it has confirmed (admin or reaper):
+    /// the batch's BatchMarker must have round-tripped, else
+    /// `BatchNotConfirmed`. Batches posted without a marker keep their counter.
+    pub fn close_attempt_counter(ctx: Context<CloseAttemptCounter>, _batch_id: u32) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let closer = ctx.accounts.authority.key();
+        require!(
+            closer == cfg.admin
+                || (cfg.receipt_reaper != Pubkey::default() && closer == cfg.receipt_reaper),
+            ZkError::NotAdminOrReaper
+        );
+        require!(ctx.accounts.batch_marker.round_tripped, ZkError::BatchNotConfirmed);
+        Ok(())
+    }
+
+    /// Returns a keccak root over the receipts in `remaining_accounts`.
+    ///
+    /// Receipts are sorted by `(sequence, emitter)`; starting from 32 zero bytes,
+    /// `root = keccak(root || emitter || sequence_be || payload_hash)` per receipt.
+    pub fn compute_receipt_root(ctx: Context<ComputeReceiptRoot>) -> Result<()> {
+        require!(!ctx.remaining_accounts.is_empty(), ZkError::NoReceiptsProvided);
+
+        let mut receipts = ctx
+            .remaining_accounts
+            .iter()
+            .map(load_receipt)
+            .collect::<Result<Vec<Receipt>>>()?;
+        receipts.sort_by(|a, b| (a.sequence, a.emitter).cmp(&(b.sequence, b.emitter)));
+
+        let mut root = [0u8; 32];
+        for (i, r) in receipts.iter().enumerate() {
+            if i > 0 {
+                let prev = &receipts[i - 1];
+                require!(
+                    (prev.sequence, prev.emitter) != (r.sequence, r.emitter),
+                    ZkError::DuplicateReceipt
+                );
+            }
+            root = keccak::hashv(&[&root, &r.emitter, &r.sequence.to_be_bytes(), &r.payload_hash])
+                .to_bytes();
+        }
+
+        set_return_bytes(&root)?;
+        Ok(())
+    }
+
+    /// Checks `receipt` is leaf `leaf_index` of the keccak Merkle tree `root`.
+    ///
+    /// `leaf = keccak(emitter || sequence_be || payload_hash)`; at each level the
+    /// index bit picks the side: 0 hashes `node || sibling`, 1 `sibling || node`.
+    pub fn verify_inclusion(
+        ctx: Context<VerifyInclusion>,
+        leaf_index: u64,
+        proof: Vec<[u8; 32]>,
+        root: [u8; 32],
+    ) -> Result<()> {
+        let r = &ctx.accounts.receipt;
+        r.require_live()?;
+        require!(
+            proof.len() >= 64 || leaf_index >> proof.len() == 0,
+            ZkError::InclusionProofInvalid
+        );
+        let mut node = keccak::hashv(&[&r.emitter, &r.sequence.to_be_bytes(), &r.payload_hash]).to_bytes();
+        let mut index = leaf_index;
+        for sibling in &proof {
+            node = if index & 1 == 0 {
+                keccak::hashv(&[&node, sibling])
+            } else {
+                keccak::hashv(&[sibling, &node])
+            }
+            .to_bytes();
+            index >>= 1;
+        }
+        require!(node == root, ZkError::InclusionProofInvalid);
+        Ok(())
+    }
+
+    /// Checks field `field_index` (`vaa::FIELD_*`) of the VAA behind
+    /// `receipt` is `value`, encoded as in `DecodedVaa::field_root`, through
+    /// `proof` against `Receipt::field_root`. Fails with `FieldRootNotStored`
+    /// if the receipt has no field root, else `FieldProofInvalid`.
+    pub fn verify_field(
+        ctx: Context<VerifyInclusion>,
+        field_index: u8,
+        value: Vec<u8>,
+        proof: [[u8; 32]; vaa::FIELD_PROOF_LEN],
+    ) -> Result<()> {
+        let r = &ctx.accounts.receipt;
+        r.require_live()?;
+        require!(r.field_root != [0u8; 32], ZkError::FieldRootNotStored);
+        require!(
+            vaa::field_len(field_index) == Some(value.len()),
+            ZkError::FieldProofInvalid
+        );
+        require!(
+            vaa::field_proof_root(field_index, &value, &proof) == r.field_root,
+            ZkError::FieldProofInvalid
+        );
+        Ok(())
+    }
+
+    /// Fails with `UnexpectedAction` unless the receipt for `(emitter, sequence)`
+    /// carries payload type `expected_action`, letting a CPI caller gate on the
+    /// message type without re-decoding the payload. Tombstoned receipts fail
+    /// with `ReceiptTombstoned`.
+    pub fn assert_receipt_action(
+        ctx: Context<AssertReceiptAction>,
+        _emitter: [u8; 32],
+        _sequence: u64,
+        expected_action: u8,
+    ) -> Result<()> {
+        let receipt = &ctx.accounts.receipt;
+        receipt.require_live()?;
+        require!(receipt.action == expected_action, ZkError::UnexpectedAction);
+        Ok(())
+    }
+
+    /// Re-emits an existing receipt's `ReceiptRecorded` fields as
+    /// `ReceiptReplayed`, so an indexer can backfill lost logs from on-chain
+    /// state. Permissionless and read-only apart from the event counter;
+    /// `chain_label` is the current config's, and `emitter_receipt_count` the
+    /// current count of the optional `emitter_count`. Fails with `ReceiptNotFound`
+    /// when no receipt is recorded at `(emitter, sequence)`.
+    pub fn reemit_receipt_event(
+        ctx: Context<ReemitReceiptEvent>,
+        emitter: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        let info = ctx.accounts.receipt.to_account_info();
+        require!(!info.data_is_empty(), ZkError::ReceiptNotFound);
+        let receipt = load_receipt(&info)?;
+        require!(receipt.is_recorded(), ZkError::ReceiptNotFound);
+        emit_event!(ctx, ReceiptReplayed {
+            emitter,
+            sequence,
+            vaa: receipt.vaa_account,
+            nonce: receipt.nonce,
+            category: receipt.category,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: receipt.external_ref,
+            reported_cost: receipt.reported_cost,
+            vaa_body_hash: receipt.vaa_body_hash,
+            calldata_hash: receipt.calldata_hash,
+            emitter_receipt_count: ctx.accounts.emitter_count.as_ref().map_or(0, |c| c.count),
+            action: receipt.action,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Checks that receipts for `emitter` exist for `sequences`, in the given order,
+    /// with non-decreasing `posted_slot`.
+    ///
+    /// `remaining_accounts[i]` is the receipt PDA for `sequences[i]`. The failing
+    /// index is logged before `ReceiptMissing` / `ReceiptOutOfOrder` is returned.
+    pub fn verify_receipt_ordering(
+        ctx: Context<ComputeReceiptRoot>,
+        emitter: [u8; 32],
+        sequences: Vec<u64>,
+    ) -> Result<()> {
+        require!(!sequences.is_empty(), ZkError::NoReceiptsProvided);
+        require!(
+            ctx.remaining_accounts.len() == sequences.len(),
+            ZkError::BatchAccountsMismatch
+        );
+
+        let mut prev_slot = 0u64;
+        for (i, (info, &sequence)) in ctx.remaining_accounts.iter().zip(&sequences).enumerate() {
+            check_receipt_pda(info, &emitter, sequence)?;
+            let receipt = load_receipt(info)
+                .ok()
+                .filter(|r| r.is_recorded() && r.emitter == emitter && r.sequence == sequence);
+            let Some(receipt) = receipt else {
+                msg!("receipt missing at index {} (sequence {})", i, sequence);
+                return err!(ZkError::ReceiptMissing);
+            };
+            if receipt.tombstoned {
+                msg!("receipt tombstoned at index {} (sequence {})", i, sequence);
+                return err!(ZkError::ReceiptTombstoned);
+            }
+            if receipt.posted_slot < prev_slot {
+                msg!("receipt out of order at index {} (sequence {})", i, sequence);
+                return err!(ZkError::ReceiptOutOfOrder);
+            }
+            prev_slot = receipt.posted_slot;
+        }
+        Ok(())
+    }
+
+    /// Checks receipts for `emitter` exist for every sequence in `start..=end`.
+    ///
+    /// `remaining_accounts[i]` is the receipt PDA for `start + i`; the first gap
+    /// is logged before `ReceiptGap` is returned.
+    pub fn assert_contiguous(
+        ctx: Context<ComputeReceiptRoot>,
+        emitter: [u8; 32],
+        start: u64,
+        end: u64,
+    ) -> Result<()> {
+        require!(start <= end, ZkError::InvalidSequenceRange);
+        let count = (end - start)
+            .checked_add(1)
+            .ok_or(error!(ZkError::InvalidSequenceRange))?;
+        require!(
+            ctx.remaining_accounts.len() as u64 == count,
+            ZkError::BatchAccountsMismatch
+        );
+
+        for (info, sequence) in ctx.remaining_accounts.iter().zip(start..=end) {
+            let receipt = (info.key() == receipt_pda(&emitter, sequence).0)
+                .then(|| load_receipt(info).ok())
+                .flatten()
+                .filter(|r| r.is_recorded() && r.emitter == emitter && r.sequence == sequence);
+            let Some(receipt) = receipt else {
+                msg!("missing receipt for sequence {}", sequence);
+                return err!(ZkError::ReceiptGap);
+            };
+            if receipt.tombstoned {
+                msg!("tombstoned receipt for sequence {}", sequence);
+                return err!(ZkError::ReceiptTombstoned);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the `prev_hash` links of `emitter`'s receipts from `start` to
+    /// `end`. `remaining_accounts` are the linked receipts in chain order,
+    /// the first at `start` and the last at `end`, sequences increasing; each
+    /// must store the `link_hash` of the one before it. If `cursor` is given,
+    /// the chain must also end at its last linked receipt. The first break is
+    /// logged before `ReceiptChainBroken` is returned.
+    pub fn verify_chain(
+        ctx: Context<VerifyChain>,
+        emitter: [u8; 32],
+        start: u64,
+        end: u64,
+    ) -> Result<()> {
+        require!(start <= end, ZkError::InvalidSequenceRange);
+        require!(!ctx.remaining_accounts.is_empty(), ZkError::NoReceiptsProvided);
+
+        let mut prev: Option<Receipt> = None;
+        for (i, info) in ctx.remaining_accounts.iter().enumerate() {
+            let receipt = load_receipt(info)?;
+            check_receipt_pda(info, &emitter, receipt.sequence)?;
+            let linked = match &prev {
+                Some(p) => receipt.sequence > p.sequence && receipt.prev_hash == p.link_hash(),
+                None => receipt.sequence == start,
+            };
+            if !linked {
+                msg!("chain broken at index {} (sequence {})", i, receipt.sequence);
+                return err!(ZkError::ReceiptChainBroken);
+            }
+            prev = Some(receipt);
+        }
+        let last = prev.as_ref().map_or(start, |r| r.sequence);
+        if last != end {
+            msg!("chain ends at sequence {}, not {}", last, end);
+            return err!(ZkError::ReceiptChainBroken);
+        }
+        if let (Some(cursor), Some(last)) = (ctx.accounts.cursor.as_ref(), prev.as_ref()) {
+            require!(cursor.emitter == emitter, ZkError::EmitterAddressMismatch);
+            if cursor.last_receipt_hash != last.link_hash() {
+                msg!("chain does not end at the cursor's last receipt");
+                return err!(ZkError::ReceiptChainBroken);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the total fee (u64 LE) of `count` posts at `finality_flag`:
+    /// `count * (bridge_fee + protocol_fee)`, read the same way posts charge it.
+    pub fn estimate_batch_fee(
+        ctx: Context<EstimateBatchFee>,
+        count: u32,
+        finality_flag: u8,
+    ) -> Result<()> {
+        finality_from_flag(finality_flag)?;
+        let core = CorePdas::derive(ctx.accounts.wormhole_program.key(), false);
+        let bridge_fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        let per_post = bridge_fee
+            .checked_add(ctx.accounts.cfg.protocol_fee_for(finality_flag))
+            .ok_or(error!(ZkError::FeeOverflow))?;
+        let total = per_post
+            .checked_mul(count as u64)
+            .ok_or(error!(ZkError::FeeOverflow))?;
+        set_return_bytes(&total.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Returns every scalar config field as a Borsh `ConfigView` (version
+    /// `CONFIG_VIEW_VERSION`), decoupled from the `ReceiptConfig` layout.
+    /// The allowlists are served by `read_config_lists`.
+    pub fn read_config(ctx: Context<ReadConfig>) -> Result<()> {
+        set_return_borsh(&ConfigView::from(&**ctx.accounts.cfg))
+    }
+
+    /// Returns the config's allowlists as a Borsh `ConfigListsView` (version
+    /// `CONFIG_VIEW_VERSION`), fitting `MAX_RETURN_DATA` with every list full.
+    pub fn read_config_lists(ctx: Context<ReadConfigLists>) -> Result<()> {
+        set_return_borsh(&ConfigListsView::from(&**ctx.accounts.cfg))
+    }
+
+    /// Returns the Borsh-encoded `StatsAccount` as return data.
+    pub fn read_stats(ctx: Context<ReadStats>) -> Result<()> {
+        set_return_borsh(&*ctx.accounts.stats)
+    }
+
+    /// Returns the effective batch limit of the batch record/close/post paths (u16 LE).
+    pub fn read_max_batch_size(ctx: Context<ReadMaxBatchSize>) -> Result<()> {
+        set_return_bytes(&ctx.accounts.cfg.max_batch_size.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the sequence the emitter's next post will receive (u64 LE).
+    pub fn peek_next_sequence(ctx: Context<PeekNextSequence>) -> Result<()> {
+        let next = read_sequence_tracker(&ctx.accounts.sequence, &ctx.accounts.wormhole_program.key())?
+            .unwrap_or(0);
+        set_return_bytes(&next.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Returns a Borsh `MessageAccount`: the program-signed message PDA the
+    /// next `emitter_role` post will use with `cfg.require_pda_message` (or
+    /// by choice), derived as the post path does from the emitter's next
+    /// sequence. Only valid until another post through that emitter lands.
+    pub fn derive_message_account(
+        ctx: Context<DeriveMessageAccount>,
+        emitter_role: u8,
+    ) -> Result<()> {
+        require!(emitter_role <= EMITTER_ROLE_SHADOW, ZkError::UnknownEmitterRole);
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let sequence = read_sequence_tracker(&ctx.accounts.sequence, &wormhole_program)?.unwrap_or(0);
+        let (address, bump) = derive_message_pda(emitter_role == EMITTER_ROLE_SHADOW, sequence);
+        set_return_borsh(&MessageAccount { address, bump, sequence })
+    }
+
+    /// Returns a Borsh `ReceiptDescription`: the stored receipt plus its age
+    /// and whether it is older than `max_age_seconds` (0 never stale).
+    pub fn describe_receipt(
+        ctx: Context<DescribeReceipt>,
+        _emitter: [u8; 32],
+        _sequence: u64,
+        max_age_seconds: i64,
+    ) -> Result<()> {
+        let receipt = load_receipt(&ctx.accounts.receipt)?;
+        let now = Clock::get()?.unix_timestamp;
+        let age_seconds = now.saturating_sub(receipt.posted_timestamp).max(0);
+        let description = ReceiptDescription {
+            receipt,
+            age_seconds,
+            is_stale: max_age_seconds > 0 && age_seconds > max_age_seconds,
+        };
+        set_return_borsh(&description)?;
+        Ok(())
+    }
+
+    /// Health check for our emitter: returns a Borsh `EmitterStatus`. An
+    /// uninitialized tracker reads as `{ false, 0 }`.
+    pub fn emitter_status(ctx: Context<ReadEmitterStatus>) -> Result<()> {
+        let tracker = read_sequence_tracker(&ctx.accounts.sequence, &ctx.accounts.wormhole_program.key())?
+            .unwrap_or(0);
+        let status = EmitterStatus {
+            initialized: tracker != 0,
+            current_sequence: tracker,
+        };
+        set_return_borsh(&status)?;
+        Ok(())
+    }
+
+    /// Diagnostic view for incident recovery: returns a Borsh `Reconciliation`
+    /// with our outbound Wormhole sequence tracker and the inbound cursor for
+    /// `remote_emitter` (and `category`, with per-category cursors). Missing
+    /// accounts read as `None`.
+    pub fn reconcile(
+        ctx: Context<Reconcile>,
+        remote_emitter: [u8; 32],
+        _category: u16,
+    ) -> Result<()> {
+        let outbound_next_sequence =
+            read_sequence_tracker(&ctx.accounts.sequence, &ctx.accounts.wormhole_program.key())?;
+
+        let cursor_info = &ctx.accounts.cursor;
+        let inbound_last_sequence = if cursor_info.owner == &crate::ID && !cursor_info.data_is_empty() {
+            let data = cursor_info.try_borrow_data()?;
+            let mut data_slice: &[u8] = &data;
+            let cursor = EmitterCursor::try_deserialize(&mut data_slice)?;
+            require!(cursor.emitter == remote_emitter, ZkError::CursorEmitterMismatch);
+            Some(cursor.last_sequence)
+        } else {
+            None
+        };
+
+        let view = Reconciliation {
+            outbound_next_sequence,
+            inbound_last_sequence,
+        };
+        set_return_borsh(&view)?;
+        Ok(())
+    }
+
+    /// Read-only preflight for `record_receipt_from_vaa`; returns a u32 bitflag
+    /// (`PREFLIGHT_*`, LE) of which checks passed. Failed checks clear their bit
+    /// instead of erroring.
+    pub fn preflight_record(
+        ctx: Context<PreflightRecord>,
+        emitter: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        let mut flags = 0u32;
+
+        let cfg_info = &ctx.accounts.cfg;
+        let cfg = if cfg_info.owner == &crate::ID {
+            let data = cfg_info.try_borrow_data()?;
+            let mut data_slice: &[u8] = &data;
+            ReceiptConfig::try_deserialize(&mut data_slice).ok()
+        } else {
+            None
+        };
+
+        let posted_vaa = &ctx.accounts.posted_vaa;
+        let decoded = vaa::decode_posted_vaa(posted_vaa).ok();
+        if posted_vaa.owner == &ctx.accounts.wormhole_program.key()
+            && decoded.as_ref().map(|v| v.emitter_address) == Some(emitter)
+        {
+            flags |= PREFLIGHT_VAA_VERIFIED;
+        }
+
+        let receipt = &ctx.accounts.receipt;
+        if receipt.key() == receipt_pda(&emitter, sequence).0 && receipt.data_is_empty() {
+            flags |= PREFLIGHT_SEQUENCE_NEW;
+        }
+
+        if let Some(cfg) = cfg {
+            flags |= PREFLIGHT_CONFIG_EXISTS;
+            if cfg.emitter_allowed(&emitter) {
+                flags |= PREFLIGHT_EMITTER_ALLOWED;
+            }
+            let now = Clock::get()?.unix_timestamp;
+            if decoded
+                .as_ref()
+                .is_some_and(|v| check_vaa_freshness(&cfg, v.timestamp as i64, now).is_ok())
+            {
+                flags |= PREFLIGHT_VAA_FRESH;
+            }
+        }
+
+        set_return_bytes(&flags.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Read-only dry run of `record_receipt_from_vaa` with the same
+    /// `category` and optional accounts, in the order it runs its checks: an
+    /// existing receipt (`AlreadyPresent`, or `PayloadHashConflict` for a
+    /// different payload), category, `validate_posted_vaa` (pause, owner,
+    /// chain, emitter, action, nonce, consistency, freshness, framing), the
+    /// given `cursor` (sequence past it or within `cfg.reorg_tolerance`), the
+    /// emitter receipt cap, the inline proof with `cfg.verify_inline`, the
+    /// guardian quorum with `cfg.verify_signatures_independently`,
+    /// `expected_digest` and `hash_index`. Returns a Borsh `RecordValidation`:
+    /// the error code of the first failing check, which is what the record
+    /// would fail with, or 0 and the `RecordOutcome` it would return. Check
+    /// failures never fail this call.
+    pub fn validate_record(
+        ctx: Context<ValidateRecord>,
+        emitter: [u8; 32],
+        sequence: u64,
+        category: u16,
+    ) -> Result<()> {
+        let validation = match run_record_checks(ctx.accounts, &emitter, sequence, category) {
+            Ok(outcome) => RecordValidation { code: 0, outcome: Some(outcome) },
+            Err(anchor_lang::error::Error::AnchorError(e)) => RecordValidation {
+                code: e.error_code_number as u64,
+                outcome: None,
+            },
+            Err(anchor_lang::error::Error::ProgramError(e)) => RecordValidation {
+                code: u64::from(e.program_error.clone()),
+                outcome: None,
+            },
+        };
+        set_return_borsh(&validation)
+    }
+
+    /// Observation call for relayer monitoring: classifies what
+    /// `record_receipt_from_vaa` would do with these accounts and emits it as
+    /// `RecordAttemptObserved`. Most rejected records emit nothing, and the
+    /// few that do (see `emit_event!`) leave their event only in the failed
+    /// transaction's logs; this call succeeds whatever the outcome, so every
+    /// rejection reason can be tallied from successful transactions. The
+    /// success tally is `StatsAccount::total_recorded`, which the paired
+    /// record bumps.
+    pub fn record_attempt(
+        ctx: Context<RecordAttempt>,
+        emitter: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let posted_vaa = &ctx.accounts.posted_vaa;
+        let receipt = &ctx.accounts.receipt;
+        let decoded = if posted_vaa.owner == &ctx.accounts.wormhole_program.key() {
+            vaa::decode_posted_vaa(posted_vaa).ok()
+        } else {
+            None
+        };
+        let now = Clock::get()?.unix_timestamp;
+        let outcome = if receipt.owner == &crate::ID && !receipt.data_is_empty() {
+            RecordAttemptOutcome::Duplicate
+        } else if !cfg.emitter_allowed(&emitter) {
+            RecordAttemptOutcome::EmitterNotAllowed
+        } else if let Some(decoded) = decoded {
+            if decoded.emitter_address != emitter {
+                RecordAttemptOutcome::EmitterMismatch
+            } else if decoded.consistency_level < cfg.min_record_consistency {
+                RecordAttemptOutcome::ConsistencyTooLow
+            } else if check_vaa_freshness(cfg, decoded.timestamp as i64, now).is_err() {
+                RecordAttemptOutcome::Stale
+            } else {
+                RecordAttemptOutcome::Recordable
+            }
+        } else {
+            RecordAttemptOutcome::InvalidVaa
+        };
+
+        emit_event!(ctx, RecordAttemptObserved {
+            emitter,
+            sequence,
+            outcome,
+            reporter: ctx.accounts.reporter.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Returns the 32-byte emitter address Wormhole records for our posts: the
+    /// emitter PDA's pubkey bytes (seed `b"emitter"`), not the seed itself.
+    /// This is the value the EVM side must trust.
+    pub fn read_emitter_address(_ctx: Context<ReadEmitterAddress>) -> Result<()> {
+        set_return_bytes(&local_emitter_address().0)?;
+        Ok(())
+    }
+
+    /// Records a receipt from a PostedVAA.
+    ///
+    /// Ordered so a failure leaves nothing half-written, independent of
+    /// transaction rollback: all checks and decoding first, then the fallible
+    /// mutations (hash index, then cursor, counters and stats together in
+    /// `advance_record_state`), then infallible writes. A failed call can
+    /// simply be re-run.
+    ///
+    /// Once `cursor` exists, `sequence` must be past its `last_sequence`, which
+    /// then advances to `sequence`; the receipt also links to the previous one
+    /// through `prev_hash` (see `verify_chain`). A sequence at most
+    /// `cfg.reorg_tolerance` below `last_sequence` is still recorded, unlinked
+    /// and leaving the cursor as is, and emits `ReorgReceiptRecorded`. With
+    /// `cfg.link_receipts` the cursor must exist, and the sequence-keyed
+    /// record paths that cannot link (ring, batch, direct, with-hash) fail
+    /// with `UnlinkedRecordPath`, so the chain has no unlinked receipts.
+    ///
+    /// If `hash_index` is given, the `[HASH_INDEX_SEED, payload_hash]` PDA is
+    /// created pointing at this receipt; `cfg.require_hash_index` requires it.
+    /// The other record paths index the same way, except
+    /// `record_receipt_direct`, whose receipts carry no payload hash.
+    ///
+    /// If `emitter_index` is given, `(sequence, payload_hash)` is appended to it.
+    ///
+    /// If `expected_digest` is given, it must be the one registered for the
+    /// VAA's nonce (the poster's batch_id) and match the payload hash. With
+    /// `cfg.require_expected_digest` it is required, and the VAA record paths
+    /// that take no `expected_digest` fail with `ExpectedDigestMissing`.
+    ///
+    /// With `cfg.verify_inline`, the payload must end in a Groth16 proof (see
+    /// `groth16`) that verifies against `verifying_key`, the key registered
+    /// for the proof's circuit id; only then is the receipt created, with
+    /// `proof_verified` set.
+    ///
+    /// With `cfg.verify_signatures_independently`, the transaction must also
+    /// carry Secp256k1 instructions in which a quorum of `trusted_guardian_set`
+    /// signs the VAA body hash, as guardians do; see `check_guardian_quorum`.
+    ///
+    /// `external_ref` (any caller correlation id) is stored and emitted as
+    /// given, zero when `None`; the ring, direct and with-hash paths take it too.
+    ///
+    /// `reported_cost` is the relayer's own report of what delivering the VAA
+    /// cost on the source chain, stored and emitted for reimbursement
+    /// reconciliation. It is untrusted: nothing on chain checks or uses it.
+    ///
+    /// Returns a Borsh `RecordOutcome`; an existing receipt is left untouched
+    /// and reported as `AlreadyPresent`. If it came from a VAA for the same
+    /// emitter and sequence with a different payload hash, this emits
+    /// `PayloadConflictDetected` and fails with `PayloadHashConflict`.
+    ///
+    /// A receipt written before a `Receipt::SIZE` growth is too short to load
+    /// and fails with `AccountDidNotDeserialize`; run `migrate_receipt` on it
+    /// first.
+    pub fn record_receipt_from_vaa(
+        ctx: Context<RecordReceiptFromVaa>,
+        emitter: [u8; 32],
+        sequence: u64,
+        category: u16,
+        external_ref: Option<[u8; 32]>,
+        reported_cost: Option<u64>,
+        calldata_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        check_cpi_caller(
+            &ctx.accounts.cfg,
+            ctx.accounts.instructions.as_ref().map(|i| i.as_ref()),
+        )?;
+        if calldata_hash.is_some() {
+            let cfg = &ctx.accounts.cfg;
+            let signer = ctx.accounts.payer.key();
+            require!(
+                signer == cfg.admin || (cfg.operator != Pubkey::default() && signer == cfg.operator),
+                ZkError::NotOperator
+            );
+        }
+        let receipt_bump =
+            check_receipt_pda(&ctx.accounts.receipt.to_account_info(), &emitter, sequence)?;
+        if ctx.accounts.receipt.is_recorded() {
+            let receipt = &ctx.accounts.receipt;
+            if receipt.source == ReceiptSource::Wormhole {
+                let posted_vaa = &ctx.accounts.posted_vaa;
+                check_posted_vaa_owner(posted_vaa, &ctx.accounts.wormhole_program.key())?;
+                let decoded = vaa::decode_posted_vaa(posted_vaa)?;
+                let incoming_hash =
+                    decoded.payload_hash(receipt.hash_algo, receipt.hash_prefix_skip)?;
+                if decoded.emitter_address == emitter
+                    && decoded.sequence == sequence
+                    && incoming_hash != receipt.payload_hash
+                {
+                    msg!("payload hash conflict for {:?}/{}", emitter, sequence);
+                    emit_event!(ctx, PayloadConflictDetected {
+                        emitter,
+                        sequence,
+                        stored_hash: ctx.accounts.receipt.payload_hash,
+                        incoming_hash,
+                        vaa: ctx.accounts.posted_vaa.key(),
+                        event_seq: ctx.accounts.event_counter.next()?,
+                    });
+                    return err!(ZkError::PayloadHashConflict);
+                }
+            }
+            set_return_borsh(&RecordOutcome::AlreadyPresent)?;
+            return Ok(());
+        }
+        require!(ctx.accounts.cfg.category_allowed(category), ZkError::CategoryNotAllowed);
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let decoded = validate_posted_vaa(
+            &ctx.accounts.cfg,
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.wormhole_program.key(),
+            &ctx.accounts.emitter_state,
+            &emitter,
+            Some(sequence),
+            clock.unix_timestamp,
+        )?;
+        let round_trip = ctx.accounts.batch_marker.as_ref().and_then(|marker| {
+            ReceiptPayloadHeader::parse(&decoded.payload)
+                .filter(|h| h.orig_emitter == ctx.accounts.cfg.local_emitter() && h.orig_sequence == marker.sequence)
+                .map(|_| (marker.batch_id, marker.sequence))
+        });
+        let mut cursor = load_cursor(&ctx.accounts.cursor, &emitter)?;
+        require!(!ctx.accounts.cfg.link_receipts || cursor.is_some(), ZkError::ChainCursorRequired);
+        let (prev_hash, reorg_cursor) = match cursor.as_ref() {
+            Some(cursor) if sequence > cursor.last_sequence => (cursor.last_receipt_hash, None),
+            Some(cursor) => {
+                require!(
+                    in_reorg_window(&ctx.accounts.cfg, cursor.last_sequence, sequence),
+                    ZkError::SequenceNotAfterCursor
+                );
+                ([0u8; 32], Some(cursor.last_sequence))
+            }
+            None => ([0u8; 32], None),
+        };
+
+        if ctx.accounts.cfg.verify_inline {
+            let vk_info = ctx
+                .accounts
+                .verifying_key
+                .as_ref()
+                .ok_or(error!(ZkError::VerifyingKeyRequired))?;
+            let (circuit_id, proof, inputs) = groth16::split_proof(&decoded.payload)?;
+            let vk = load_verifying_key(vk_info, circuit_id)?;
+            groth16::verify(&vk, &proof, &inputs)?;
+        }
+        if ctx.accounts.cfg.verify_signatures_independently {
+            let set = ctx
+                .accounts
+                .trusted_guardian_set
+                .as_ref()
+                .ok_or(error!(ZkError::TrustedGuardianSetRequired))?;
+            let instructions = ctx
+                .accounts
+                .instructions
+                .as_ref()
+                .ok_or(error!(ZkError::InstructionsSysvarRequired))?;
+            check_guardian_quorum(set, instructions, &decoded)?;
+        }
+
+        let payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+        check_expected_digest(
+            &ctx.accounts.cfg,
+            ctx.accounts.expected_digest.as_deref(),
+            &decoded,
+            &payload_hash,
+        )?;
+        index_payload_hash!(
+            ctx,
+            payer,
+            ctx.accounts.hash_index.as_ref().map(|i| i.as_ref()),
+            payload_hash,
+            emitter,
+            sequence
+        );
+        if let Some(index) = ctx.accounts.emitter_index.as_ref() {
+            index.load_mut()?.push(sequence, payload_hash);
+        }
+
+        let events = 1 + u64::from(round_trip.is_some()) + u64::from(reorg_cursor.is_some());
+        let (first_event_seq, emitter_receipt_count) = advance_record_state(
+            &ctx.accounts.cfg,
+            cursor.as_mut(),
+            ctx.accounts.emitter_count.as_mut(),
+            ctx.bumps.emitter_count,
+            &mut ctx.accounts.stats,
+            &mut ctx.accounts.event_counter,
+            (&emitter, sequence, clock.slot, events),
+        )?;
+        let round_trip_seq = round_trip.map(|_| first_event_seq);
+        let recorded_seq = first_event_seq + u64::from(round_trip.is_some());
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.emitter = emitter;
+        receipt.sequence = sequence;
+        receipt.vaa_account = ctx.accounts.posted_vaa.key();
+        receipt.posted_timestamp = clock.unix_timestamp;
+        receipt.posted_slot = clock.slot;
+        receipt.bump = receipt_bump;
+        receipt.payload_hash = payload_hash;
+        receipt.source = ReceiptSource::Wormhole;
+        receipt.proof_ref = [0u8; 32];
+        receipt.origin_batch_id = round_trip.map_or(0, |(batch_id, _)| batch_id);
+        receipt.round_tripped = round_trip.is_some();
+        receipt.nonce = decoded.nonce;
+        receipt.consistency_level = decoded.consistency_level;
+        receipt.hash_algo = ctx.accounts.cfg.hash_algo;
+        receipt.hash_prefix_skip = ctx.accounts.cfg.hash_prefix_skip;
+        receipt.category = category;
+        receipt.status = ReceiptStatus::Unverified;
+        receipt.attested_by = [0u8; 32];
+        receipt.external_ref = external_ref.unwrap_or_default();
+        receipt.reported_cost = reported_cost.unwrap_or_default();
+        receipt.action = decoded.action();
+        receipt.vaa_body_hash = decoded.body_hash();
+        receipt.calldata_hash = calldata_hash.unwrap_or_default();
+        receipt.prev_hash = prev_hash;
+        receipt.field_root = ctx.accounts.cfg.field_root(&decoded, &payload_hash);
+        receipt.tombstoned = false;
+        receipt.tombstone_reason = 0;
+        receipt.reserved = [0u8; Receipt::RESERVED_LEN];
+        receipt.proof_verified = ctx.accounts.cfg.verify_inline;
+        if let Some(cursor) = cursor.as_mut() {
+            if reorg_cursor.is_none() {
+                cursor.last_receipt_hash = receipt.link_hash();
+            }
+            let mut data = ctx.accounts.cursor.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            cursor.try_serialize(&mut writer)?;
+        }
+        let (vaa, nonce, vaa_body_hash) =
+            (receipt.vaa_account, receipt.nonce, receipt.vaa_body_hash);
+
+        if let (Some((batch_id, outbound_sequence)), Some(event_seq)) = (round_trip, round_trip_seq) {
+            if let Some(marker) = ctx.accounts.batch_marker.as_mut() {
+                if !marker.round_tripped {
+                    ctx.accounts.cfg.in_flight = ctx.accounts.cfg.in_flight.saturating_sub(1);
+                }
+                marker.round_tripped = true;
+            }
+            emit_event!(ctx, RoundTripObserved {
+                batch_id,
+                outbound_sequence,
+                emitter,
+                sequence,
+                event_seq,
+            });
+        }
+
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            recorded_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa,
+            nonce,
+            category,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: external_ref.unwrap_or_default(),
+            reported_cost: reported_cost.unwrap_or_default(),
+            vaa_body_hash,
+            calldata_hash: calldata_hash.unwrap_or_default(),
+            emitter_receipt_count,
+            action: decoded.action(),
+            event_seq: recorded_seq,
+        });
+        if let Some(cursor_sequence) = reorg_cursor {
+            emit_event!(ctx, ReorgReceiptRecorded {
+                emitter,
+                sequence,
+                cursor_sequence,
+                reorg_tolerance: ctx.accounts.cfg.reorg_tolerance,
+                event_seq: recorded_seq + 1,
+            });
+        }
+
+        set_return_borsh(&RecordOutcome::NewlyRecorded)?;
+        Ok(())
+    }
+
+    /// Verifies several Groth16 proofs of circuit `circuit_id` against its
+    /// `verifying_key` in one batched pairing check (`groth16::verify_batch`,
+    /// which documents the soundness argument). Each entry is a proof section
+    /// in the format trailed on inline-verified payloads and must carry
+    /// `circuit_id` (`CircuitIdMismatch` otherwise). Fails with
+    /// `BatchProofInvalid` unless every proof verifies; stores nothing.
+    pub fn verify_proofs_batch(
+        ctx: Context<VerifyProofsBatch>,
+        circuit_id: u32,
+        proofs: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let mut batch = Vec::with_capacity(proofs.len());
+        for section in &proofs {
+            let (id, proof, inputs) = groth16::split_proof(section)?;
+            require!(id == circuit_id, ZkError::CircuitIdMismatch);
+            batch.push((proof, inputs));
+        }
+        groth16::verify_batch(&ctx.accounts.verifying_key, &batch)
+    }
+
+    /// Registers, or replaces, the Groth16 verifying key of circuit
+    /// `circuit_id` used by `cfg.verify_inline` (admin only).
+    pub fn register_verifying_key(
+        ctx: Context<RegisterVerifyingKey>,
+        circuit_id: u32,
+        alpha_g1: [u8; 64],
+        beta_g2: [u8; 128],
+        gamma_g2: [u8; 128],
+        delta_g2: [u8; 128],
+        ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(
+            ctx,
+            admin,
+            "register_verifying_key",
+            (circuit_id, alpha_g1, beta_g2, gamma_g2, delta_g2, &ic)
+        );
+        throttle_config_change!(ctx);
+        require!(
+            !ic.is_empty() && ic.len() <= VerifyingKey::MAX_PUBLIC_INPUTS + 1,
+            ZkError::InvalidVerifyingKey
+        );
+
+        let vk = &mut ctx.accounts.verifying_key;
+        vk.circuit_id = circuit_id;
+        vk.alpha_g1 = alpha_g1;
+        vk.beta_g2 = beta_g2;
+        vk.gamma_g2 = gamma_g2;
+        vk.delta_g2 = delta_g2;
+        vk.ic = [[0u8; 64]; VerifyingKey::MAX_PUBLIC_INPUTS + 1];
+        vk.ic[..ic.len()].copy_from_slice(&ic);
+        vk.ic_len = ic.len() as u8;
+        vk.bump = ctx.bumps.verifying_key;
+        Ok(())
+    }
+
+    /// Removes circuit `circuit_id`'s verifying key (admin only), refunding
+    /// its rent to the admin. Its proofs no longer verify inline afterwards.
+    /// Emits `VerifyingKeyRemoved`.
+    pub fn remove_verifying_key(ctx: Context<RemoveVerifyingKey>, circuit_id: u32) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "remove_verifying_key", circuit_id);
+        throttle_config_change!(ctx);
+        emit_event!(ctx, VerifyingKeyRemoved {
+            circuit_id,
+            legacy: false,
+            removed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Closes the single verifying key stored at `[VERIFYING_KEY_SEED]`
+    /// before keys were keyed by circuit id (admin only), refunding its rent
+    /// to the admin. Nothing reads it any more; once closed, a second call
+    /// fails with `LegacyVerifyingKeyNotFound`. Emits `VerifyingKeyRemoved`
+    /// with `legacy` set and `circuit_id` 0.
+    pub fn close_legacy_verifying_key(ctx: Context<CloseLegacyVerifyingKey>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "close_legacy_verifying_key", ());
+        throttle_config_change!(ctx);
+        let legacy = ctx.accounts.legacy_verifying_key.to_account_info();
+        require!(
+            legacy.owner == &crate::ID
+                && legacy.try_borrow_data()?.starts_with(VerifyingKey::DISCRIMINATOR),
+            ZkError::LegacyVerifyingKeyNotFound
+        );
+        anchor_lang::common::close(legacy, ctx.accounts.admin.to_account_info())?;
+        emit_event!(ctx, VerifyingKeyRemoved {
+            circuit_id: 0,
+            legacy: true,
+            removed_by: ctx.accounts.admin.key(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Sets the guardian set used by `cfg.verify_signatures_independently`
+    /// (admin only): Core Bridge set `index` and its guardian addresses in
+    /// set order.
+    pub fn set_trusted_guardian_set(
+        ctx: Context<SetTrustedGuardianSet>,
+        index: u32,
+        keys: Vec<[u8; 20]>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        timelock_gate!(ctx, admin, "set_trusted_guardian_set", (index, &keys));
+        throttle_config_change!(ctx);
+        require!(
+            !keys.is_empty() && keys.len() <= TrustedGuardianSet::MAX_GUARDIANS,
+            ZkError::InvalidGuardianSet
+        );
+
+        let set = &mut ctx.accounts.trusted_guardian_set;
+        set.index = index;
+        set.keys = [[0u8; 20]; TrustedGuardianSet::MAX_GUARDIANS];
+        set.keys[..keys.len()].copy_from_slice(&keys);
+        set.key_count = keys.len() as u8;
+        set.bump = ctx.bumps.trusted_guardian_set;
+        set.updated_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    /// Returns the stored TrustedGuardianSet as `index u32 LE | key_count u8 |
+    /// updated_slot u64 LE`, for monitors comparing it with the Core Bridge's
+    /// current guardian set index.
+    pub fn read_trusted_guardian_set(ctx: Context<ReadTrustedGuardianSet>) -> Result<()> {
+        let set = &ctx.accounts.trusted_guardian_set;
+        set_return_bytes(
+            &[
+                &set.index.to_le_bytes()[..],
+                &[set.key_count],
+                &set.updated_slot.to_le_bytes(),
+            ]
+            .concat(),
+        )?;
+        Ok(())
+    }
+
+    /// Registers the payload digest expected back for `batch_id` (admin or
+    /// operator). `record_receipt_from_vaa` checks it when passed the account.
+    pub fn register_expected_digest(
+        ctx: Context<RegisterExpectedDigest>,
+        batch_id: u32,
+        digest: [u8; 32],
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let signer = ctx.accounts.authority.key();
+        require!(
+            signer == cfg.admin || (cfg.operator != Pubkey::default() && signer == cfg.operator),
+            ZkError::NotOperator
+        );
+        throttle_config_change!(ctx);
+
+        let expected = &mut ctx.accounts.expected_digest;
+        expected.batch_id = batch_id;
+        expected.digest = digest;
+        expected.registered_by = signer;
+        expected.bump = ctx.bumps.expected_digest;
+        Ok(())
+    }
+
+    /// Validates a PostedVAA like `record_receipt_from_vaa` and appends a
+    /// compact record to the emitter's ReceiptLog instead of creating a
+    /// receipt PDA. Fails with `LogFull` at capacity and `AlreadyInLog` for a
+    /// sequence already logged. Returns the entry index (u32 LE).
+    pub fn append_receipt_to_log(
+        ctx: Context<AppendReceiptToLog>,
+        emitter: [u8; 32],
+        sequence: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.cfg.verify_inline, ZkError::InlineVerificationRequired);
+        require!(
+            !ctx.accounts.cfg.verify_signatures_independently,
+            ZkError::IndependentVerificationRequired
+        );
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let decoded = validate_posted_vaa(
+            &ctx.accounts.cfg,
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.wormhole_program.key(),
+            &ctx.accounts.emitter_state,
+            &emitter,
+            Some(sequence),
+            clock.unix_timestamp,
+        )?;
+        require!(!ctx.accounts.cfg.require_expected_digest, ZkError::ExpectedDigestMissing);
+        let payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+        index_payload_hash!(
+            ctx,
+            payer,
+            ctx.accounts.hash_index.as_ref().map(|i| i.as_ref()),
+            payload_hash,
+            emitter,
+            sequence
+        );
+        let record = LogRecord {
+            sequence,
+            posted_timestamp: clock.unix_timestamp,
+            payload_hash,
+            nonce: decoded.nonce,
+            consistency_level: decoded.consistency_level,
+            _padding: [0u8; 3],
+        };
+
+        let index = ctx.accounts.receipt_log.load_mut()?.append(record)?;
+        ctx.accounts.stats.note_recorded(clock.slot, false)?;
+        let event_seq = ctx.accounts.event_counter.next()?;
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            event_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa: ctx.accounts.posted_vaa.key(),
+            nonce: decoded.nonce,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: [0u8; 32],
+            reported_cost: 0,
+            vaa_body_hash: decoded.body_hash(),
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count: 0,
+            action: decoded.action(),
+            event_seq,
+        });
+        set_return_bytes(&index.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Summarises recording state for `cursor`'s emitter as a
+    /// `StateAttestation`: `last_sequence` from the cursor, the emitter's
+    /// receipt counts from `emitter_count`, the current slot, and our Wormhole emitter address
+    /// as the attesting party. Returned as `StateAttestation::to_bytes` for a
+    /// caller to post cross-chain, and emitted as `StateAttested`.
+    pub fn attest_state(ctx: Context<AttestState>) -> Result<()> {
+        let cursor = &ctx.accounts.cursor;
+        let counter = &ctx.accounts.emitter_count;
+        let attestation = StateAttestation {
+            program_emitter: ctx.accounts.cfg.local_emitter().0,
+            emitter: cursor.emitter,
+            category: cursor.category,
+            last_sequence: cursor.last_sequence,
+            total_recorded: counter.total_recorded,
+            total_closed: counter.total_closed,
+            slot: Clock::get()?.slot,
+        };
+        set_return_bytes(&attestation.to_bytes())?;
+        emit_event!(ctx, StateAttested {
+            attestation,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Returns the receipts passed in `remaining_accounts`, in that order, as
+    /// `count u8 | count * Receipt::EXPORT_LEN` records (`Receipt::to_export_bytes`),
+    /// sparing exporters a fetch and decode per account. Each account must be
+    /// a receipt owned by this program. Fails with `ExportTooLarge` when the
+    /// result exceeds `MAX_RETURN_DATA`, i.e. beyond `Receipt::EXPORT_MAX`.
+    pub fn export_receipts<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExportReceipts>,
+    ) -> Result<()> {
+        let count = ctx.remaining_accounts.len();
+        require!(count <= Receipt::EXPORT_MAX, ZkError::ExportTooLarge);
+        let mut out = Vec::with_capacity(1 + count * Receipt::EXPORT_LEN);
+        out.push(count as u8);
+        for info in ctx.remaining_accounts {
+            out.extend_from_slice(&load_receipt(info)?.to_export_bytes());
+        }
+        set_return_bytes(&out)?;
+        Ok(())
+    }
+
+    /// Returns ReceiptLog entry `index`: `sequence u64 LE | posted_timestamp
+    /// i64 LE | payload_hash [u8; 32] | nonce u32 LE | consistency_level u8`.
+    pub fn read_log_entry(ctx: Context<ReadLogEntry>, index: u32) -> Result<()> {
+        let log = ctx.accounts.receipt_log.load()?;
+        require!(index < log.count, ZkError::LogIndexOutOfRange);
+        let r = &log.records[index as usize];
+        set_return_bytes(
+            &[
+                &r.sequence.to_le_bytes()[..],
+                &r.posted_timestamp.to_le_bytes(),
+                &r.payload_hash,
+                &r.nonce.to_le_bytes(),
+                &[r.consistency_level],
+            ]
+            .concat(),
+        )?;
+        Ok(())
+    }
+
+    /// Returns EventRing entries from index `since_index` on, at most
+    /// `EventRing::READ_MAX` per call: `first_index u64 LE | count u8 |
+    /// count * (emitter | sequence LE | event_seq LE | slot LE)`. If
+    /// `since_index` was already overwritten, `first_index` is the oldest
+    /// entry still held and the difference is what the poller missed. Call
+    /// again from `first_index + count` until `count` is 0.
+    pub fn read_event_ring(ctx: Context<ReadEventRing>, since_index: u64) -> Result<()> {
+        let ring = ctx.accounts.event_ring.load()?;
+        let first = since_index.max(ring.oldest()).min(ring.head);
+        let count = (ring.head - first).min(EventRing::READ_MAX as u64);
+        let mut out = Vec::with_capacity(9 + count as usize * EventRingEntry::SIZE);
+        out.extend_from_slice(&first.to_le_bytes());
+        out.push(count as u8);
+        for index in first..first + count {
+            let e = &ring.entries[(index % EventRing::CAPACITY as u64) as usize];
+            out.extend_from_slice(&e.emitter);
+            out.extend_from_slice(&e.sequence.to_le_bytes());
+            out.extend_from_slice(&e.event_seq.to_le_bytes());
+            out.extend_from_slice(&e.slot.to_le_bytes());
+        }
+        set_return_bytes(&out)?;
+        Ok(())
+    }
+
+    /// Grows a config written under an older, shorter layout to
+    /// `ReceiptConfig::SIZE` (permissionless; `payer` funds the extra rent).
+    /// Appended fields read as zero, i.e. unset. Already-current configs are
+    /// left as is.
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let cfg = ctx.accounts.cfg.to_account_info();
+        require_keys_eq!(*cfg.owner, crate::ID, ZkError::ConfigNotMigratable);
+        require!(
+            cfg.try_borrow_data()?.starts_with(ReceiptConfig::DISCRIMINATOR),
+            ZkError::ConfigNotMigratable
+        );
+
+        let target = 8 + ReceiptConfig::SIZE;
+        if cfg.data_len() >= target {
+            return Ok(());
+        }
+        let rent_due = Rent::get()?
+            .minimum_balance(target)
+            .saturating_sub(cfg.lamports());
+        transfer_lamports(&ctx.accounts.payer.to_account_info(), &cfg, rent_due)?;
+        cfg.realloc(target, true)?;
+        msg!("migrated config to {} bytes", target);
+        Ok(())
+    }
+
+    /// Grows a receipt written under an older, shorter layout to
+    /// `Receipt::SIZE` (permissionless; `payer` funds the extra rent).
+    /// Appended fields read as zero: category 0, `Unverified`, no attestor.
+    /// See `check_receipt_migratable` for the accounts it refuses.
+    pub fn migrate_receipt(
+        ctx: Context<MigrateReceipt>,
+        _emitter: [u8; 32],
+        _sequence: u64,
+    ) -> Result<()> {
+        let receipt = ctx.accounts.receipt.to_account_info();
+        check_receipt_migratable(receipt.owner, &receipt.try_borrow_data()?)?;
+
+        let target = 8 + Receipt::SIZE;
+        let rent_due = Rent::get()?
+            .minimum_balance(target)
+            .saturating_sub(receipt.lamports());
+        transfer_lamports(&ctx.accounts.payer.to_account_info(), &receipt, rent_due)?;
+        receipt.realloc(target, true)?;
+        msg!("migrated receipt {} to {} bytes", receipt.key(), target);
+        Ok(())
+    }
+
+    /// Grows an EmitterCursor written under an older, shorter layout to
+    /// `EmitterCursor::SIZE` (permissionless; `payer` funds the extra rent).
+    /// `last_receipt_hash` reads as zero, so the next receipt recorded through
+    /// the cursor starts a new chain. Already-current cursors are left as is.
+    pub fn migrate_cursor(
+        ctx: Context<MigrateCursor>,
+        _emitter: [u8; 32],
+        _category: u16,
+    ) -> Result<()> {
+        let cursor = ctx.accounts.cursor.to_account_info();
+        require_keys_eq!(*cursor.owner, crate::ID, ZkError::CursorNotMigratable);
+        require!(
+            cursor.try_borrow_data()?.starts_with(EmitterCursor::DISCRIMINATOR),
+            ZkError::CursorNotMigratable
+        );
+
+        let target = 8 + EmitterCursor::SIZE;
+        if cursor.data_len() >= target {
+            return Ok(());
+        }
+        let rent_due = Rent::get()?
+            .minimum_balance(target)
+            .saturating_sub(cursor.lamports());
+        transfer_lamports(&ctx.accounts.payer.to_account_info(), &cursor, rent_due)?;
+        cursor.realloc(target, true)?;
+        msg!("migrated cursor {} to {} bytes", cursor.key(), target);
+        Ok(())
+    }
+
+    /// Grows an EmitterReceiptCount written before `total_recorded` and
+    /// `total_closed` to `EmitterReceiptCount::SIZE` (permissionless; `payer`
+    /// funds the extra rent). Both totals read as zero, counting from here.
+    /// Already-current counters are left as is.
+    pub fn migrate_emitter_count(ctx: Context<MigrateEmitterCount>, _emitter: [u8; 32]) -> Result<()> {
+        let counter = ctx.accounts.emitter_count.to_account_info();
+        require_keys_eq!(*counter.owner, crate::ID, ZkError::InvalidEmitterCountAccount);
+        require!(
+            counter.try_borrow_data()?.starts_with(EmitterReceiptCount::DISCRIMINATOR),
+            ZkError::InvalidEmitterCountAccount
+        );
+
+        let target = 8 + EmitterReceiptCount::SIZE;
+        if counter.data_len() >= target {
+            return Ok(());
+        }
+        let rent_due = Rent::get()?
+            .minimum_balance(target)
+            .saturating_sub(counter.lamports());
+        transfer_lamports(&ctx.accounts.payer.to_account_info(), &counter, rent_due)?;
+        counter.realloc(target, true)?;
+        msg!("migrated emitter count {} to {} bytes", counter.key(), target);
+        Ok(())
+    }
+
+    /// Records a receipt from a PostedVAA into the emitter's ring of `ring_size`
+    /// slots at `[RECEIPT_RING_SEED, emitter, (sequence % ring_size) as u32 BE]`,
+    /// evicting the older receipt occupying the slot. The stored `sequence` tells
+    /// which receipt a slot holds. Changing `ring_size` remaps slots.
+    pub fn record_receipt_ring(
+        ctx: Context<RecordReceiptRing>,
+        emitter: [u8; 32],
+        sequence: u64,
+        external_ref: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require!(ctx.accounts.cfg.ring_size > 0, ZkError::RingModeDisabled);
+        require!(!ctx.accounts.cfg.link_receipts, ZkError::UnlinkedRecordPath);
+        require!(!ctx.accounts.cfg.verify_inline, ZkError::InlineVerificationRequired);
+        require!(
+            !ctx.accounts.cfg.verify_signatures_independently,
+            ZkError::IndependentVerificationRequired
+        );
+        check_cpi_caller(
+            &ctx.accounts.cfg,
+            ctx.accounts.instructions.as_ref().map(|i| i.as_ref()),
+        )?;
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let decoded = validate_posted_vaa(
+            &ctx.accounts.cfg,
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.wormhole_program.key(),
+            &ctx.accounts.emitter_state,
+            &emitter,
+            Some(sequence),
+            clock.unix_timestamp,
+        )?;
+        require!(!ctx.accounts.cfg.require_expected_digest, ZkError::ExpectedDigestMissing);
+
+        // An eviction replaces a receipt of the same emitter: the count holds.
+        let emitter_receipt_count = if ctx.accounts.receipt.vaa_account == Pubkey::default() {
+            note_emitter_receipt(
+                &ctx.accounts.cfg,
+                ctx.accounts.emitter_count.as_mut(),
+                ctx.bumps.emitter_count,
+                &emitter,
+            )?
+        } else {
+            let counter = ctx.accounts.emitter_count.as_ref();
+            counter.ok_or(error!(ZkError::EmitterCountRequired))?.count
+        };
+        let payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+        index_payload_hash!(
+            ctx,
+            payer,
+            ctx.accounts.hash_index.as_ref().map(|i| i.as_ref()),
+            payload_hash,
+            emitter,
+            sequence
+        );
+        let slot = ctx.accounts.receipt.key();
+        let receipt = &mut ctx.accounts.receipt;
+        if receipt.vaa_account != Pubkey::default() {
+            require!(receipt.sequence < sequence, ZkError::RingSlotHoldsNewer);
+            emit_event!(ctx, ReceiptEvicted {
+                emitter,
+                evicted_sequence: receipt.sequence,
+                sequence,
+                slot,
+                event_seq: ctx.accounts.event_counter.next()?,
+            });
+        }
+
+        receipt.emitter = emitter;
+        receipt.sequence = sequence;
+        receipt.vaa_account = ctx.accounts.posted_vaa.key();
+        receipt.posted_timestamp = clock.unix_timestamp;
+        receipt.posted_slot = clock.slot;
+        receipt.bump = ctx.bumps.receipt;
+        receipt.payload_hash = payload_hash;
+        receipt.source = ReceiptSource::Wormhole;
+        receipt.proof_ref = [0u8; 32];
+        receipt.origin_batch_id = 0;
+        receipt.round_tripped = false;
+        receipt.nonce = decoded.nonce;
+        receipt.consistency_level = decoded.consistency_level;
+        receipt.hash_algo = ctx.accounts.cfg.hash_algo;
+        receipt.hash_prefix_skip = ctx.accounts.cfg.hash_prefix_skip;
+        receipt.category = 0;
+        receipt.status = ReceiptStatus::Unverified;
+        receipt.attested_by = [0u8; 32];
+        receipt.external_ref = external_ref.unwrap_or_default();
+        receipt.reported_cost = 0;
+        receipt.action = decoded.action();
+        receipt.vaa_body_hash = decoded.body_hash();
+        receipt.calldata_hash = [0u8; 32];
+        receipt.prev_hash = [0u8; 32];
+        receipt.field_root = ctx.accounts.cfg.field_root(&decoded, &receipt.payload_hash);
+        receipt.tombstoned = false;
+        receipt.tombstone_reason = 0;
+        receipt.reserved = [0u8; Receipt::RESERVED_LEN];
+        receipt.proof_verified = false;
+        let (vaa, nonce, vaa_body_hash) =
+            (receipt.vaa_account, receipt.nonce, receipt.vaa_body_hash);
+        ctx.accounts.stats.note_recorded(clock.slot, false)?;
+
+        let event_seq = ctx.accounts.event_counter.next()?;
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            event_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa,
+            nonce,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: external_ref.unwrap_or_default(),
+            reported_cost: 0,
+            vaa_body_hash,
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count,
+            action: decoded.action(),
+            event_seq,
+        });
+
+        Ok(())
+    }
+
+    /// Records a receipt from a PostedVAA at `[RECEIPT_HASH_SEED, vaa_hash]`,
+    /// for consumers that identify messages by VAA hash rather than emitter
+    /// and sequence, which the receipt stores. `vaa_hash` is
+    /// `DecodedVaa::body_hash`, the single keccak of the body Core Bridge keys
+    /// PostedVAAs by, not the double keccak guardians sign (EVM's `vm.hash`).
+    /// Runs the same checks as `record_receipt_from_vaa` and fails with
+    /// `VaaHashMismatch` unless `vaa_hash` is the VAA's. Coexists with the
+    /// sequence-keyed receipt: recording one does not create or check the
+    /// other. Close and tombstone it with `close_receipt_by_hash` and
+    /// `tombstone_receipt_by_hash`. Returns a Borsh `RecordOutcome`.
+    pub fn record_receipt_by_hash(
+        ctx: Context<RecordReceiptByHash>,
+        emitter: [u8; 32],
+        vaa_hash: [u8; 32],
+        external_ref: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.cfg.verify_inline, ZkError::InlineVerificationRequired);
+        require!(
+            !ctx.accounts.cfg.verify_signatures_independently,
+            ZkError::IndependentVerificationRequired
+        );
+        check_cpi_caller(
+            &ctx.accounts.cfg,
+            ctx.accounts.instructions.as_ref().map(|i| i.as_ref()),
+        )?;
+        if ctx.accounts.receipt.is_recorded() {
+            set_return_borsh(&RecordOutcome::AlreadyPresent)?;
+            return Ok(());
+        }
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let decoded = validate_posted_vaa(
+            &ctx.accounts.cfg,
+            &ctx.accounts.posted_vaa,
+            &ctx.accounts.wormhole_program.key(),
+            &ctx.accounts.emitter_state,
+            &emitter,
+            None,
+            clock.unix_timestamp,
+        )?;
+        require!(!ctx.accounts.cfg.require_expected_digest, ZkError::ExpectedDigestMissing);
+        require!(decoded.body_hash() == vaa_hash, ZkError::VaaHashMismatch);
+        let sequence = decoded.sequence;
+        let emitter_receipt_count = note_emitter_receipt(
+            &ctx.accounts.cfg,
+            ctx.accounts.emitter_count.as_mut(),
+            ctx.bumps.emitter_count,
+            &emitter,
+        )?;
+        let payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+        index_payload_hash!(
+            ctx,
+            payer,
+            ctx.accounts.hash_index.as_ref().map(|i| i.as_ref()),
+            payload_hash,
+            emitter,
+            sequence
+        );
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.emitter = emitter;
+        receipt.sequence = sequence;
+        receipt.vaa_account = ctx.accounts.posted_vaa.key();
+        receipt.posted_timestamp = clock.unix_timestamp;
+        receipt.posted_slot = clock.slot;
+        receipt.bump = ctx.bumps.receipt;
+        receipt.payload_hash = payload_hash;
+        receipt.source = ReceiptSource::Wormhole;
+        receipt.proof_ref = [0u8; 32];
+        receipt.origin_batch_id = 0;
+        receipt.round_tripped = false;
+        receipt.nonce = decoded.nonce;
+        receipt.consistency_level = decoded.consistency_level;
+        receipt.hash_algo = ctx.accounts.cfg.hash_algo;
+        receipt.hash_prefix_skip = ctx.accounts.cfg.hash_prefix_skip;
+        receipt.category = 0;
+        receipt.status = ReceiptStatus::Unverified;
+        receipt.attested_by = [0u8; 32];
+        receipt.external_ref = external_ref.unwrap_or_default();
+        receipt.reported_cost = 0;
+        receipt.action = decoded.action();
+        receipt.vaa_body_hash = vaa_hash;
+        receipt.calldata_hash = [0u8; 32];
+        receipt.prev_hash = [0u8; 32];
+        receipt.field_root = ctx.accounts.cfg.field_root(&decoded, &receipt.payload_hash);
+        receipt.tombstoned = false;
+        receipt.tombstone_reason = 0;
+        receipt.reserved = [0u8; Receipt::RESERVED_LEN];
+        receipt.proof_verified = false;
+        let (vaa, nonce, vaa_body_hash) =
+            (receipt.vaa_account, receipt.nonce, receipt.vaa_body_hash);
+        ctx.accounts.stats.note_recorded(clock.slot, false)?;
+
+        let event_seq = ctx.accounts.event_counter.next()?;
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            event_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa,
+            nonce,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: external_ref.unwrap_or_default(),
+            reported_cost: 0,
+            vaa_body_hash,
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count,
+            action: decoded.action(),
+            event_seq,
+        });
+
+        set_return_borsh(&RecordOutcome::NewlyRecorded)?;
+        Ok(())
+    }
+
+    /// Records a receipt without VAA (admin only). Returns a Borsh
+    /// `RecordOutcome`, like `record_receipt_from_vaa`. Rejected while
+    /// `cfg.verify_inline` is set, as there is no proof to check.
+    pub fn record_receipt_direct(
+        ctx: Context<RecordReceiptDirect>,
+        emitter: [u8; 32],
+        sequence: u64,
+        external_ref: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        require!(!ctx.accounts.cfg.link_receipts, ZkError::UnlinkedRecordPath);
+        require!(!ctx.accounts.cfg.verify_inline, ZkError::InlineVerificationRequired);
+        let receipt_bump =
+            check_receipt_pda(&ctx.accounts.receipt.to_account_info(), &emitter, sequence)?;
+        if ctx.accounts.receipt.is_recorded() {
+            set_return_borsh(&RecordOutcome::AlreadyPresent)?;
+            return Ok(());
+        }
+
+        let emitter_receipt_count = note_emitter_receipt(
+            &ctx.accounts.cfg,
+            ctx.accounts.emitter_count.as_mut(),
+            ctx.bumps.emitter_count,
+            &emitter,
+        )?;
+
+        let r = &mut ctx.accounts.receipt;
+        r.emitter = emitter;
+        r.sequence = sequence;
+        r.vaa_account = Pubkey::default();
+        let clock = Clock::clone(&ctx.accounts.clock);
+        r.posted_timestamp = clock.unix_timestamp;
+        r.posted_slot = clock.slot;
+        r.bump = receipt_bump;
+        r.payload_hash = [0u8; 32];
+        r.source = ReceiptSource::Direct;
+        r.proof_ref = [0u8; 32];
+        r.origin_batch_id = 0;
+        r.round_tripped = false;
+        r.nonce = 0;
+        r.consistency_level = 0;
+        r.hash_algo = HASH_ALGO_KECCAK256;
+        r.hash_prefix_skip = 0;
+        r.category = 0;
+        r.status = ReceiptStatus::Unverified;
+        r.attested_by = [0u8; 32];
+        r.external_ref = external_ref.unwrap_or_default();
+        r.reported_cost = 0;
+        r.action = 0;
+        r.vaa_body_hash = [0u8; 32];
+        r.calldata_hash = [0u8; 32];
+        r.prev_hash = [0u8; 32];
+        r.field_root = [0u8; 32];
+        r.tombstoned = false;
+        r.tombstone_reason = 0;
+        r.reserved = [0u8; Receipt::RESERVED_LEN];
+        r.proof_verified = false;
+        let vaa = r.vaa_account;
+        ctx.accounts.stats.note_recorded(clock.slot, true)?;
+
+        let event_seq = ctx.accounts.event_counter.next()?;
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            event_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa,
+            nonce: 0,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: external_ref.unwrap_or_default(),
+            reported_cost: 0,
+            vaa_body_hash: [0u8; 32],
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count,
+            action: 0,
+            event_seq,
+        });
+
+        set_return_borsh(&RecordOutcome::NewlyRecorded)?;
+        Ok(())
+    }
+
+    /// Records a receipt verified off-chain by a trusted verifier (operator or admin).
+    /// Rejected while `cfg.verify_inline` is set, as there is no proof to check.
+    pub fn record_receipt_with_hash(
+        ctx: Context<RecordReceiptWithHash>,
+        emitter: [u8; 32],
+        sequence: u64,
+        payload_hash: [u8; 32],
+        proof_ref: [u8; 32],
+        external_ref: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let signer = ctx.accounts.authority.key();
+        require!(
+            signer == cfg.admin || (cfg.operator != Pubkey::default() && signer == cfg.operator),
+            ZkError::NotOperator
+        );
+        require!(!cfg.link_receipts, ZkError::UnlinkedRecordPath);
+        require!(!cfg.verify_inline, ZkError::InlineVerificationRequired);
+
+        let receipt_bump =
+            check_receipt_pda(&ctx.accounts.receipt.to_account_info(), &emitter, sequence)?;
+        // Re-recording an existing receipt replaces it: the count holds.
+        let emitter_receipt_count = if ctx.accounts.receipt.is_recorded() {
+            let counter = ctx.accounts.emitter_count.as_ref();
+            counter.ok_or(error!(ZkError::EmitterCountRequired))?.count
+        } else {
+            note_emitter_receipt(
+                &ctx.accounts.cfg,
+                ctx.accounts.emitter_count.as_mut(),
+                ctx.bumps.emitter_count,
+                &emitter,
+            )?
+        };
+        index_payload_hash!(
+            ctx,
+            authority,
+            ctx.accounts.hash_index.as_ref().map(|i| i.as_ref()),
+            payload_hash,
+            emitter,
+            sequence
+        );
+
+        let r = &mut ctx.accounts.receipt;
+        r.emitter = emitter;
+        r.sequence = sequence;
+        r.vaa_account = Pubkey::default();
+        let clock = Clock::clone(&ctx.accounts.clock);
+        r.posted_timestamp = clock.unix_timestamp;
+        r.posted_slot = clock.slot;
+        r.bump = receipt_bump;
+        r.payload_hash = payload_hash;
+        r.source = ReceiptSource::External;
+        r.proof_ref = proof_ref;
+        r.origin_batch_id = 0;
+        r.round_tripped = false;
+        r.nonce = 0;
+        r.consistency_level = 0;
+        r.hash_algo = ctx.accounts.cfg.hash_algo;
+        r.hash_prefix_skip = 0;
+        r.category = 0;
+        r.status = ReceiptStatus::Unverified;
+        r.attested_by = [0u8; 32];
+        r.external_ref = external_ref.unwrap_or_default();
+        r.reported_cost = 0;
+        r.action = 0;
+        r.vaa_body_hash = [0u8; 32];
+        r.calldata_hash = [0u8; 32];
+        r.prev_hash = [0u8; 32];
+        r.field_root = [0u8; 32];
+        r.tombstoned = false;
+        r.tombstone_reason = 0;
+        r.reserved = [0u8; Receipt::RESERVED_LEN];
+        r.proof_verified = false;
+        let vaa = r.vaa_account;
+        ctx.accounts.stats.note_recorded(clock.slot, false)?;
+
+        let event_seq = ctx.accounts.event_counter.next()?;
+        push_event_ring(
+            &ctx.accounts.cfg,
+            ctx.accounts.event_ring.as_ref(),
+            &emitter,
+            sequence,
+            event_seq,
+            clock.slot,
+        )?;
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa,
+            nonce: 0,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: external_ref.unwrap_or_default(),
+            reported_cost: 0,
+            vaa_body_hash: [0u8; 32],
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count,
+            action: 0,
+            event_seq,
+        });
+
+        Ok(())
+    }
+
+    /// Tombstones a receipt (admin only): it stays on chain for audit, but
+    /// `attest_receipt`, `verify_inclusion` and the sequence checks reject it.
+    /// `tombstoned = false` lifts the tombstone and clears the reason.
+    pub fn tombstone_receipt(
+        ctx: Context<TombstoneReceipt>,
+        _emitter: [u8; 32],
+        _sequence: u64,
+        tombstoned: bool,
+        reason: u8,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        throttle_config_change!(ctx);
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.tombstoned = tombstoned;
+        receipt.tombstone_reason = if tombstoned { reason } else { 0 };
+
+        emit_event!(ctx, ReceiptTombstoned {
+            emitter: receipt.emitter,
+            sequence: receipt.sequence,
+            tombstoned,
+            reason: receipt.tombstone_reason,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// `tombstone_receipt` for a hash-keyed receipt from `record_receipt_by_hash`.
+    pub fn tombstone_receipt_by_hash(
+        ctx: Context<TombstoneReceiptByHash>,
+        _vaa_hash: [u8; 32],
+        tombstoned: bool,
+        reason: u8,
+    ) -> Result<()> {
+        require_keys_eq!(ctx.accounts.cfg.admin, ctx.accounts.admin.key(), ZkError::NotAdmin);
+        throttle_config_change!(ctx);
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.tombstoned = tombstoned;
+        receipt.tombstone_reason = if tombstoned { reason } else { 0 };
+
+        emit_event!(ctx, ReceiptTombstoned {
+            emitter: receipt.emitter,
+            sequence: receipt.sequence,
+            tombstoned,
+            reason: receipt.tombstone_reason,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Marks a receipt `Verified` by `attestor` (operator or admin), which
+    /// must be the Ed25519 key of `cfg.admin` or `cfg.operator`. The
+    /// transaction must include an Ed25519 program instruction verifying
+    /// `signature` by `attestor` over `Receipt::attestation_message`, which
+    /// tags the receipt with this program and the attestation purpose so the
+    /// signature cannot be replayed to another program or use. The receipt
+    /// must be at least `cfg.min_verify_age_seconds` old.
+    pub fn attest_receipt(
+        ctx: Context<AttestReceipt>,
+        _emitter: [u8; 32],
+        _sequence: u64,
+        signature: [u8; 64],
+        attestor: [u8; 32],
+    ) -> Result<()> {
+        let cfg = &ctx.accounts.cfg;
+        let signer = ctx.accounts.authority.key();
+        require!(
+            signer == cfg.admin || (cfg.operator != Pubkey::default() && signer == cfg.operator),
+            ZkError::NotOperator
+        );
+
+        require!(
+            attestor == cfg.admin.to_bytes()
+                || (cfg.operator != Pubkey::default() && attestor == cfg.operator.to_bytes()),
+            ZkError::AttestorNotAllowed
+        );
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.require_live()?;
+        let age_seconds = Clock::get()?.unix_timestamp.saturating_sub(receipt.posted_timestamp);
+        require!(age_seconds >= cfg.min_verify_age_seconds, ZkError::VerifyTooSoon);
+        require!(
+            attest::has_verified_signature(
+                &ctx.accounts.instructions,
+                &attestor,
+                &signature,
+                &receipt.attestation_message(),
+            ),
+            ZkError::AttestationInvalid
+        );
+        receipt.status = ReceiptStatus::Verified;
+        receipt.attested_by = attestor;
+
+        emit_event!(ctx, ReceiptAttested {
+            emitter: receipt.emitter,
+            sequence: receipt.sequence,
+            attested_by: attestor,
+            age_seconds,
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+
+    /// Records receipts for `sequences` from `(posted_vaa, receipt)` pairs in
+    /// `remaining_accounts`, optionally followed by one HashIndex PDA per
+    /// sequence, in order (required with `cfg.require_hash_index`). With
+    /// `skip_existing`, already-recorded receipts are skipped and a bitmask of
+    /// newly recorded indices is returned; otherwise any existing receipt
+    /// fails the whole batch.
+    pub fn record_receipts_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecordReceiptsBatch<'info>>,
+        emitter: [u8; 32],
+        sequences: Vec<u64>,
+        skip_existing: bool,
+    ) -> Result<()> {
+        require!(!sequences.is_empty(), ZkError::NoReceiptsProvided);
+        require!(!ctx.accounts.cfg.link_receipts, ZkError::UnlinkedRecordPath);
+        require!(!ctx.accounts.cfg.verify_inline, ZkError::InlineVerificationRequired);
+        require!(
+            !ctx.accounts.cfg.verify_signatures_independently,
+            ZkError::IndependentVerificationRequired
+        );
+        check_cpi_caller(
+            &ctx.accounts.cfg,
+            ctx.accounts.instructions.as_ref().map(|i| i.as_ref()),
+        )?;
+        require!(
+            sequences.len() <= ctx.accounts.cfg.max_batch_size as usize,
+            ZkError::BatchTooLarge
+        );
+        let with_index = ctx.remaining_accounts.len() == sequences.len() * 3;
+        require!(
+            with_index || ctx.remaining_accounts.len() == sequences.len() * 2,
+            ZkError::BatchAccountsMismatch
+        );
+        let (pairs, hash_indexes) = ctx.remaining_accounts.split_at(sequences.len() * 2);
+
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let now = clock.unix_timestamp;
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let mut recorded = vec![0u8; (sequences.len() + 7) / 8];
+
+        for (i, (&sequence, pair)) in sequences
+            .iter()
+            .zip(pairs.chunks(2))
+            .enumerate()
+        {
+            let (posted_vaa, receipt_info) = (&pair[0], &pair[1]);
+            let bump = check_receipt_pda(receipt_info, &emitter, sequence)?;
+
+            let exists = receipt_info.owner == &crate::ID && !receipt_info.data_is_empty();
+            if exists && load_receipt(receipt_info).map_or(true, |r| r.is_recorded()) {
+                require!(skip_existing, ZkError::ReceiptAlreadyRecorded);
+                continue;
+            }
+
+            let decoded = validate_posted_vaa(
+                &ctx.accounts.cfg,
+                posted_vaa,
+                &wormhole_program,
+                &ctx.accounts.emitter_state,
+                &emitter,
+                Some(sequence),
+                now,
+            )?;
+            require!(!ctx.accounts.cfg.require_expected_digest, ZkError::ExpectedDigestMissing);
+            let emitter_receipt_count = note_emitter_receipt(
+                &ctx.accounts.cfg,
+                ctx.accounts.emitter_count.as_mut(),
+                ctx.bumps.emitter_count,
+                &emitter,
+            )?;
+            if !exists {
+                create_receipt_account(
+                    receipt_info,
+                    &ctx.accounts.payer.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                    &emitter,
+                    sequence,
+                    bump,
+                )?;
+            }
+
+            let payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+            index_payload_hash!(ctx, payer, hash_indexes.get(i), payload_hash, emitter, sequence);
+            let receipt = Receipt {
+                emitter,
+                sequence,
+                vaa_account: posted_vaa.key(),
+                posted_timestamp: now,
+                bump,
+                payload_hash,
+                source: ReceiptSource::Wormhole,
+                proof_ref: [0u8; 32],
+                origin_batch_id: 0,
+                round_tripped: false,
+                posted_slot: clock.slot,
+                nonce: decoded.nonce,
+                consistency_level: decoded.consistency_level,
+                hash_algo: ctx.accounts.cfg.hash_algo,
+                hash_prefix_skip: ctx.accounts.cfg.hash_prefix_skip,
+                category: 0,
+                status: ReceiptStatus::Unverified,
+                attested_by: [0u8; 32],
+                proof_verified: false,
+                external_ref: [0u8; 32],
+                reported_cost: 0,
+                action: decoded.action(),
+                vaa_body_hash: decoded.body_hash(),
+                calldata_hash: [0u8; 32],
+                tombstoned: false,
+                tombstone_reason: 0,
+                prev_hash: [0u8; 32],
+                field_root: ctx.accounts.cfg.field_root(&decoded, &payload_hash),
+                reserved: [0u8; Receipt::RESERVED_LEN],
+            };
+            let mut data = receipt_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            receipt.try_serialize(&mut writer)?;
+            ctx.accounts.stats.note_recorded(clock.slot, false)?;
+
+            let event_seq = ctx.accounts.event_counter.next()?;
+            push_event_ring(
+                &ctx.accounts.cfg,
+                ctx.accounts.event_ring.as_ref(),
+                &emitter,
+                sequence,
+                event_seq,
+                clock.slot,
+            )?;
+            emit_receipt_recorded!(ctx, ReceiptRecorded {
+                emitter,
+                sequence,
+                vaa: receipt.vaa_account,
+                nonce: receipt.nonce,
+                category: 0,
+                chain_label: ctx.accounts.cfg.chain_label,
+                external_ref: [0u8; 32],
+                reported_cost: 0,
+                vaa_body_hash: receipt.vaa_body_hash,
+                calldata_hash: [0u8; 32],
+                emitter_receipt_count,
+                action: receipt.action,
+                event_seq,
+            });
+            recorded[i / 8] |= 1 << (i % 8);
+        }
+
+        set_return_bytes(&recorded)?;
+        Ok(())
+    }
+
+    /// Allocates the receipt PDAs for sequences `start..start + count` of an
+    /// allowlisted `emitter` ahead of time, in `remaining_accounts` order, so
+    /// recording them later skips the account creation. No VAA is checked:
+    /// each receipt only carries its key and `status = Preallocated`, which
+    /// `is_recorded` and `require_live` treat as absent. Accounts that
+    /// already exist are skipped. `count` is bounded by `max_batch_size`.
+    pub fn preallocate_receipts<'info>(
+        ctx: Context<'_, '_, '_, 'info, PreallocateReceipts<'info>>,
+        emitter: [u8; 32],
+        start: u64,
+        count: u16,
+    ) -> Result<()> {
+        require!(count > 0, ZkError::NoReceiptsProvided);
+        let cfg = &ctx.accounts.cfg;
+        require!(count <= cfg.max_batch_size, ZkError::BatchTooLarge);
+        require!(cfg.emitter_allowed(&emitter), ZkError::EmitterAddressMismatch);
+        require!(
+            ctx.remaining_accounts.len() == count as usize,
+            ZkError::BatchAccountsMismatch
+        );
+        start
+            .checked_add(u64::from(count) - 1)
+            .ok_or(error!(ZkError::InvalidSequenceRange))?;
+
+        for (sequence, receipt_info) in (start..).zip(ctx.remaining_accounts) {
+            let bump = check_receipt_pda(receipt_info, &emitter, sequence)?;
+            if receipt_info.owner == &crate::ID && !receipt_info.data_is_empty() {
+                continue;
+            }
+            create_receipt_account(
+                receipt_info,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &emitter,
+                sequence,
+                bump,
+            )?;
+            let receipt = Receipt {
+                emitter,
+                sequence,
+                vaa_account: Pubkey::default(),
+                posted_timestamp: 0,
+                bump,
+                payload_hash: [0u8; 32],
+                source: ReceiptSource::Wormhole,
+                proof_ref: [0u8; 32],
+                origin_batch_id: 0,
+                round_tripped: false,
+                posted_slot: 0,
+                nonce: 0,
+                consistency_level: 0,
+                hash_algo: 0,
+                hash_prefix_skip: 0,
+                category: 0,
+                status: ReceiptStatus::Preallocated,
+                attested_by: [0u8; 32],
+                proof_verified: false,
+                external_ref: [0u8; 32],
+                reported_cost: 0,
+                action: 0,
+                vaa_body_hash: [0u8; 32],
+                calldata_hash: [0u8; 32],
+                tombstoned: false,
+                tombstone_reason: 0,
+                prev_hash: [0u8; 32],
+                field_root: [0u8; 32],
+                reserved: [0u8; Receipt::RESERVED_LEN],
+            };
+            let mut data = receipt_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            receipt.try_serialize(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Records `count` receipts for the gap-free run starting at
+    /// `start_sequence`, from `(posted_vaa, receipt)` pairs in
+    /// `remaining_accounts`. Every VAA must carry `emitter` and sequence
+    /// `start_sequence + i`; the first that does not fails the whole run with
+    /// `ContiguousRunBroken`, its index logged. Then records as
+    /// `record_receipts_batch` without `skip_existing`, HashIndex PDAs
+    /// included.
+    pub fn record_contiguous_run<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecordReceiptsBatch<'info>>,
+        emitter: [u8; 32],
+        start_sequence: u64,
+        count: u32,
+    ) -> Result<()> {
+        require!(count > 0, ZkError::NoReceiptsProvided);
+        require!(
+            ctx.remaining_accounts.len() == count as usize * 2
+                || ctx.remaining_accounts.len() == count as usize * 3,
+            ZkError::BatchAccountsMismatch
+        );
+        let end = start_sequence
+            .checked_add(u64::from(count))
+            .ok_or(error!(ZkError::ContiguousRunBroken))?;
+
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        for (i, pair) in ctx.remaining_accounts[..count as usize * 2].chunks(2).enumerate() {
+            let posted_vaa = &pair[0];
+            check_posted_vaa_owner(posted_vaa, &wormhole_program)?;
+            let decoded = vaa::decode_posted_vaa(posted_vaa)?;
+            if decoded.emitter_address != emitter || decoded.sequence != start_sequence + i as u64 {
+                msg!(
+                    "contiguous run broken at index {}: sequence {} (expected {})",
+                    i,
+                    decoded.sequence,
+                    start_sequence + i as u64
+                );
+                return err!(ZkError::ContiguousRunBroken);
+            }
+        }
+
+        record_receipts_batch(ctx, emitter, (start_sequence..end).collect(), false)
+    }
+
+    /// Test-only round trip: posts `payload` through Core Bridge, then records
+    /// the test-injected `posted_vaa` for it, asserting it carries our emitter,
+    /// the sequence just posted and the same payload. Skips the emitter
+    /// allowlist and freshness checks, which would reject our own emitter.
+    #[cfg(feature = "test-helpers")]
+    pub fn post_and_record_test(
+        ctx: Context<PostAndRecordTest>,
+        batch_id: u32,
+        payload: Vec<u8>,
+        sequence: u64,
+    ) -> Result<()> {
+        let wormhole_program = ctx.accounts.wormhole_program.key();
+        let next_sequence =
+            read_sequence_tracker(&ctx.accounts.sequence, &wormhole_program)?.unwrap_or(0);
+        require!(next_sequence == sequence, ZkError::TestRoundTripMismatch);
+
+        let core = CorePdas::derive(wormhole_program, false);
+        let fee = read_bridge_fee(&ctx.accounts.config, &core)?;
+        transfer_lamports(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.fee_collector.to_account_info(),
+            fee,
+        )?;
+
+        let cpi_accounts = wormhole::instructions::PostMessage {
+            config:         ctx.accounts.config.to_account_info(),
+            message:        ctx.accounts.message.to_account_info(),
+            emitter:        ctx.accounts.emitter.to_account_info(),
+            sequence:       ctx.accounts.sequence.to_account_info(),
+            payer:          ctx.accounts.payer.to_account_info(),
+            fee_collector:  ctx.accounts.fee_collector.to_account_info(),
+            clock:          ctx.accounts.clock.to_account_info(),
+            rent:           ctx.accounts.rent.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let bump_arr = [ctx.bumps.emitter];
+        let emitter_seeds: [&[u8]; 2] = [b"emitter", &bump_arr];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.wormhole_program.to_account_info(),
+            cpi_accounts,
+            &[&emitter_seeds],
+        );
+        wormhole::instructions::post_message(
+            cpi_ctx,
+            batch_id,
+            payload.clone(),
+            wormhole::types::Finality::Confirmed,
+        )?;
+        require!(
+            posted_sequence(&ctx.accounts.sequence, &wormhole_program)? == sequence,
+            ZkError::TestRoundTripMismatch
+        );
+
+        let posted_vaa = &ctx.accounts.posted_vaa;
+        check_posted_vaa_owner(posted_vaa, &wormhole_program)?;
+        let decoded = vaa::decode_posted_vaa(posted_vaa)?;
+        let emitter = ctx.accounts.emitter.key().to_bytes();
+        require!(decoded.emitter_address == emitter, ZkError::VaaEmitterMismatch);
+        require!(
+            decoded.sequence == sequence && decoded.payload == payload,
+            ZkError::TestRoundTripMismatch
+        );
+
+        let clock = Clock::clone(&ctx.accounts.clock);
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.emitter = emitter;
+        receipt.sequence = sequence;
+        receipt.vaa_account = posted_vaa.key();
+        receipt.posted_timestamp = clock.unix_timestamp;
+        receipt.posted_slot = clock.slot;
+        receipt.bump = ctx.bumps.receipt;
+        receipt.payload_hash = ctx.accounts.cfg.payload_hash(&decoded)?;
+        receipt.source = ReceiptSource::Wormhole;
+        receipt.proof_ref = [0u8; 32];
+        receipt.origin_batch_id = 0;
+        receipt.round_tripped = false;
+        receipt.nonce = decoded.nonce;
+        receipt.consistency_level = decoded.consistency_level;
+        receipt.hash_algo = ctx.accounts.cfg.hash_algo;
+        receipt.hash_prefix_skip = ctx.accounts.cfg.hash_prefix_skip;
+        receipt.category = 0;
+        receipt.status = ReceiptStatus::Unverified;
+        receipt.attested_by = [0u8; 32];
+        receipt.proof_verified = false;
+        receipt.external_ref = [0u8; 32];
+        receipt.reported_cost = 0;
+        receipt.action = decoded.action();
+        let vaa_body_hash = decoded.body_hash();
+        receipt.vaa_body_hash = vaa_body_hash;
+        receipt.calldata_hash = [0u8; 32];
+        receipt.prev_hash = [0u8; 32];
+        receipt.field_root = ctx.accounts.cfg.field_root(&decoded, &receipt.payload_hash);
+        receipt.tombstoned = false;
+        receipt.tombstone_reason = 0;
+        receipt.reserved = [0u8; Receipt::RESERVED_LEN];
+        ctx.accounts.stats.note_recorded(clock.slot, false)?;
+
+        emit_receipt_recorded!(ctx, ReceiptRecorded {
+            emitter,
+            sequence,
+            vaa: posted_vaa.key(),
+            nonce: decoded.nonce,
+            category: 0,
+            chain_label: ctx.accounts.cfg.chain_label,
+            external_ref: [0u8; 32],
+            reported_cost: 0,
+            vaa_body_hash,
+            calldata_hash: [0u8; 32],
+            emitter_receipt_count: 0,
+            action: decoded.action(),
+            event_seq: ctx.accounts.event_counter.next()?,
+        });
+        Ok(())
+    }
+}
+
+/// Writes a fresh config with default policy and zeroed event counter and stats.
+fn init_config(
+    cfg: &mut ReceiptConfig,
+    counter: &mut EventCounter,
+    stats: &mut StatsAccount,
+    admin: Pubkey,
+    (evm_chain, chain_label, evm_emitters): (u16, [u8; 16], bool),
+    emitter: RemoteEmitter,
+    (cfg_bump, counter_bump, stats_bump): (u8, u8, u8),
+) -> Result<()> {
+    // Derive our emitter and its Core Bridge sequence tracker once; later
+    // checks read the cached values.
+    let (local_pda, emitter_bump) = Pubkey::find_program_address(&[b"emitter"], &crate::ID);
+    let local = LocalEmitter(local_pda.to_bytes());
+    cfg.cached_emitter_address = local;
+    cfg.emitter_bump = emitter_bump;
+    cfg.shadow_emitter_bump =
+        Pubkey::find_program_address(&[SHADOW_EMITTER_SEED], &crate::ID).1;
+    cfg.cached_sequence =
+        Pubkey::find_program_address(&[SEQUENCE_SEED, &local.0], &Wormhole::id()).0;
+    check_not_local_emitter(&local, &emitter)?;
+    check_emitter_format(evm_emitters, &emitter)?;
+    check_chain_label(&chain_label)?;
+    cfg.admin = admin;
+    cfg.evm_chain = evm_chain;
+    cfg.emitter = emitter;
+    cfg.bump = cfg_bump;
+    cfg.finalized_min_payload_len = 0;
+    cfg.receipt_reaper = Pubkey::default();
+    cfg.min_age_seconds = 0;
+    cfg.strict_fee_collector = true;
+    cfg.max_vaa_age_seconds = 0;
+    cfg.clock_skew_tolerance_seconds = DEFAULT_CLOCK_SKEW_TOLERANCE_SECONDS;
+    cfg.fee_asset = FeeAsset::Sol;
+    cfg.track_attempts = false;
+    cfg.timelock_seconds = 0;
+    cfg.ring_size = 0;
+    cfg.budget_per_window = 0;
+    cfg.budget_window_seconds = 0;
+    cfg.skip_post_payload_hash = false;
+    cfg.min_record_consistency = 0;
+    cfg.posting_paused = false;
+    cfg.hash_algo = HASH_ALGO_KECCAK256;
+    cfg.enforce_length_prefix = false;
+    cfg.allowed_categories = [0u16; ReceiptConfig::MAX_CATEGORIES];
+    cfg.allowed_category_count = 0;
+    cfg.extra_emitters = [RemoteEmitter([0u8; 32]); ReceiptConfig::MAX_EMITTERS - 1];
+    cfg.extra_emitter_count = 0;
+    cfg.verbose = false;
+    cfg.max_batch_size = MAX_BATCH_SIZE_CEILING;
+    cfg.require_pda_message = false;
+    cfg.per_category_cursors = false;
+    cfg.treasury_allowlist = [Pubkey::default(); ReceiptConfig::MAX_TREASURIES];
+    cfg.last_config_change_slot = 0;
+    cfg.config_change_cooldown_slots = 0;
+    cfg.chain_label = chain_label;
+    cfg.avoid_expiring_guardian_set = false;
+    cfg.expiration_buffer_seconds = 0;
+    cfg.allowed_waivers = [Pubkey::default(); ReceiptConfig::MAX_WAIVERS];
+    cfg.verify_inline = false;
+    cfg.enforce_cpi_callers = false;
+    cfg.allowed_cpi_callers = [Pubkey::default(); ReceiptConfig::MAX_CPI_CALLERS];
+    cfg.allow_unset_chain = false;
+    cfg.maintenance_until_slot = 0;
+    cfg.evm_emitters = evm_emitters;
+    cfg.fee_authority = Pubkey::default();
+    cfg.enforce_batch_monotonic = false;
+    cfg.compact_receipt_logs = false;
+    cfg.close_authority = Pubkey::default();
+    cfg.emitter_activation_delay_slots = 0;
+    cfg.emitter_active_at_slot = [0u64; ReceiptConfig::MAX_EMITTERS];
+    cfg.emitter_chains = [0u16; ReceiptConfig::MAX_EMITTERS];
+    cfg.in_flight = 0;
+    cfg.max_in_flight = 0;
+    cfg.hash_prefix_skip = 0;
+    cfg.wormhole_program_id = Wormhole::id();
+    cfg.min_post_interval_slots = 0;
+    cfg.reject_zero_nonce = false;
+    cfg.has_default_finality = false;
+    cfg.default_finality = 0;
+    cfg.max_receipts_per_emitter = 0;
+    cfg.enforce_framing = false;
+    cfg.verify_signatures_independently = false;
+    cfg.fee_slippage = 0;
+    cfg.reorg_tolerance = 0;
+    cfg.min_verify_age_seconds = 0;
+    cfg.store_field_root = false;
+    cfg.require_chain_pins = false;
+    cfg.require_expected_digest = false;
+    cfg.require_hash_index = false;
+    cfg.link_receipts = false;
+    cfg.event_ring_enabled = false;
+    cfg.abi_payload_words = 0;
+    cfg.reserved = [0u8; ReceiptConfig::RESERVED_LEN];
+
+    counter.global_event_seq = 0;
+    counter.bump = counter_bump;
+
+    stats.total_recorded = 0;
+    stats.total_direct = 0;
+    stats.total_closed = 0;
+    stats.last_recorded_slot = 0;
+    stats.bump = stats_bump;
+    Ok(())
+}
+
+/// With `cfg.enforce_cpi_callers`, a CPI into a record path must come from a
+/// top-level instruction of a program in `allowed_cpi_callers`. The runtime
+/// only exposes the top-level program, so deeper nesting is rejected.
+/// Top-level calls always pass.
+fn check_cpi_caller(cfg: &ReceiptConfig, instructions: Option<&AccountInfo>) -> Result<()> {
+    let height = get_stack_height();
+    if !cfg.enforce_cpi_callers || height == TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(());
+    }
+    require!(height == TRANSACTION_LEVEL_STACK_HEIGHT + 1, ZkError::CallerNotAllowed);
+    let instructions = instructions.ok_or(error!(ZkError::InstructionsSysvarRequired))?;
+    let caller = get_instruction_relative(0, instructions)?.program_id;
+    require!(
+        caller != Pubkey::default() && cfg.allowed_cpi_callers.contains(&caller),
+        ZkError::CallerNotAllowed
+    );
+    Ok(())
+}
+
+/// A chain label is printable ASCII, zero-padded on the right; all zeros
+/// means unset.
+fn check_chain_label(label: &[u8; 16]) -> Result<()> {
+    let len = label.iter().position(|b| *b == 0).unwrap_or(label.len());
+    require!(
+        label[..len].iter().all(|b| (0x20..=0x7e).contains(b))
+            && label[len..].iter().all(|b| *b == 0),
+        ZkError::InvalidChainLabel
+    );
+    Ok(())
+}
+
+/// Category tracked by an emitter's cursor: 0 unless `per_category_cursors`.
+fn cursor_category(cfg: &ReceiptConfig, category: u16) -> u16 {
+    if cfg.per_category_cursors {
+        category
+    } else {
+        0
+    }
+}
+
+/// Trailing EmitterCursor seed: `category_le` with `per_category_cursors`,
+/// else empty, which derives the same PDA as the two-seed per-emitter cursor.
+fn cursor_category_seed(cfg: &ReceiptConfig, category: u16) -> Vec<u8> {
+    if cfg.per_category_cursors {
+        category.to_le_bytes().to_vec()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Whether `sequence` is at most `cfg.reorg_tolerance` below a cursor at
+/// `last_sequence` (or equal to it), where `record_receipt_from_vaa` records
+/// it again after a source-chain reorg.
+fn in_reorg_window(cfg: &ReceiptConfig, last_sequence: u64, sequence: u64) -> bool {
+    cfg.reorg_tolerance > 0
+        && sequence <= last_sequence
+        && last_sequence - sequence <= cfg.reorg_tolerance
+}
+
+/// With `cfg.reorg_tolerance` set, finds `receipt`'s EmitterCursor PDA in
+/// `cursors` (`CursorRequired` if absent) and fails with
+/// `ReceiptInReorgWindow` if the receipt sits inside its reorg window. A
+/// cursor PDA that was never created has no window.
+fn check_close_outside_reorg_window(
+    cfg: &ReceiptConfig,
+    receipt: &Receipt,
+    cursors: &[AccountInfo],
+) -> Result<()> {
+    if cfg.reorg_tolerance == 0 {
+        return Ok(());
+    }
+    let (expected, _) = Pubkey::find_program_address(
+        &[
+            EMITTER_CURSOR_SEED,
+            receipt.emitter.as_ref(),
+            &cursor_category_seed(cfg, receipt.category),
+        ],
+        &crate::ID,
+    );
+    let info = cursors
+        .iter()
+        .find(|c| c.key() == expected)
+        .ok_or(error!(ZkError::CursorRequired))?;
+    if let Some(cursor) = load_emitter_cursor(info)? {
+        require!(
+            !in_reorg_window(cfg, cursor.last_sequence, receipt.sequence),
+            ZkError::ReceiptInReorgWindow
+        );
+    }
+    Ok(())
+}
+
+/// EmitterCursor behind `info`, or `None` if it was never created.
+fn load_emitter_cursor(info: &AccountInfo) -> Result<Option<EmitterCursor>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    Ok(Some(EmitterCursor::try_deserialize(&mut data_slice)?))
+}
+
+/// Rejects emitters that can never be valid allowlist entries: the all-zero
+/// address and our own emitters.
+fn check_allowlist_emitter(local: &LocalEmitter, emitter: &RemoteEmitter) -> Result<()> {
+    require!(emitter.0 != [0u8; 32], ZkError::ZeroEmitter);
+    check_not_local_emitter(local, emitter)
+}
+
+/// With `evm_emitters`, rejects emitters that are not a 20-byte EVM address
+/// left-padded to 32 bytes, as Wormhole encodes them; a right-padded address
+/// would never match a VAA.
+fn check_emitter_format(evm_emitters: bool, emitter: &RemoteEmitter) -> Result<()> {
+    require!(
+        !evm_emitters || emitter.0[..12].iter().all(|b| *b == 0),
+        ZkError::EmitterNotLeftPadded
+    );
+    Ok(())
+}
+
+/// Rejects our own emitters, primary (`local`) or shadow, as a remote one:
+/// recording our outbound posts as inbound receipts is a self-loop.
+fn check_not_local_emitter(local: &LocalEmitter, emitter: &RemoteEmitter) -> Result<()> {
+    require!(
+        emitter.0 != local.0 && emitter.0 != shadow_emitter_address().0,
+        ZkError::CannotRecordOwnEmitter
+    );
+    Ok(())
+}
+
+/// Records `batch_id` on the emitter's OutboundCursor, if one was passed. With
+/// `cfg.enforce_batch_monotonic` the cursor is required and `batch_id` must
+/// exceed the last one posted.
+fn advance_outbound_cursor(
+    cfg: &ReceiptConfig,
+    cursor: Option<&mut Account<OutboundCursor>>,
+    bump: Option<u8>,
+    batch_id: u32,
+) -> Result<()> {
+    let Some(cursor) = cursor else {
+        require!(!cfg.enforce_batch_monotonic, ZkError::OutboundCursorRequired);
+        return Ok(());
+    };
+    if cfg.enforce_batch_monotonic && cursor.has_posted {
+        require!(batch_id > cursor.last_batch_id, ZkError::BatchIdNotIncreasing);
+    }
+    cursor.last_batch_id = batch_id;
+    cursor.has_posted = true;
+    cursor.bump = bump.ok_or(error!(ZkError::OutboundCursorRequired))?;
+    Ok(())
+}
+
+/// Enforces `cfg.min_post_interval_slots` between posts through an emitter
+/// and stamps its OutboundRate with `slot`. The account is required while
+/// the interval is set; a never-used one (`last_post_slot` 0) always passes.
+fn check_post_interval(
+    cfg: &ReceiptConfig,
+    rate: Option<&mut Account<OutboundRate>>,
+    bump: Option<u8>,
+    slot: u64,
+) -> Result<()> {
+    let interval = cfg.min_post_interval_slots;
+    let Some(rate) = rate else {
+        require!(interval == 0, ZkError::OutboundRateRequired);
+        return Ok(());
+    };
+    if interval > 0 && rate.last_post_slot != 0 {
+        let since = slot.saturating_sub(rate.last_post_slot);
+        if since < interval {
+            msg!("post {} slots after the last one; minimum is {}", since, interval);
+            return err!(ZkError::PostTooSoon);
+        }
+    }
+    rate.last_post_slot = slot;
+    rate.bump = bump.ok_or(error!(ZkError::OutboundRateRequired))?;
+    Ok(())
+}
+
+/// Fails with `EmitterPaused` if `state` (the emitter's EmitterState PDA,
+/// checked by the caller's seeds) exists and is paused.
+fn check_emitter_not_paused(state: &AccountInfo) -> Result<()> {
+    if let Some(state) = load_emitter_state(state)? {
+        require!(!state.paused, ZkError::EmitterPaused);
+    }
+    Ok(())
+}
+
+/// Fails with `ActionNotAllowedForEmitter` unless the emitter's EmitterState
+/// allows `action`; a missing account allows every action.
+fn check_emitter_action(state: &AccountInfo, action: u8) -> Result<()> {
+    if let Some(state) = load_emitter_state(state)? {
+        require!(state.action_allowed(action), ZkError::ActionNotAllowedForEmitter);
+    }
+    Ok(())
+}
+
+/// EmitterState behind `state`, or `None` if it was never created.
+fn load_emitter_state(state: &AccountInfo) -> Result<Option<EmitterState>> {
+    if state.owner != &crate::ID || state.data_is_empty() {
+        return Ok(None);
+    }
+    let data = state.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    Ok(Some(EmitterState::try_deserialize(&mut data_slice)?))
+}
+
+/// Counts a new receipt for `emitter` on its EmitterReceiptCount and returns
+/// the count including it. The account is required (`check_emitter_cap`)
+/// and a full emitter fails with `EmitterReceiptCapReached`.
+fn note_emitter_receipt(
+    cfg: &ReceiptConfig,
+    counter: Option<&mut Account<EmitterReceiptCount>>,
+    bump: Option<u8>,
+    emitter: &[u8; 32],
+) -> Result<u64> {
+    check_emitter_cap(cfg, counter.as_ref().map(|c| c.count))?;
+    let counter = counter.ok_or(error!(ZkError::EmitterCountRequired))?;
+    // Every fallible step runs before the first write.
+    let bump = bump.ok_or(error!(ZkError::EmitterCountRequired))?;
+    let count = counter.count.checked_add(1).ok_or(error!(ZkError::StatsOverflow))?;
+    let total_recorded = counter
+        .total_recorded
+        .checked_add(1)
+        .ok_or(error!(ZkError::StatsOverflow))?;
+    counter.emitter = *emitter;
+    counter.count = count;
+    counter.total_recorded = total_recorded;
+    counter.bump = bump;
+    Ok(count)
+}
+
+/// Releases one receipt of `emitter` on its EmitterReceiptCount, created by
+/// the single close paths when an emitter dates from before counting was
+/// required. Like `note_emitter_receipt`, the account is always required.
+fn release_emitter_receipt(
+    counter: Option<&mut Account<EmitterReceiptCount>>,
+    bump: Option<u8>,
+    emitter: &[u8; 32],
+) -> Result<()> {
+    let counter = counter.ok_or(error!(ZkError::EmitterCountRequired))?;
+    counter.emitter = *emitter;
+    counter.bump = bump.ok_or(error!(ZkError::EmitterCountRequired))?;
+    counter.note_closed();
+    Ok(())
+}
+
+/// Advances what a receipt recorded by `record_receipt_from_vaa` moves:
+/// `cursor` to `sequence` when past it (a reorg-window receipt leaves it),
+/// the emitter's EmitterReceiptCount, the stats, and the event counter by
+/// `events` reserved sequences. Returns the first reserved event sequence and
+/// the emitter's count. Every fallible step runs before the first write, so
+/// after an error none of them has advanced.
+fn advance_record_state(
+    cfg: &ReceiptConfig,
+    cursor: Option<&mut EmitterCursor>,
+    emitter_count: Option<&mut Account<EmitterReceiptCount>>,
+    count_bump: Option<u8>,
+    stats: &mut StatsAccount,
+    event_counter: &mut EventCounter,
+    (emitter, sequence, slot, events): (&[u8; 32], u64, u64, u64),
+) -> Result<(u64, u64)> {
+    let seq = event_counter.global_event_seq;
+    let first_event_seq = seq.checked_add(1).ok_or(error!(ZkError::EventSeqOverflow))?;
+    let last_event_seq = seq.checked_add(events).ok_or(error!(ZkError::EventSeqOverflow))?;
+    let total_recorded = stats
+        .total_recorded
+        .checked_add(1)
+        .ok_or(error!(ZkError::StatsOverflow))?;
+    // The last fallible step; it writes the count only on success.
+    let emitter_receipt_count = note_emitter_receipt(cfg, emitter_count, count_bump, emitter)?;
+    if let Some(cursor) = cursor.filter(|c| sequence > c.last_sequence) {
+        cursor.last_sequence = sequence;
+    }
+    event_counter.global_event_seq = last_event_seq;
+    stats.total_recorded = total_recorded;
+    stats.last_recorded_slot = slot;
+    Ok((first_event_seq, emitter_receipt_count))
+}
+
+/// `cfg.max_receipts_per_emitter` for an emitter holding `held` receipts,
+/// `None` without its EmitterReceiptCount. The count is kept whether or not
+/// the cap is set, so a cap enabled later holds against the true number:
+/// `None` always fails with `EmitterCountRequired`, a full emitter with
+/// `EmitterReceiptCapReached`.
+fn check_emitter_cap(cfg: &ReceiptConfig, held: Option<u64>) -> Result<()> {
+    let held = held.ok_or(error!(ZkError::EmitterCountRequired))?;
+    let cap = cfg.max_receipts_per_emitter;
+    if cap > 0 && held >= cap {
+        msg!("emitter holds {} receipts; cap is {}", held, cap);
+        return err!(ZkError::EmitterReceiptCapReached);
+    }
+    Ok(())
+}
+
+/// EmitterCursor behind `info`, an EmitterCursor PDA for `emitter`, or `None`
+/// while it hasn't been created.
+fn load_cursor(info: &AccountInfo, emitter: &[u8; 32]) -> Result<Option<EmitterCursor>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    let cursor = EmitterCursor::try_deserialize(&mut data_slice)?;
+    require!(cursor.emitter == *emitter, ZkError::CursorEmitterMismatch);
+    Ok(Some(cursor))
+}
+
+/// EmitterReceiptCount behind `info`, which must be the PDA for the emitter
+/// it names.
+fn load_emitter_count(info: &AccountInfo) -> Result<EmitterReceiptCount> {
+    require_keys_eq!(*info.owner, crate::ID, ZkError::InvalidEmitterCountAccount);
+    let counter = {
+        let data = info.try_borrow_data()?;
+        let mut data_slice: &[u8] = &data;
+        EmitterReceiptCount::try_deserialize(&mut data_slice)
+            .map_err(|_| error!(ZkError::InvalidEmitterCountAccount))?
+    };
+    let expected = Pubkey::create_program_address(
+        &[EMITTER_COUNT_SEED, counter.emitter.as_ref(), &[counter.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(ZkError::InvalidEmitterCountAccount))?;
+    require_keys_eq!(info.key(), expected, ZkError::InvalidEmitterCountAccount);
+    Ok(counter)
+}
+
+/// Appends a `ReceiptRecorded` summary to the EventRing. Once
+/// `init_event_ring` has run (`cfg.event_ring_enabled`) the ring is
+/// required, so it misses no record; before that none exists to pass.
+fn push_event_ring(
+    cfg: &ReceiptConfig,
+    ring: Option<&AccountLoader<EventRing>>,
+    emitter: &[u8; 32],
+    sequence: u64,
+    event_seq: u64,
+    slot: u64,
+) -> Result<()> {
+    let Some(ring) = ring else {
+        require!(!cfg.event_ring_enabled, ZkError::EventRingRequired);
+        return Ok(());
+    };
+    ring.load_mut()?.push(EventRingEntry { emitter: *emitter, sequence, event_seq, slot });
+    Ok(())
+}
+
+/// Requires `sequence` to be the tracker Wormhole increments for `emitter`,
+/// `[SEQUENCE_SEED, emitter]` under the Core Bridge, so an unrelated (even
+/// valid) tracker cannot skew our sequence reads.
+fn check_sequence_for_emitter(
+    sequence: &AccountInfo,
+    emitter: &AccountInfo,
+    wormhole_program: &Pubkey,
+) -> Result<()> {
+    let (expected, _) =
+        Pubkey::find_program_address(&[SEQUENCE_SEED, emitter.key.as_ref()], wormhole_program);
+    require_keys_eq!(sequence.key(), expected, ZkError::WrongSequenceForEmitter);
+    Ok(())
+}
+
+/// Requires the post's sequence account to be the tracker cached at init
+/// (configs created before caching skip this; Core Bridge still checks it).
+fn check_cached_sequence(cfg: &ReceiptConfig, sequence: &AccountInfo) -> Result<()> {
+    if cfg.cached_sequence != Pubkey::default() {
+        require_keys_eq!(sequence.key(), cfg.cached_sequence, ZkError::SequenceAccountMismatch);
+    }
+    Ok(())
+}
+
+/// Default allowance for cross-chain clock skew in VAA freshness checks.
+pub const DEFAULT_CLOCK_SKEW_TOLERANCE_SECONDS: i64 = 30;
+
+/// Seed prefix for receipt PDAs: `[RECEIPT_SEED, emitter, receipt_sequence_seed(sequence)]`.
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+/// Canonical sequence encoding in receipt seeds: 8 bytes, big-endian.
+/// Clients deriving with `to_le_bytes` get mismatched PDAs ("receipt not found").
+pub fn receipt_sequence_seed(sequence: u64) -> [u8; 8] {
+    sequence.to_be_bytes()
+}
+
+/// Receipt PDA seeds (without bump) for runtime derivation and signing.
+pub fn receipt_seeds<'a>(emitter: &'a [u8; 32], sequence_seed: &'a [u8; 8]) -> [&'a [u8]; 3] {
+    [RECEIPT_SEED, emitter.as_ref(), sequence_seed.as_ref()]
+}
+
+/// Derives the receipt PDA for `(emitter, sequence)`.
+pub fn receipt_pda(emitter: &[u8; 32], sequence: u64) -> (Pubkey, u8) {
+    let seq = receipt_sequence_seed(sequence);
+    Pubkey::find_program_address(&receipt_seeds(emitter, &seq), &crate::ID)
+}
+
+/// Seed prefix for hash-keyed receipt PDAs: `[RECEIPT_HASH_SEED, vaa_hash]`.
+pub const RECEIPT_HASH_SEED: &[u8] = b"receipt_h";
+
+/// Seed prefix for ring-mode receipt PDAs.
+pub const RECEIPT_RING_SEED: &[u8] = b"receipt_ring";
+
+/// Ring slot seed for `sequence`: `(sequence % ring_size) as u32`, big-endian.
+/// A zero `ring_size` maps everything to slot 0 (ring mode is rejected anyway).
+pub fn receipt_ring_slot_seed(sequence: u64, ring_size: u32) -> [u8; 4] {
+    let slot = sequence.checked_rem(ring_size as u64).unwrap_or(0);
+    (slot as u32).to_be_bytes()
+}
+
+/// SPL Token program, used when `FeeAsset::Spl` is configured.
+pub const SPL_TOKEN_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Seed for the PendingChange PDA.
+pub const PENDING_CHANGE_SEED: &[u8] = b"pending_change";
+
+/// Seed prefix for PendingAction PDAs, followed by the action hash.
+pub const PENDING_ACTION_SEED: &[u8] = b"pending_action";
+
+/// Purpose tag leading `Receipt::attestation_message`.
+pub const ATTEST_RECEIPT_TAG: &[u8] = b"zkcb:attest_receipt:v1";
+
+/// Seed for the PosterConfig PDA.
+pub const POSTER_CONFIG_SEED: &[u8] = b"poster_cfg";
+
+/// Seed prefix for HashIndex PDAs.
+pub const HASH_INDEX_SEED: &[u8] = b"hashidx";
+
+/// Seed prefix for SpendBudget PDAs.
+pub const SPEND_BUDGET_SEED: &[u8] = b"spend_budget";
+
+/// Seed prefix for AttemptCounter PDAs.
+pub const ATTEMPT_SEED: &[u8] = b"attempt";
+
+/// Seed prefix for EmitterCursor PDAs.
+pub const EMITTER_CURSOR_SEED: &[u8] = b"cursor";
+
+/// Seed prefix for OutboundCursor PDAs.
+pub const OUTBOUND_CURSOR_SEED: &[u8] = b"outbound_cursor";
+
+/// Seed prefix for OutboundRate PDAs.
+pub const OUTBOUND_RATE_SEED: &[u8] = b"outbound_rate";
+
+/// Seed prefix for EmitterReceiptCount PDAs.
+pub const EMITTER_COUNT_SEED: &[u8] = b"emitter_count";
+
+/// Seed prefix for EmitterState PDAs.
+pub const EMITTER_STATE_SEED: &[u8] = b"emitter_state";
+
+/// `emitter_role` values for `post_wormhole_message`.
+pub const EMITTER_ROLE_PRIMARY: u8 = 0;
+pub const EMITTER_ROLE_SHADOW: u8 = 1;
+
+/// Seed of the shadow emitter PDA used for canary posts.
+pub const SHADOW_EMITTER_SEED: &[u8] = b"emitter_shadow";
+
+/// Message PDA seed prefix for shadow posts, whose sequences overlap the
+/// primary emitter's.
+pub const SHADOW_MESSAGE_SEED: &[u8] = b"message_shadow";
+
+/// Program-signed message PDA and bump for the post Core Bridge will assign
+/// `sequence`, under `SHADOW_MESSAGE_SEED` for shadow posts. Shared by the
+/// post paths and `derive_message_account`.
+fn derive_message_pda(shadow: bool, sequence: u64) -> (Pubkey, u8) {
+    let seed = if shadow { SHADOW_MESSAGE_SEED } else { MESSAGE_SEED };
+    Pubkey::find_program_address(&[seed, &sequence.to_le_bytes()], &crate::ID)
+}
+
+/// Emitter PDA seed for `role`; unknown roles map to the primary seed and are
+/// rejected by the handler.
+fn emitter_seed(role: u8) -> &'static [u8] {
+    if role == EMITTER_ROLE_SHADOW {
+        SHADOW_EMITTER_SEED
+    } else {
+        b"emitter"
+    }
+}
+
+/// Seed prefix for program-signed message PDAs: `[MESSAGE_SEED, next_sequence_le]`.
+/// Keying by the sequence Core Bridge will assign, rather than by `batch_id`,
+/// means no two posts can share a PDA even when a batch id is reused; the
+/// post paths still require it unwritten (`check_fresh_message`).
+pub const MESSAGE_SEED: &[u8] = b"message";
+
+/// Core Bridge PostedMessage account size without the payload bytes.
+const POSTED_MESSAGE_HEADER_LEN: usize = 95;
+
+/// Seed prefix for EmitterIndex PDAs.
+pub const EMITTER_INDEX_SEED: &[u8] = b"emitter_index";
+
+/// Seed prefix for BatchMarker PDAs.
+pub const BATCH_MARKER_SEED: &[u8] = b"batch";
+
+/// Seed prefix for ReceiptLog PDAs.
+pub const RECEIPT_LOG_SEED: &[u8] = b"receipt_log";
+
+/// Seed prefix for ExpectedDigest PDAs.
+pub const EXPECTED_DIGEST_SEED: &[u8] = b"expected_digest";
+
+/// Seed for the EventRing PDA.
+pub const EVENT_RING_SEED: &[u8] = b"event_ring";
+
+/// Seed prefix for VerifyingKey PDAs, followed by `circuit_id_le`.
+pub const VERIFYING_KEY_SEED: &[u8] = b"vk";
+
+/// Seed for the TrustedGuardianSet PDA.
+pub const TRUSTED_GUARDIAN_SET_SEED: &[u8] = b"trusted_guardians";
+
+/// `preflight_record` result bits.
+pub const PREFLIGHT_CONFIG_EXISTS: u32 = 1 << 0;
+pub const PREFLIGHT_EMITTER_ALLOWED: u32 = 1 << 1;
+pub const PREFLIGHT_SEQUENCE_NEW: u32 = 1 << 2;
+pub const PREFLIGHT_VAA_VERIFIED: u32 = 1 << 3;
+pub const PREFLIGHT_VAA_FRESH: u32 = 1 << 4;
+
+/// Hard ceiling on `max_batch_size` (batch record, close and post), bounded
+/// by transaction account limits.
+pub const MAX_BATCH_SIZE_CEILING: u16 = 32;
+
+/// Size of the `u16` body length prefix checked by `enforce_length_prefix`.
+const LENGTH_PREFIX_LEN: usize = 2;
+
+/// Leading `u16` (big-endian, "ZK") of outbound payloads framed for
+/// `enforce_framing`.
+pub const FRAMING_MAGIC: u16 = 0x5a4b;
+
+/// Framing header: `magic u16 BE | schema_version u8 | body_len u16 BE`.
+pub const FRAMING_HEADER_LEN: usize = 5;
+
+/// `hash_algo` values for stored payload hashes.
+pub const HASH_ALGO_KECCAK256: u8 = 0;
+pub const HASH_ALGO_SHA256: u8 = 1;
+
+/// Wormhole Core seed for the Bridge (config) PDA.
+const BRIDGE_SEED: &[u8] = b"Bridge";
+
+/// Wormhole Core seed for the fee collector PDA.
+const FEE_COLLECTOR_SEED: &[u8] = b"fee_collector";
+
+/// Wormhole Core seed prefix for GuardianSet PDAs (`index_be` follows).
+const GUARDIAN_SET_SEED: &[u8] = b"GuardianSet";
+
+/// Wormhole Core seed prefix for an emitter's sequence tracker.
+const SEQUENCE_SEED: &[u8] = b"Sequence";
+
+/// Sets `data` as the instruction's return data. Anything past
+/// `MAX_RETURN_DATA` fails with `ReturnDataTooLarge` here, so a view that
+/// outgrows the limit errors instead of reaching the runtime; every handler
+/// returns data through this.
+fn set_return_bytes(data: &[u8]) -> Result<()> {
+    if data.len() > MAX_RETURN_DATA {
+        msg!("return data is {} bytes; the limit is {}", data.len(), MAX_RETURN_DATA);
+        return err!(ZkError::ReturnDataTooLarge);
+    }
+    set_return_data(data);
+    Ok(())
+}
+
+/// Hash a timelocked admin call is queued under: keccak of the instruction
+/// name followed by its Borsh-encoded arguments, i.e. the instruction data
+/// after the 8-byte discriminator.
+pub fn admin_action_hash<T: AnchorSerialize>(name: &str, args: &T) -> Result<[u8; 32]> {
+    let mut data = name.as_bytes().to_vec();
+    args.serialize(&mut data)
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotSerialize))?;
+    Ok(keccak::hash(&data).to_bytes())
+}
+
+/// Sets Borsh-encoded `value` as the instruction's return data.
+fn set_return_borsh<T: AnchorSerialize>(value: &T) -> Result<()> {
+    let mut data = Vec::new();
+    value
+        .serialize(&mut data)
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotSerialize))?;
+    set_return_bytes(&data)
+}
+
+/// Reads Wormhole's sequence tracker, which stores the *next* sequence to be
+/// assigned. Returns `None` if the emitter has never posted.
+fn read_sequence_tracker(sequence: &AccountInfo, wormhole_program: &Pubkey) -> Result<Option<u64>> {
+    if sequence.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*sequence.owner, *wormhole_program, ZkError::SequenceAccountMismatch);
+    let data = sequence.try_borrow_data()?;
+    require!(data.len() >= 8, ZkError::SequenceDecode);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[..8]);
+    Ok(Some(u64::from_le_bytes(bytes)))
+}
+
+/// `BridgeData` carries no version byte, so its length names the layout:
+/// `guardian_set_index u32 | last_lamports u64 | expiration_time u32 | fee u64`,
+/// with early deployments appending `fee_persistent u64`. Both keep `fee` at
+/// the same offset; any other length is rejected rather than guessed at.
+const BRIDGE_DATA_LEN: usize = 24;
+const BRIDGE_DATA_FEE_PERSISTENT_LEN: usize = 32;
+
+/// The Wormhole Core PDAs a post checks its accounts against, derived once
+/// per instruction for the `program` it invokes.
+struct CorePdas {
+    program: Pubkey,
+    bridge: Pubkey,
+    /// Only derived while `strict_fee_collector` pins the collector.
+    fee_collector: Option<Pubkey>,
+}
+
+impl CorePdas {
+    fn derive(program: Pubkey, strict_fee_collector: bool) -> Self {
+        let (bridge, _) = Pubkey::find_program_address(&[BRIDGE_SEED], &program);
+        let fee_collector = strict_fee_collector
+            .then(|| Pubkey::find_program_address(&[FEE_COLLECTOR_SEED], &program).0);
+        Self { program, bridge, fee_collector }
+    }
+}
+
+/// Checks `config` is the Core Bridge config PDA in a known layout and
+/// decodes it. The length check alone would accept any Wormhole-owned
+/// account that happens to be 24 or 32 bytes long, so the address is checked
+/// first and other Core accounts fail with `NotBridgeConfigAccount`.
+fn read_bridge_data(
+    config: &AccountInfo,
+    core: &CorePdas,
+) -> Result<wormhole::accounts::BridgeData> {
+    require_keys_eq!(*config.owner, core.program, ZkError::ConfigOwnerMismatch);
+    require_keys_eq!(config.key(), core.bridge, ZkError::NotBridgeConfigAccount);
+    let data_ref = config.try_borrow_data()?;
+    match data_ref.len() {
+        BRIDGE_DATA_LEN | BRIDGE_DATA_FEE_PERSISTENT_LEN => {}
+        len => {
+            msg!("unsupported BridgeData length {}", len);
+            return err!(ZkError::UnsupportedBridgeLayout);
+        }
+    }
+    let mut data_slice: &[u8] = &data_ref;
+    wormhole::accounts::BridgeData::try_deserialize(&mut data_slice)
+        .map_err(|_| error!(ZkError::BridgeDeserialize))
+}
+
+/// Checks `config` is the Core Bridge account and returns its message fee.
+fn read_bridge_fee(config: &AccountInfo, core: &CorePdas) -> Result<u64> {
+    Ok(read_bridge_data(config, core)?.fee())
+}
+
+/// Fails with `FeeAboveSlippage` when the on-chain bridge `fee` exceeds the
+/// caller's `expected` fee by more than `cfg.fee_slippage`.
+fn check_fee_slippage(cfg: &ReceiptConfig, expected: Option<u64>, fee: u64) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let limit = expected.saturating_add(cfg.fee_slippage);
+    if fee > limit {
+        msg!("bridge fee {} above expected {} + slippage {}", fee, expected, cfg.fee_slippage);
+        return err!(ZkError::FeeAboveSlippage);
+    }
+    Ok(())
+}
+
+/// Core Bridge `GuardianSetData` (Borsh, no discriminator).
+#[derive(AnchorDeserialize)]
+struct GuardianSetData {
+    _index: u32,
+    _keys: Vec<[u8; 20]>,
+    _creation_time: u32,
+    /// Unix seconds the set stops being valid; 0 while it is current.
+    expiration_time: u32,
+}
+
+/// With `cfg.avoid_expiring_guardian_set`, rejects posts while the Bridge's
+/// current guardian set expires within `cfg.expiration_buffer_seconds`.
+fn check_guardian_set_expiry(
+    cfg: &ReceiptConfig,
+    config: &AccountInfo,
+    guardian_set: Option<&AccountInfo>,
+    core: &CorePdas,
+) -> Result<()> {
+    if !cfg.avoid_expiring_guardian_set {
+        return Ok(());
+    }
+    let guardian_set = guardian_set.ok_or(error!(ZkError::GuardianSetRequired))?;
+    let index = read_bridge_data(config, core)?.guardian_set_index;
+    let (expected, _) =
+        Pubkey::find_program_address(&[GUARDIAN_SET_SEED, &index.to_be_bytes()], &core.program);
+    require_keys_eq!(guardian_set.key(), expected, ZkError::GuardianSetMismatch);
+
+    let data = guardian_set.try_borrow_data()?;
+    let set = GuardianSetData::deserialize(&mut &data[..])
+        .map_err(|_| error!(ZkError::GuardianSetDecode))?;
+    if set.expiration_time != 0 {
+        let cutoff = Clock::get()?
+            .unix_timestamp
+            .saturating_add(cfg.expiration_buffer_seconds);
+        require!(set.expiration_time as i64 > cutoff, ZkError::GuardianSetExpiring);
+    }
+    Ok(())
+}
+
+/// For `verify_signatures_independently`: the VAA must be signed under
+/// `set.index`, and Secp256k1 instructions in this transaction must verify
+/// signatures by a quorum (`TrustedGuardianSet::quorum`) of its guardians
+/// over `keccak(body)`. The precompile hashes that message once more, giving
+/// the double keccak guardians sign.
+fn check_guardian_quorum(
+    set: &TrustedGuardianSet,
+    instructions: &AccountInfo,
+    decoded: &vaa::DecodedVaa,
+) -> Result<()> {
+    require!(
+        decoded.guardian_set_index == set.index,
+        ZkError::GuardianSetIndexMismatch
+    );
+    let guardians = &set.keys[..set.key_count as usize];
+    let signed = attest::secp256k1_signers(instructions, guardians, &decoded.body_hash());
+    let count = signed.count_ones() as usize;
+    if count < set.quorum() {
+        msg!("{} of {} guardians signed; quorum is {}", count, guardians.len(), set.quorum());
+        return err!(ZkError::InsufficientGuardianSignatures);
+    }
+    Ok(())
+}
+
+/// Requires `config` to be the bridge PDA of the Wormhole program we invoke,
+/// and `fee_collector` its fee-collector PDA while `strict_fee_collector` is
+/// set, so one deployment's accounts can't be mixed with another's. With the
+/// flag off the collector is left to `check_fee_collector`'s writable check.
+fn check_bridge_accounts(
+    config: &AccountInfo,
+    fee_collector: &AccountInfo,
+    core: &CorePdas,
+) -> Result<()> {
+    require_keys_eq!(config.key(), core.bridge, ZkError::BridgeAccountsInconsistent);
+    if let Some(collector) = core.fee_collector {
+        require_keys_eq!(fee_collector.key(), collector, ZkError::BridgeAccountsInconsistent);
+    }
+    Ok(())
+}
+
+/// Validates fee_collector: the Core Bridge PDA when strict, else just writable.
+fn check_fee_collector(fee_collector: &AccountInfo, core: &CorePdas) -> Result<()> {
+    if let Some(expected) = core.fee_collector {
+        require_keys_eq!(fee_collector.key(), expected, ZkError::FeeCollectorMismatch);
+    } else {
+        require!(fee_collector.is_writable, ZkError::FeeCollectorNotWritable);
+    }
+    Ok(())
+}
+
+/// System transfer from a signer; no-op for zero.
+fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let ix = anchor_lang::solana_program::system_instruction::transfer(from.key, to.key, amount);
+    anchor_lang::solana_program::program::invoke(&ix, &[from.clone(), to.clone()])?;
+    Ok(())
+}
+
+/// Requires a message account that Core Bridge has not written yet.
+fn check_fresh_message(message: &AccountInfo) -> Result<()> {
+    require!(
+        message.data_is_empty()
+            && (message.lamports() == 0 || *message.owner == anchor_lang::system_program::ID),
+        ZkError::MessageAccountNotFresh
+    );
+    Ok(())
+}
+
+/// Requires the client-allocated draft message to already hold the rent-exempt
+/// minimum for a message of `payload_len` bytes, logging the amount due, so
+/// underfunding fails here rather than deep inside the Core Bridge CPIs.
+#[cfg(feature = "core-bridge-v2")]
+fn check_draft_message_rent(message: &AccountInfo, payload_len: usize) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(POSTED_MESSAGE_HEADER_LEN + payload_len);
+    if message.lamports() < required {
+        msg!(
+            "draft_message holds {} lamports; {} required for a {}-byte payload",
+            message.lamports(),
+            required,
+            payload_len
+        );
+        return err!(ZkError::MessageAccountUnderfunded);
+    }
+    Ok(())
+}
+
+/// Enforces the poster allowlist when a PosterConfig exists with `enforce` set.
+fn check_poster(poster_config: &AccountInfo, payer: &Pubkey) -> Result<()> {
+    if poster_config.owner != &crate::ID || poster_config.data_is_empty() {
+        return Ok(());
+    }
+    let data = poster_config.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    let poster_cfg = PosterConfig::try_deserialize(&mut data_slice)?;
+    if poster_cfg.enforce {
+        require!(poster_cfg.allowed_posters.contains(payer), ZkError::PosterNotAllowed);
+    }
+    Ok(())
+}
+
+/// Prepends `destination` to the payload (canonical position: bytes 0..32).
+/// A `framed` payload keeps `FRAMING_MAGIC` in front: the destination opens
+/// the frame body instead and `body_len` grows by 32. EVM destinations must
+/// have their top 12 bytes zero.
+fn prepend_destination(
+    payload: Vec<u8>,
+    destination: Option<[u8; 32]>,
+    dest_is_evm: bool,
+    framed: bool,
+) -> Result<Vec<u8>> {
+    let Some(dest) = destination else {
+        return Ok(payload);
+    };
+    if dest_is_evm {
+        require!(dest[..12].iter().all(|b| *b == 0), ZkError::InvalidEvmDestination);
+    }
+    let at = if framed { FRAMING_HEADER_LEN } else { 0 };
+    require!(payload.len() >= at, ZkError::InvalidFraming);
+    let mut out = Vec::with_capacity(32 + payload.len());
+    out.extend_from_slice(&payload[..at]);
+    out.extend_from_slice(&dest);
+    out.extend_from_slice(&payload[at..]);
+    if framed {
+        let body_len = u16::from_be_bytes([out[3], out[4]])
+            .checked_add(32)
+            .ok_or(error!(ZkError::InvalidFraming))?;
+        out[3..FRAMING_HEADER_LEN].copy_from_slice(&body_len.to_be_bytes());
+    }
+    Ok(out)
+}
+
+/// Keccak-256 of an outbound payload for `MessagePosted`; zero when
+/// `skip_post_payload_hash` is set.
+fn post_payload_hash(cfg: &ReceiptConfig, payload: &[u8]) -> [u8; 32] {
+    if cfg.skip_post_payload_hash {
+        [0u8; 32]
+    } else {
+        keccak::hash(payload).to_bytes()
+    }
+}
+
+/// Sequence of the message just posted: the tracker holds the next one.
+fn posted_sequence(sequence: &AccountInfo, wormhole_program: &Pubkey) -> Result<u64> {
+    read_sequence_tracker(sequence, wormhole_program)?
+        .and_then(|next| next.checked_sub(1))
+        .ok_or(error!(ZkError::SequenceDecode))
+}
+
+/// Pays the Core Bridge fee in the configured asset.
+fn pay_bridge_fee<'info>(
+    asset: &FeeAsset,
+    payer: &AccountInfo<'info>,
+    fee_collector: &AccountInfo<'info>,
+    remaining: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    match asset {
+        FeeAsset::Sol => transfer_lamports(payer, fee_collector, amount),
+        FeeAsset::Spl { mint } => {
+            if amount == 0 {
+                return Ok(());
+            }
+            require!(remaining.len() >= 3, ZkError::FeeTokenAccountsMissing);
+            let (from, to, token_program) = (&remaining[0], &remaining[1], &remaining[2]);
+            require_keys_eq!(token_program.key(), SPL_TOKEN_PROGRAM_ID, ZkError::InvalidTokenProgram);
+            require_keys_eq!(read_token_account(from)?.0, *mint, ZkError::FeeMintMismatch);
+            let (to_mint, to_owner) = read_token_account(to)?;
+            require_keys_eq!(to_mint, *mint, ZkError::FeeMintMismatch);
+            require_keys_eq!(to_owner, fee_collector.key(), ZkError::FeeTokenAccountNotCollector);
+
+            // SPL Token `Transfer` (tag 3): source, destination, owner.
+            let mut data = Vec::with_capacity(9);
+            data.push(3u8);
+            data.extend_from_slice(&amount.to_le_bytes());
+            let ix = anchor_lang::solana_program::instruction::Instruction {
+                program_id: SPL_TOKEN_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(from.key(), false),
+                    AccountMeta::new(to.key(), false),
+                    AccountMeta::new_readonly(payer.key(), true),
+                ],
+                data,
+            };
+            anchor_lang::solana_program::program::invoke(
+                &ix,
+                &[from.clone(), to.clone(), payer.clone(), token_program.clone()],
+            )?;
+            Ok(())
+        }
+    }
+}
+
+/// Length of an SPL Token account; mints and multisigs are shorter.
+const SPL_TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// `(mint, owner)` of an SPL token account, its first two 32-byte fields,
+/// after an owner check. Anything but a token account fails with
+/// `FeeMintMismatch`.
+fn read_token_account(account: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+    require_keys_eq!(*account.owner, SPL_TOKEN_PROGRAM_ID, ZkError::InvalidTokenProgram);
+    let data = account.try_borrow_data()?;
+    require!(data.len() == SPL_TOKEN_ACCOUNT_LEN, ZkError::FeeMintMismatch);
+    let key = |at: usize| {
+        let bytes: [u8; 32] = data[at..at + 32].try_into().expect("slice is 32 bytes");
+        Pubkey::new_from_array(bytes)
+    };
+    Ok((key(0), key(32)))
+}
+
+/// Charges the finality-tier protocol fee to `cfg.treasury`; returns the amount.
+fn charge_protocol_fee<'info>(
+    cfg: &ReceiptConfig,
+    finality_flag: u8,
+    payer: &AccountInfo<'info>,
+    treasury: Option<&AccountInfo<'info>>,
+) -> Result<u64> {
+    let protocol_fee = cfg.protocol_fee_for(finality_flag);
+    if protocol_fee > 0 {
+        let treasury = treasury.ok_or(error!(ZkError::TreasuryMismatch))?;
+        require_keys_eq!(treasury.key(), cfg.treasury, ZkError::TreasuryMismatch);
+        require!(cfg.treasury_allowed(treasury.key), ZkError::TreasuryNotAllowed);
+        transfer_lamports(payer, treasury, protocol_fee)?;
+    }
+    Ok(protocol_fee)
+}
+
+/// Maps the documented finality flag (0 = Confirmed, 1 = Finalized).
+fn finality_from_flag(finality_flag: u8) -> Result<wormhole::types::Finality> {
+    match finality_flag {
+        0 => Ok(wormhole::types::Finality::Confirmed),
+        1 => Ok(wormhole::types::Finality::Finalized),
+        _ => err!(ZkError::InvalidFinalityFlag),
+    }
+}
+
+/// Leading fields of the Portal's `publishReceipt` payload (abi.encodePacked):
+/// `u8 version (1) | u16 origEmitterChain | bytes32 origEmitter | u64 origSequence | ...`.
+struct ReceiptPayloadHeader {
+    /// Emitter of the message this receipt answers; ours for a round trip.
+    orig_emitter: LocalEmitter,
+    orig_sequence: u64,
+}
+impl ReceiptPayloadHeader {
+    const VERSION: u8 = 1;
+    const LEN: usize = 1 + 2 + 32 + 8;
+
+    /// Returns `None` for payloads that are not a v1 receipt.
+    fn parse(payload: &[u8]) -> Option<Self> {
+        if payload.len() < Self::LEN || payload[0] != Self::VERSION {
+            return None;
+        }
+        let mut orig_emitter = [0u8; 32];
+        orig_emitter.copy_from_slice(&payload[3..35]);
+        let mut seq = [0u8; 8];
+        seq.copy_from_slice(&payload[35..43]);
+        Some(Self {
+            orig_emitter: LocalEmitter(orig_emitter),
+            orig_sequence: u64::from_be_bytes(seq),
+        })
+    }
+}
+
+/// 32-byte address Wormhole records for this program's emitter PDA.
+fn local_emitter_address() -> LocalEmitter {
+    LocalEmitter(Pubkey::find_program_address(&[b"emitter"], &crate::ID).0.to_bytes())
+}
+
+/// Same for the shadow emitter PDA (`[SHADOW_EMITTER_SEED]`).
+fn shadow_emitter_address() -> LocalEmitter {
+    LocalEmitter(Pubkey::find_program_address(&[SHADOW_EMITTER_SEED], &crate::ID).0.to_bytes())
+}
+
+/// `record_receipt_from_vaa`'s checks for `validate_record`, without its
+/// mutations; the first failure is returned, else the record's outcome.
+fn run_record_checks(
+    accounts: &ValidateRecord,
+    emitter: &[u8; 32],
+    sequence: u64,
+    category: u16,
+) -> Result<RecordOutcome> {
+    let cfg = &accounts.cfg;
+    let receipt = &accounts.receipt;
+    check_receipt_pda(receipt, emitter, sequence)?;
+    if receipt.owner == &crate::ID && !receipt.data_is_empty() {
+        let existing = load_receipt(receipt)?;
+        if existing.is_recorded() {
+            if existing.source == ReceiptSource::Wormhole {
+                let posted_vaa = &accounts.posted_vaa;
+                check_posted_vaa_owner(posted_vaa, &accounts.wormhole_program.key())?;
+                let decoded = vaa::decode_posted_vaa(posted_vaa)?;
+                let incoming_hash =
+                    decoded.payload_hash(existing.hash_algo, existing.hash_prefix_skip)?;
+                require!(
+                    decoded.emitter_address != *emitter
+                        || decoded.sequence != sequence
+                        || incoming_hash == existing.payload_hash,
+                    ZkError::PayloadHashConflict
+                );
+            }
+            return Ok(RecordOutcome::AlreadyPresent);
+        }
+    }
+    require!(cfg.category_allowed(category), ZkError::CategoryNotAllowed);
+    let decoded = validate_posted_vaa(
+        cfg,
+        &accounts.posted_vaa,
+        &accounts.wormhole_program.key(),
+        &accounts.emitter_state,
+        emitter,
+        Some(sequence),
+        Clock::get()?.unix_timestamp,
+    )?;
+    let cursor = load_cursor(&accounts.cursor, emitter)?;
+    require!(!cfg.link_receipts || cursor.is_some(), ZkError::ChainCursorRequired);
+    if let Some(cursor) = cursor {
+        require!(
+            sequence > cursor.last_sequence || in_reorg_window(cfg, cursor.last_sequence, sequence),
+            ZkError::SequenceNotAfterCursor
+        );
+    }
+    let held = match accounts.emitter_count.as_ref() {
+        Some(info) if info.owner == &crate::ID && !info.data_is_empty() => {
+            let counter = load_emitter_count(info)?;
+            require!(counter.emitter == *emitter, ZkError::InvalidEmitterCountAccount);
+            Some(counter.count)
+        }
+        Some(_) => Some(0),
+        None => None,
+    };
+    check_emitter_cap(cfg, held)?;
+    if cfg.verify_inline {
+        let vk_info = accounts
+            .verifying_key
+            .as_ref()
+            .ok_or(error!(ZkError::VerifyingKeyRequired))?;
+        let (circuit_id, proof, inputs) = groth16::split_proof(&decoded.payload)?;
+        let vk = load_verifying_key(vk_info, circuit_id)?;
+        groth16::verify(&vk, &proof, &inputs)?;
+    }
+    if cfg.verify_signatures_independently {
+        let set = accounts
+            .trusted_guardian_set
+            .as_ref()
+            .ok_or(error!(ZkError::TrustedGuardianSetRequired))?;
+        let instructions = accounts
+            .instructions
+            .as_ref()
+            .ok_or(error!(ZkError::InstructionsSysvarRequired))?;
+        check_guardian_quorum(set, instructions, &decoded)?;
+    }
+    let payload_hash = cfg.payload_hash(&decoded)?;
+    check_expected_digest(cfg, accounts.expected_digest.as_deref(), &decoded, &payload_hash)?;
+    match accounts.hash_index.as_ref() {
+        Some(index) => {
+            if let (Some(existing), _) = load_hash_index(index, &payload_hash)? {
+                require!(existing.sequence == sequence, ZkError::DuplicatePayloadAcrossSequences);
+                require!(existing.emitter == *emitter, ZkError::HashIndexCollision);
+            }
+        }
+        None => require!(!cfg.require_hash_index, ZkError::HashIndexRequired),
+    }
+    Ok(RecordOutcome::NewlyRecorded)
+}
+
+/// Runs the record-path checks on a PostedVAA and returns it decoded.
+/// `sequence` is the receipt's; `None` where the sequence comes from the VAA.
+/// `emitter_state` is the emitter's EmitterState PDA, checked by the
+/// caller's seeds.
+fn validate_posted_vaa(
+    cfg: &ReceiptConfig,
+    posted_vaa: &AccountInfo,
+    wormhole_program: &Pubkey,
+    emitter_state: &AccountInfo,
+    emitter: &[u8; 32],
+    sequence: Option<u64>,
+    now: i64,
+) -> Result<vaa::DecodedVaa> {
+    check_emitter_not_paused(emitter_state)?;
+    require!(cfg.evm_chain != 0 || cfg.allow_unset_chain, ZkError::ChainNotConfigured);
+    cfg.check_wormhole_program(wormhole_program)?;
+    check_posted_vaa_owner(posted_vaa, wormhole_program)?;
+    require!(cfg.emitter_allowed(emitter), ZkError::EmitterAddressMismatch);
+
+    let decoded = vaa::decode_posted_vaa(posted_vaa)?;
+    require!(decoded.emitter_address == *emitter, ZkError::VaaEmitterMismatch);
+    let entry = cfg
+        .emitter_entry(emitter, decoded.emitter_chain)
+        .ok_or(error!(ZkError::ChainEmitterPairMismatch))?;
+    require!(
+        Clock::get()?.slot >= cfg.emitter_active_at_slot[entry],
+        ZkError::EmitterNotYetActive
+    );
+    if let Some(sequence) = sequence {
+        check_vaa_sequence(&decoded, sequence)?;
+    }
+    check_emitter_action(emitter_state, decoded.action())?;
+    require!(!cfg.reject_zero_nonce || decoded.nonce != 0, ZkError::ZeroNonceNotAllowed);
+    require!(
+        decoded.consistency_level >= cfg.min_record_consistency,
+        ZkError::ConsistencyTooLow
+    );
+    check_vaa_freshness(cfg, decoded.timestamp as i64, now)?;
+    if cfg.verbose {
+        msg!(
+            "record: vaa={} sequence={} consistency={} payload_len={}",
+            posted_vaa.key(),
+            decoded.sequence,
+            decoded.consistency_level,
+            decoded.payload.len()
+        );
+    }
+    if cfg.enforce_length_prefix {
+        check_length_prefix(&decoded.payload)?;
+    }
+    if cfg.abi_payload_words > 0 {
+        validate_abi_layout(&decoded.payload, cfg.abi_payload_words)?;
+    }
+    Ok(decoded)
+}
+
+/// `migrate_receipt` grows only a receipt of ours written under an older,
+/// shorter layout: an account never created fails with `ReceiptNotFound`,
+/// another program's with `ReceiptNotMigratable`, another of our account
+/// types with `NotAReceiptAccount`, and one already at `Receipt::SIZE` with
+/// `ReceiptAlreadyMigrated`.
+fn check_receipt_migratable(owner: &Pubkey, data: &[u8]) -> Result<()> {
+    require!(!data.is_empty(), ZkError::ReceiptNotFound);
+    require_keys_eq!(*owner, crate::ID, ZkError::ReceiptNotMigratable);
+    require!(data.starts_with(Receipt::DISCRIMINATOR), ZkError::NotAReceiptAccount);
+    require!(data.len() < 8 + Receipt::SIZE, ZkError::ReceiptAlreadyMigrated);
+    Ok(())
+}
+
+/// A PostedVAA must carry the sequence its receipt is keyed under.
+fn check_vaa_sequence(decoded: &vaa::DecodedVaa, sequence: u64) -> Result<()> {
+    require!(decoded.sequence == sequence, ZkError::VaaSequenceMismatch);
+    Ok(())
+}
+
+/// Requires a Core Bridge-owned PostedVAA. A system-owned account is almost
+/// always a PostedVAA PDA that was never created, so it gets its own error.
+fn check_posted_vaa_owner(posted_vaa: &AccountInfo, wormhole_program: &Pubkey) -> Result<()> {
+    if *posted_vaa.owner == anchor_lang::system_program::ID {
+        msg!(
+            "posted_vaa {} is not initialized: post the VAA to Core Bridge (verify_signatures + post_vaa) first",
+            posted_vaa.key()
+        );
+        return err!(ZkError::PostedVaaUninitialized);
+    }
+    require_keys_eq!(*posted_vaa.owner, *wormhole_program, ZkError::InvalidPostedVaaOwner);
+    Ok(())
+}
+
+/// Payloads framed as `u16 BE body_len | body` must carry exactly
+/// `LENGTH_PREFIX_LEN + body_len` bytes; anything else is truncated or padded.
+fn check_length_prefix(payload: &[u8]) -> Result<()> {
+    require!(payload.len() >= LENGTH_PREFIX_LEN, ZkError::PayloadLengthPrefixMismatch);
+    let declared = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+    require!(
+        LENGTH_PREFIX_LEN + declared == payload.len(),
+        ZkError::PayloadLengthPrefixMismatch
+    );
+    Ok(())
+}
+
+/// With `cfg.enforce_framing`, an outbound `payload` must open with
+/// `FRAMING_MAGIC` and carry exactly the `body_len` its header declares. The
+/// schema version is the consumer's to interpret. It runs before the post
+/// paths add a destination, which then goes inside the frame.
+fn check_framing(cfg: &ReceiptConfig, payload: &[u8]) -> Result<()> {
+    if !cfg.enforce_framing {
+        return Ok(());
+    }
+    require!(payload.len() >= FRAMING_HEADER_LEN, ZkError::InvalidFraming);
+    require!(
+        u16::from_be_bytes([payload[0], payload[1]]) == FRAMING_MAGIC,
+        ZkError::InvalidFraming
+    );
+    let declared = u16::from_be_bytes([payload[3], payload[4]]) as usize;
+    require!(
+        FRAMING_HEADER_LEN + declared == payload.len(),
+        ZkError::InvalidFraming
+    );
+    Ok(())
+}
+
+/// Structural guard for payloads ABI-encoded by an EVM contract with a
+/// fixed-head schema: the payload must be exactly `expected_words` 32-byte
+/// words. Checks shape only, not contents; stores nothing. The VAA record
+/// paths run it with `cfg.abi_payload_words`; public so CPI consumers can
+/// run it on a payload before recording.
+pub fn validate_abi_layout(payload: &[u8], expected_words: u32) -> Result<()> {
+    let expected_len = (expected_words as usize)
+        .checked_mul(32)
+        .ok_or(error!(ZkError::AbiLayoutInvalid))?;
+    require!(payload.len() == expected_len, ZkError::AbiLayoutInvalid);
+    Ok(())
+}
+
+/// Rejects VAAs from the future or older than `max_vaa_age_seconds`.
+/// Both bounds are widened by `clock_skew_tolerance_seconds`: a larger
+/// tolerance avoids spurious rejections near the edge but admits slightly
+/// staler or early-stamped VAAs.
+fn check_vaa_freshness(cfg: &ReceiptConfig, vaa_time: i64, now: i64) -> Result<()> {
+    let skew = cfg.clock_skew_tolerance_seconds;
+    require!(vaa_time <= now.saturating_add(skew), ZkError::VaaFromFuture);
+    if cfg.max_vaa_age_seconds > 0 {
+        let age = now.saturating_sub(vaa_time);
+        require!(
+            age <= cfg.max_vaa_age_seconds.saturating_add(skew),
+            ZkError::VaaTooOld
+        );
+    }
+    Ok(())
+}
+
+/// Checks `receipt` is the PDA for `(emitter, sequence)`; returns its bump.
+/// Logs the expected address, since the usual mistake is a little-endian sequence seed.
+/// Every path storing an `(emitter, sequence)` receipt, single or batch, checks
+/// through this and stores the bump it returns.
+fn check_receipt_pda(receipt: &AccountInfo, emitter: &[u8; 32], sequence: u64) -> Result<u8> {
+    let (expected, bump) = receipt_pda(emitter, sequence);
+    if receipt.key() != expected {
+        msg!(
+            "receipt PDA mismatch for sequence {}: expected {}, got {} (seeds: \"receipt\", emitter, sequence u64 BE)",
+            sequence,
+            expected,
+            receipt.key()
+        );
+        return err!(ZkError::ReceiptPdaMismatch);
+    }
+    Ok(bump)
+}
+
+/// Creates a receipt PDA outside of Anchor's `init` (batch paths).
+/// `bump` comes from [`check_receipt_pda`].
+fn create_receipt_account<'info>(
+    receipt: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    emitter: &[u8; 32],
+    sequence: u64,
+    bump: u8,
+) -> Result<()> {
+    let seq_bytes = receipt_sequence_seed(sequence);
+    let space = 8 + Receipt::SIZE;
+    let bump_arr = [bump];
+    let [prefix, emitter_seed, seq_seed] = receipt_seeds(emitter, &seq_bytes);
+    let seeds: [&[u8]; 4] = [prefix, emitter_seed, seq_seed, &bump_arr];
+    let signer_seeds: [&[&[u8]]; 1] = [&seeds];
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: receipt.clone(),
+            },
+            &signer_seeds,
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+    Ok(())
+}
+
+/// `expected`, the ExpectedDigest given to a VAA record, must be the one
+/// registered for the VAA's nonce (the poster's batch_id) and hold its
+/// `payload_hash`. With `cfg.require_expected_digest` it must be given.
+fn check_expected_digest(
+    cfg: &ReceiptConfig,
+    expected: Option<&ExpectedDigest>,
+    decoded: &vaa::DecodedVaa,
+    payload_hash: &[u8; 32],
+) -> Result<()> {
+    let Some(expected) = expected else {
+        require!(!cfg.require_expected_digest, ZkError::ExpectedDigestMissing);
+        return Ok(());
+    };
+    require!(expected.batch_id == decoded.nonce, ZkError::ExpectedDigestBatchMismatch);
+    require!(expected.digest == *payload_hash, ZkError::PayloadDigestMismatch);
+    Ok(())
+}
+
+/// The HashIndex entry at `index`, which must be `payload_hash`'s PDA, or
+/// `None` if it was never created; with the PDA's bump.
+fn load_hash_index(index: &AccountInfo, payload_hash: &[u8; 32]) -> Result<(Option<HashIndex>, u8)> {
+    let (expected, bump) = Pubkey::find_program_address(&[HASH_INDEX_SEED, payload_hash], &crate::ID);
+    require_keys_eq!(index.key(), expected, ZkError::HashIndexMismatch);
+    if index.owner != &crate::ID || index.data_is_empty() {
+        return Ok((None, bump));
+    }
+    let data = index.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    Ok((Some(HashIndex::try_deserialize(&mut data_slice)?), bump))
+}
+
+/// Creates the HashIndex PDA for `payload_hash`, or checks an existing one
+/// already points at `(emitter, sequence)`. An existing entry for another
+/// sequence is returned for the caller to report as a cross-sequence
+/// duplicate; one for the same sequence of another emitter fails with
+/// `HashIndexCollision`.
+fn write_hash_index<'info>(
+    index: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    payload_hash: &[u8; 32],
+    emitter: &[u8; 32],
+    sequence: u64,
+) -> Result<Option<HashIndex>> {
+    let (existing, bump) = load_hash_index(index, payload_hash)?;
+    if let Some(existing) = existing {
+        if existing.sequence != sequence {
+            return Ok(Some(existing));
+        }
+        require!(existing.emitter == *emitter, ZkError::HashIndexCollision);
+        return Ok(None);
+    }
+
+    let space = 8 + HashIndex::SIZE;
+    let bump_arr = [bump];
+    let seeds: [&[u8]; 3] = [HASH_INDEX_SEED, payload_hash, &bump_arr];
+    let signer_seeds: [&[&[u8]]; 1] = [&seeds];
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: index.clone(),
+            },
+            &signer_seeds,
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+
+    let entry = HashIndex { emitter: *emitter, sequence, bump };
+    let mut data = index.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    entry.try_serialize(&mut writer)?;
+    Ok(None)
+}
+
+/// Reads the VerifyingKey registered for `circuit_id` from `info`, failing
+/// with `VerifyingKeyNotFound` if `info` holds no key or another circuit's.
+/// Program-owned VerifyingKey accounts exist only at their circuit's PDA.
+fn load_verifying_key(info: &AccountInfo, circuit_id: u32) -> Result<VerifyingKey> {
+    require!(
+        info.owner == &crate::ID && !info.data_is_empty(),
+        ZkError::VerifyingKeyNotFound
+    );
+    let data = info.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    let vk = VerifyingKey::try_deserialize(&mut data_slice)
+        .map_err(|_| error!(ZkError::VerifyingKeyNotFound))?;
+    require!(vk.circuit_id == circuit_id, ZkError::VerifyingKeyNotFound);
+    Ok(vk)
+}
+
+/// Deserializes a program-owned Receipt account.
+fn load_receipt(info: &AccountInfo) -> Result<Receipt> {
+    require!(!info.data_is_empty(), ZkError::ReceiptAccountEmpty);
+    require_keys_eq!(*info.owner, crate::ID, ZkError::InvalidReceiptAccount);
+    let data = info.try_borrow_data()?;
+    let mut data_slice: &[u8] = &data;
+    Receipt::try_deserialize(&mut data_slice).map_err(|_| error!(ZkError::ReceiptLayoutMismatch))
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(
+    batch_id: u32,
+    payload: Vec<u8>,
+    finality_flag: Option<u8>,
+    destination: Option<[u8; 32]>,
+    dest_is_evm: bool,
+    waive_protocol_fee: bool,
+    emitter_role: u8,
+)]
+pub struct PostWormholeMessage<'info> {
+    /// Mutable for the `in_flight` count of posts with a batch marker.
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: PosterConfig PDA; may be uninitialized (allowlist disabled).
+    #[account(seeds = [POSTER_CONFIG_SEED], bump)]
+    pub poster_config: UncheckedAccount<'info>,
+
+    /// CHECK: Points to Wormhole Core Bridge(Config).
+    #[account(mut)]
+    pub config: AccountInfo<'info>,
+
+    /// CHECK: Fresh signing keypair or our message PDA; checked in the handler.
+    #[account(mut)]
+    pub message: UncheckedAccount<'info>,
+
+    /// CHECK: Primary or shadow emitter PDA per `emitter_role`; verified by Wormhole Core.
+    #[account(seeds = [emitter_seed(emitter_role)], bump = cfg.emitter_pda_bump(emitter_role))]
+    pub emitter: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(mut)]
+    pub sequence: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Derives from Bridge(Config).
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent:  Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Must equal `cfg.treasury`; required only when a protocol fee applies.
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.budget_per_window` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SpendBudget::SIZE,
+        seeds = [SPEND_BUDGET_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub spend_budget: Option<Account<'info, SpendBudget>>,
+
+    /// CHECK: Current Core Bridge GuardianSet; required with
+    /// `cfg.avoid_expiring_guardian_set`, checked in the handler.
+    pub guardian_set: Option<UncheckedAccount<'info>>,
+
+    /// Optional marker for round-trip monitoring of this batch_id; reused only
+    /// once its previous post has round-tripped.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BatchMarker::SIZE,
+        seeds = [BATCH_MARKER_SEED, &batch_id.to_le_bytes()],
+        bump
+    )]
+    pub batch_marker: Option<Account<'info, BatchMarker>>,
+
+    /// Required when `cfg.track_attempts` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AttemptCounter::SIZE,
+        seeds = [ATTEMPT_SEED, emitter.key().as_ref(), &batch_id.to_le_bytes()],
+        bump
+    )]
+    pub attempt_counter: Option<Account<'info, AttemptCounter>>,
+
+    /// Required when `cfg.enforce_batch_monotonic` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundCursor::SIZE,
+        seeds = [OUTBOUND_CURSOR_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_cursor: Option<Account<'info, OutboundCursor>>,
+
+    /// Required when `cfg.min_post_interval_slots` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundRate::SIZE,
+        seeds = [OUTBOUND_RATE_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_rate: Option<Account<'info, OutboundRate>>,
+
+    /// Registered by `post_and_register_expected` only; `post_wormhole_message`
+    /// rejects it.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ExpectedDigest::SIZE,
+        seeds = [EXPECTED_DIGEST_SEED, &batch_id.to_le_bytes()],
+        bump
+    )]
+    pub expected_digest: Option<Account<'info, ExpectedDigest>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct PostMessagesBatch<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: PosterConfig PDA; may be uninitialized (allowlist disabled).
+    #[account(seeds = [POSTER_CONFIG_SEED], bump)]
+    pub poster_config: UncheckedAccount<'info>,
+
+    /// CHECK: Points to Wormhole Core Bridge(Config).
+    #[account(mut)]
+    pub config: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(mut)]
+    pub sequence: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Derives from Bridge(Config).
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent:  Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Must equal `cfg.treasury`; required only when a protocol fee applies.
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.budget_per_window` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SpendBudget::SIZE,
+        seeds = [SPEND_BUDGET_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub spend_budget: Option<Account<'info, SpendBudget>>,
+
+    /// CHECK: Current Core Bridge GuardianSet; required with
+    /// `cfg.avoid_expiring_guardian_set`, checked in the handler.
+    pub guardian_set: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.enforce_batch_monotonic` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundCursor::SIZE,
+        seeds = [OUTBOUND_CURSOR_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_cursor: Option<Account<'info, OutboundCursor>>,
+
+    /// Required when `cfg.min_post_interval_slots` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundRate::SIZE,
+        seeds = [OUTBOUND_RATE_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_rate: Option<Account<'info, OutboundRate>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct PostDualFinality<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: PosterConfig PDA; may be uninitialized (allowlist disabled).
+    #[account(seeds = [POSTER_CONFIG_SEED], bump)]
+    pub poster_config: UncheckedAccount<'info>,
+
+    /// CHECK: Points to Wormhole Core Bridge(Config).
+    #[account(mut)]
+    pub config: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub message_confirmed: Signer<'info>,
+
+    #[account(mut)]
+    pub message_finalized: Signer<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(mut)]
+    pub sequence: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Derives from Bridge(Config).
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent:  Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Must equal `cfg.treasury`; required only when a protocol fee applies.
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.budget_per_window` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SpendBudget::SIZE,
+        seeds = [SPEND_BUDGET_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub spend_budget: Option<Account<'info, SpendBudget>>,
+
+    /// CHECK: Current Core Bridge GuardianSet; required with
+    /// `cfg.avoid_expiring_guardian_set`, checked in the handler.
+    pub guardian_set: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.enforce_batch_monotonic` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundCursor::SIZE,
+        seeds = [OUTBOUND_CURSOR_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_cursor: Option<Account<'info, OutboundCursor>>,
+
+    /// Required when `cfg.min_post_interval_slots` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundRate::SIZE,
+        seeds = [OUTBOUND_RATE_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_rate: Option<Account<'info, OutboundRate>>,
+}
+
+/// Accounts for the test-only `post_and_record_test`.
+#[cfg(feature = "test-helpers")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(batch_id: u32, payload: Vec<u8>, sequence: u64)]
+pub struct PostAndRecordTest<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// CHECK: Points to Wormhole Core Bridge(Config).
+    #[account(mut)]
+    pub config: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub message: Signer<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(mut)]
+    pub sequence: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Derives from Bridge(Config).
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    /// CHECK: Test-injected PostedVAA for the message posted here; owner and
+    /// contents are checked in the handler.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Receipt::SIZE,
+        seeds = [RECEIPT_SEED, emitter.key().as_ref(), &receipt_sequence_seed(sequence)],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent:  Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+#[cfg(feature = "core-bridge-v2")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct PostWormholeMessageV2<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: PosterConfig PDA; may be uninitialized (allowlist disabled).
+    #[account(seeds = [POSTER_CONFIG_SEED], bump)]
+    pub poster_config: UncheckedAccount<'info>,
+
+    /// CHECK: Points to Wormhole Core Bridge(Config).
+    #[account(mut)]
+    pub config: AccountInfo<'info>,
+
+    /// CHECK: Client-allocated draft message owned by Core Bridge.
+    #[account(mut, owner = wormhole_program.key())]
+    pub draft_message: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: AccountInfo<'info>,
+
+    /// CHECK: Verified by Wormhole Core.
+    #[account(mut)]
+    pub sequence: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Derives from Bridge(Config).
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent:  Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Must equal `cfg.treasury`; required only when a protocol fee applies.
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.budget_per_window` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SpendBudget::SIZE,
+        seeds = [SPEND_BUDGET_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub spend_budget: Option<Account<'info, SpendBudget>>,
+
+    /// CHECK: Current Core Bridge GuardianSet; required with
+    /// `cfg.avoid_expiring_guardian_set`, checked in the handler.
+    pub guardian_set: Option<UncheckedAccount<'info>>,
+
+    /// Required when `cfg.enforce_batch_monotonic` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundCursor::SIZE,
+        seeds = [OUTBOUND_CURSOR_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_cursor: Option<Account<'info, OutboundCursor>>,
+
+    /// Required when `cfg.min_post_interval_slots` is set.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OutboundRate::SIZE,
+        seeds = [OUTBOUND_RATE_SEED, emitter.key().as_ref()],
+        bump
+    )]
+    pub outbound_rate: Option<Account<'info, OutboundRate>>,
+}
+
+#[cfg(feature = "core-bridge-v2")]
+impl<'info> PostWormholeMessageV2<'info> {
+    fn to_account_infos_v2(&self) -> core_bridge_v2::PostAccounts<'info> {
+        core_bridge_v2::PostAccounts {
+            config:         self.config.clone(),
+            draft_message:  self.draft_message.clone(),
+            emitter:        self.emitter.clone(),
+            sequence:       self.sequence.clone(),
+            payer:          self.payer.to_account_info(),
+            fee_collector:  self.fee_collector.clone(),
+            clock:          self.clock.to_account_info(),
+            rent:           self.rent.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            core_bridge:    self.wormhole_program.to_account_info(),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReadConfig<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct ReadConfigLists<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct ReadStats<'info> {
+    #[account(seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReadMaxBatchSize<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct EstimateBatchFee<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: Wormhole Core Bridge(Config); owner checked in the handler.
+    pub config: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+#[derive(Accounts)]
+pub struct PeekNextSequence<'info> {
+    /// CHECK: Any emitter; only its key is used to derive the sequence PDA.
+    pub emitter: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the Wormhole sequence PDA for `emitter`.
+    #[account(
+        seeds = [SEQUENCE_SEED, emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub sequence: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter_role: u8)]
+pub struct DeriveMessageAccount<'info> {
+    /// CHECK: Primary or shadow emitter PDA per `emitter_role`; only its key is used.
+    #[account(seeds = [emitter_seed(emitter_role)], bump)]
+    pub emitter: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the Wormhole sequence PDA for `emitter`.
+    #[account(
+        seeds = [SEQUENCE_SEED, emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub sequence: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+#[derive(Accounts)]
+pub struct ReadEmitterAddress {}
+
+#[derive(Accounts)]
+#[instruction(remote_emitter: [u8; 32], category: u16)]
+pub struct Reconcile<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: Our emitter PDA; only its key is used.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: UncheckedAccount<'info>,
+
+    /// CHECK: May not exist yet; owner checked when it does.
+    #[account(
+        seeds = [SEQUENCE_SEED, emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub sequence: UncheckedAccount<'info>,
+
+    /// CHECK: EmitterCursor PDA for `remote_emitter`; may not exist.
+    #[account(
+        seeds = [EMITTER_CURSOR_SEED, remote_emitter.as_ref(), &cursor_category_seed(&cfg, category)],
+        bump
+    )]
+    pub cursor: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct DescribeReceipt<'info> {
+    /// CHECK: Receipt PDA; may not exist, which `describe_receipt` reports.
+    #[account(seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)], bump)]
+    pub receipt: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadEmitterStatus<'info> {
+    /// CHECK: Our emitter PDA; only its key is used.
+    #[account(seeds = [b"emitter"], bump)]
+    pub emitter: UncheckedAccount<'info>,
+
+    /// CHECK: May not exist yet; owner checked when it does.
+    #[account(
+        seeds = [SEQUENCE_SEED, emitter.key().as_ref()],
+        bump,
+        seeds::program = wormhole_program.key()
+    )]
+    pub sequence: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+}
+
+/// Accounts `validate_record` checks are left unchecked where a failed
+/// constraint would abort the dry run instead of reporting its code.
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64, category: u16)]
+pub struct ValidateRecord<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: Checked like the record path does, in the handler.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Receipt PDA for `(emitter, sequence)`; may not exist yet.
+    #[account(seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)], bump)]
+    pub receipt: UncheckedAccount<'info>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist.
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    /// CHECK: EmitterCursor PDA the record would be given; may not exist.
+    #[account(
+        seeds = [EMITTER_CURSOR_SEED, emitter.as_ref(), &cursor_category_seed(&cfg, category)],
+        bump
+    )]
+    pub cursor: UncheckedAccount<'info>,
+
+    /// CHECK: The EmitterReceiptCount PDA the record would be given; may not
+    /// exist yet.
+    pub emitter_count: Option<UncheckedAccount<'info>>,
+
+    /// Optional; the digest the record would be given.
+    pub expected_digest: Option<Account<'info, ExpectedDigest>>,
+
+    /// CHECK: The HashIndex PDA the record would be given; checked in the handler.
+    pub hash_index: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Required with `cfg.verify_inline`; checked in the handler.
+    pub verifying_key: Option<UncheckedAccount<'info>>,
+
+    /// Required with `cfg.verify_signatures_independently`.
+    #[account(seeds = [TRUSTED_GUARDIAN_SET_SEED], bump = trusted_guardian_set.bump)]
+    pub trusted_guardian_set: Option<Box<Account<'info, TrustedGuardianSet>>>,
+
+    /// CHECK: Instructions sysvar, for the guardian quorum check.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+/// Checked accounts are left unchecked so a failed check never aborts the simulation.
+#[derive(Accounts)]
+pub struct PreflightRecord<'info> {
+    /// CHECK: Receipt config PDA; may not exist.
+    #[account(seeds = [b"cfg"], bump)]
+    pub cfg: UncheckedAccount<'info>,
+
+    /// CHECK: Inspected for owner and emitter only.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Expected receipt PDA; must be empty for a new sequence.
+    pub receipt: UncheckedAccount<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct RecordAttempt<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: Classified, never trusted; may be missing or malformed.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: Receipt PDA for `(emitter, sequence)`; may not exist yet.
+    #[account(seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)], bump)]
+    pub receipt: UncheckedAccount<'info>,
+
+    pub reporter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitReceiptConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ReceiptConfig::SIZE,
+        seeds = [b"cfg"],
+        bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EventCounter::SIZE,
+        seeds = [b"event_counter"],
+        bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StatsAccount::SIZE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct QueueChange<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PendingChange::SIZE,
+        seeds = [PENDING_CHANGE_SEED],
+        bump
+    )]
+    pub pending_change: Box<Account<'info, PendingChange>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by `apply_change` and `cancel_change`; both close the pending change.
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ApplyChange<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [PENDING_CHANGE_SEED],
+        bump = pending_change.bump
+    )]
+    pub pending_change: Box<Account<'info, PendingChange>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(action_hash: [u8; 32])]
+pub struct QueueAction<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingAction::SIZE,
+        seeds = [PENDING_ACTION_SEED, action_hash.as_ref()],
+        bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
+    /// The admin, or the fee authority for its own setters.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(action_hash: [u8; 32])]
+pub struct CancelAction<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [PENDING_ACTION_SEED, action_hash.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SetPostingPaused<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SetMaintenanceWindow<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct SetEmitterPaused<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + EmitterState::SIZE,
+        seeds = [EMITTER_STATE_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_state: Account<'info, EmitterState>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct SetEmitterActions<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + EmitterState::SIZE,
+        seeds = [EMITTER_STATE_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_state: Account<'info, EmitterState>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter_role: u8)]
+pub struct ResetOutboundCursor<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: Primary or shadow emitter PDA per `emitter_role`; only its key is used.
+    #[account(seeds = [emitter_seed(emitter_role)], bump = cfg.emitter_pda_bump(emitter_role))]
+    pub emitter: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [OUTBOUND_CURSOR_SEED, emitter.key().as_ref()],
+        bump = outbound_cursor.bump
+    )]
+    pub outbound_cursor: Account<'info, OutboundCursor>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct InitEmitterIndex<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EmitterIndex::SIZE,
+        seeds = [EMITTER_INDEX_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_index: AccountLoader<'info, EmitterIndex>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64, force_backward: bool, category: u16)]
+pub struct SetCursor<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + EmitterCursor::SIZE,
+        seeds = [EMITTER_CURSOR_SEED, emitter.as_ref(), &cursor_category_seed(&cfg, category)],
+        bump
+    )]
+    pub cursor: Account<'info, EmitterCursor>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SetPosterConfig<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + PosterConfig::SIZE,
+        seeds = [POSTER_CONFIG_SEED],
+        bump
+    )]
+    pub poster_config: Box<Account<'info, PosterConfig>>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitReceiptConfigFromPda<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ReceiptConfig::SIZE,
+        seeds = [b"cfg"],
+        bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EventCounter::SIZE,
+        seeds = [b"event_counter"],
+        bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StatsAccount::SIZE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// CHECK: Emitter PDA of the Solana-origin program; only its key is used.
+    pub emitter_pda: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateReceiptConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"cfg"],
+        bump = cfg.bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"cfg"],
+        bump = cfg.bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    /// `cfg.fee_authority`, or the admin while it is unset.
+    #[account(mut)]
+    pub fee_authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64, category: u16)]
+pub struct RecordReceiptFromVaa<'info> {
+    #[account(
+        mut,
+        seeds = [b"cfg"],
+        bump = cfg.bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// CHECK: Owned by Wormhole Core.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Receipt::SIZE,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist (not paused, every
+    /// action allowed).
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    /// Optional marker of one of our outbound posts, for round-trip detection.
+    #[account(
+        mut,
+        seeds = [BATCH_MARKER_SEED, &batch_marker.batch_id.to_le_bytes()],
+        bump = batch_marker.bump
+    )]
+    pub batch_marker: Option<Account<'info, BatchMarker>>,
+
+    /// CHECK: Optional HashIndex PDA `[HASH_INDEX_SEED, payload_hash]`; verified in the handler.
+    #[account(mut)]
+    pub hash_index: Option<UncheckedAccount<'info>>,
+
+    /// Optional per-emitter index of recent receipts.
+    #[account(
+        mut,
+        seeds = [EMITTER_INDEX_SEED, emitter.as_ref()],
+        bump = emitter_index.load()?.bump
+    )]
+    pub emitter_index: Option<AccountLoader<'info, EmitterIndex>>,
+
+    /// CHECK: EmitterCursor PDA for `emitter` (per `category` with
+    /// `cfg.per_category_cursors`); may not exist. Once it does, it enforces
+    /// strictly increasing sequences, so it can't be left out to skip them.
+    #[account(
+        mut,
+        seeds = [EMITTER_CURSOR_SEED, emitter.as_ref(), &cursor_category_seed(&cfg, category)],
+        bump
+    )]
+    pub cursor: UncheckedAccount<'info>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    /// Optional digest registered for the VAA's batch_id; checked in the handler.
+    pub expected_digest: Option<Account<'info, ExpectedDigest>>,
+
+    /// CHECK: Required with `cfg.verify_inline`: the VerifyingKey PDA of the
+    /// payload proof's circuit id, which is only known once the VAA is
+    /// decoded, so it is checked in the handler.
+    pub verifying_key: Option<UncheckedAccount<'info>>,
+
+    /// Required with `cfg.verify_signatures_independently`.
+    #[account(seeds = [TRUSTED_GUARDIAN_SET_SEED], bump = trusted_guardian_set.bump)]
+    pub trusted_guardian_set: Option<Box<Account<'info, TrustedGuardianSet>>>,
+
+    /// CHECK: Instructions sysvar; required for CPI calls with `cfg.enforce_cpi_callers`.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32)]
+pub struct RegisterVerifyingKey<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + VerifyingKey::SIZE,
+        seeds = [VERIFYING_KEY_SEED, &circuit_id.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(circuit_id: u32)]
+pub struct RemoveVerifyingKey<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [VERIFYING_KEY_SEED, &circuit_id.to_le_bytes()],
+        bump = verifying_key.bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseLegacyVerifyingKey<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: Pre-circuit-id VerifyingKey PDA `[VERIFYING_KEY_SEED]`, in a
+    /// layout the current `VerifyingKey` no longer decodes; checked in the
+    /// handler.
+    #[account(mut, seeds = [VERIFYING_KEY_SEED], bump)]
+    pub legacy_verifying_key: UncheckedAccount<'info>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SetTrustedGuardianSet<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + TrustedGuardianSet::SIZE,
+        seeds = [TRUSTED_GUARDIAN_SET_SEED],
+        bump
+    )]
+    pub trusted_guardian_set: Box<Account<'info, TrustedGuardianSet>>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadTrustedGuardianSet<'info> {
+    #[account(seeds = [TRUSTED_GUARDIAN_SET_SEED], bump = trusted_guardian_set.bump)]
+    pub trusted_guardian_set: Box<Account<'info, TrustedGuardianSet>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(batch_id: u32)]
+pub struct RegisterExpectedDigest<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ExpectedDigest::SIZE,
+        seeds = [EXPECTED_DIGEST_SEED, &batch_id.to_le_bytes()],
+        bump
+    )]
+    pub expected_digest: Account<'info, ExpectedDigest>,
+
+    /// Admin or operator.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct RecordReceiptRing<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// CHECK: Owned by Wormhole Core.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist (not paused, every
+    /// action allowed).
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Receipt::SIZE,
+        seeds = [
+            RECEIPT_RING_SEED,
+            emitter.as_ref(),
+            &receipt_ring_slot_seed(sequence, cfg.ring_size)
+        ],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: Instructions sysvar; required for CPI calls with `cfg.enforce_cpi_callers`.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    /// CHECK: Optional HashIndex PDA `[HASH_INDEX_SEED, payload_hash]`; verified in the handler.
+    #[account(mut)]
+    pub hash_index: Option<UncheckedAccount<'info>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], vaa_hash: [u8; 32])]
+pub struct RecordReceiptByHash<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// CHECK: Owned by Wormhole Core.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist (not paused, every
+    /// action allowed).
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Receipt::SIZE,
+        seeds = [RECEIPT_HASH_SEED, vaa_hash.as_ref()],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: Instructions sysvar; required for CPI calls with `cfg.enforce_cpi_callers`.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    /// CHECK: Optional HashIndex PDA `[HASH_INDEX_SEED, payload_hash]`; verified in the handler.
+    #[account(mut)]
+    pub hash_index: Option<UncheckedAccount<'info>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct InitReceiptLog<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ReceiptLog::SIZE,
+        seeds = [RECEIPT_LOG_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub receipt_log: AccountLoader<'info, ReceiptLog>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct AppendReceiptToLog<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: Owned by Wormhole Core.
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist (not paused, every
+    /// action allowed).
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_LOG_SEED, emitter.as_ref()],
+        bump = receipt_log.load()?.bump
+    )]
+    pub receipt_log: AccountLoader<'info, ReceiptLog>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// CHECK: Optional HashIndex PDA `[HASH_INDEX_SEED, payload_hash]`; verified in the handler.
+    #[account(mut)]
+    pub hash_index: Option<UncheckedAccount<'info>>,
+
+    /// Pays for the HashIndex entry.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadLogEntry<'info> {
+    pub receipt_log: AccountLoader<'info, ReceiptLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(circuit_id: u32)]
+pub struct VerifyProofsBatch<'info> {
+    #[account(
+        seeds = [VERIFYING_KEY_SEED, &circuit_id.to_le_bytes()],
+        bump = verifying_key.bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKey>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitEventRing<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EventRing::SIZE,
+        seeds = [EVENT_RING_SEED],
+        bump
+    )]
+    pub event_ring: AccountLoader<'info, EventRing>,
+
+    /// Required while `cfg.timelock_seconds > 0`; the queued call, closed on use.
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitStats<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StatsAccount::SIZE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitEventCounter<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + EventCounter::SIZE,
+        seeds = [b"event_counter"],
+        bump
+    )]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExportReceipts {}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct AttestState<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// Any emitter's cursor (per-category ones included).
+    pub cursor: Account<'info, EmitterCursor>,
+
+    #[account(seeds = [EMITTER_COUNT_SEED, cursor.emitter.as_ref()], bump = emitter_count.bump)]
+    pub emitter_count: Account<'info, EmitterReceiptCount>,
+}
+
+#[derive(Accounts)]
+pub struct ReadEventRing<'info> {
+    #[account(seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: AccountLoader<'info, EventRing>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct AssertReceiptAction<'info> {
+    #[account(
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Config PDA at a possibly outdated size; owner and
+    /// discriminator are checked in the handler.
+    #[account(mut, seeds = [b"cfg"], bump)]
+    pub cfg: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct ReemitReceiptEvent<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    /// CHECK: Receipt PDA, possibly never created; emptiness and owner are
+    /// checked in the handler.
+    #[account(seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)], bump)]
+    pub receipt: UncheckedAccount<'info>,
+
+    /// Optional; supplies `emitter_receipt_count`.
+    #[account(seeds = [EMITTER_COUNT_SEED, emitter.as_ref()], bump = emitter_count.bump)]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct MigrateReceipt<'info> {
+    /// CHECK: Receipt PDA at a possibly outdated size; owner and
+    /// discriminator are checked in the handler.
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump
+    )]
+    pub receipt: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], category: u16)]
+pub struct MigrateCursor<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: EmitterCursor PDA at a possibly outdated size; owner and
+    /// discriminator are checked in the handler.
+    #[account(
+        mut,
+        seeds = [EMITTER_CURSOR_SEED, emitter.as_ref(), &cursor_category_seed(&cfg, category)],
+        bump
+    )]
+    pub cursor: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct MigrateEmitterCount<'info> {
+    /// CHECK: EmitterReceiptCount PDA at a possibly outdated size; owner and
+    /// discriminator are checked in the handler.
+    #[account(mut, seeds = [EMITTER_COUNT_SEED, emitter.as_ref()], bump)]
+    pub emitter_count: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct RecordReceiptDirect<'info> {
+    #[account(
+        mut,
+        seeds = [b"cfg"],
+        bump = cfg.bump
+    )]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Receipt::SIZE,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct RecordReceiptWithHash<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Receipt::SIZE,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    /// Operator or admin.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    /// CHECK: Optional HashIndex PDA `[HASH_INDEX_SEED, payload_hash]`; verified in the handler.
+    #[account(mut)]
+    pub hash_index: Option<UncheckedAccount<'info>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct AttestReceipt<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    /// Operator or admin.
+    pub authority: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, checked by address.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct TombstoneReceipt<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct TombstoneReceiptByHash<'info> {
+    #[account(mut, seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_HASH_SEED, vaa_hash.as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32])]
+pub struct RecordReceiptsBatch<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    pub wormhole_program: Program<'info, Wormhole>,
+
+    /// CHECK: EmitterState PDA for `emitter`; may not exist (not paused, every
+    /// action allowed).
+    #[account(seeds = [EMITTER_STATE_SEED, emitter.as_ref()], bump)]
+    pub emitter_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: Instructions sysvar; required for CPI calls with `cfg.enforce_cpi_callers`.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    /// EventRing; required once `init_event_ring` has run.
+    #[account(mut, seeds = [EVENT_RING_SEED], bump = event_ring.load()?.bump)]
+    pub event_ring: Option<AccountLoader<'info, EventRing>>,
+
+    /// Required, cap or not, so `max_receipts_per_emitter` counts every receipt.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+}
+
+#[derive(Accounts)]
+pub struct PreallocateReceipts<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(emitter: [u8; 32], sequence: u64)]
+pub struct CloseReceipt<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [RECEIPT_SEED, emitter.as_ref(), &receipt_sequence_seed(sequence)],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    /// Admin or reaper; receives the reclaimed rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Released by one for `max_receipts_per_emitter`; required, cap or not,
+    /// and created for an emitter that has none yet.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    /// CHECK: The receipt's EmitterCursor PDA, derived in the handler; may not
+    /// exist. Required while `cfg.reorg_tolerance` is set.
+    pub cursor: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(vaa_hash: [u8; 32])]
+pub struct CloseReceiptByHash<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [RECEIPT_HASH_SEED, vaa_hash.as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Box<Account<'info, Receipt>>,
+
+    /// Admin or reaper; receives the reclaimed rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Released by one for `max_receipts_per_emitter`; required, cap or not,
+    /// and created for an emitter that has none yet.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + EmitterReceiptCount::SIZE,
+        seeds = [EMITTER_COUNT_SEED, receipt.emitter.as_ref()],
+        bump
+    )]
+    pub emitter_count: Option<Account<'info, EmitterReceiptCount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseReceiptsBatch<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    #[account(mut, seeds = [b"event_counter"], bump = event_counter.bump)]
+    pub event_counter: Account<'info, EventCounter>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, StatsAccount>,
+
+    /// Admin or reaper; receives the reclaimed rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: u32)]
+pub struct CloseAttemptCounter<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// CHECK: Our emitter PDA; only its key seeds the counter.
+    #[account(seeds = [b"emitter"], bump = cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY))]
+    pub emitter: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ATTEMPT_SEED, emitter.key().as_ref(), &batch_id.to_le_bytes()],
+        bump = attempt_counter.bump
+    )]
+    pub attempt_counter: Account<'info, AttemptCounter>,
+
+    /// The batch's marker; its round trip confirms the batch.
+    #[account(
+        seeds = [BATCH_MARKER_SEED, &batch_id.to_le_bytes()],
+        bump = batch_marker.bump
+    )]
+    pub batch_marker: Account<'info, BatchMarker>,
+
+    /// Admin or reaper; receives the reclaimed rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeReceiptRoot<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyChain<'info> {
+    #[account(seeds = [b"cfg"], bump = cfg.bump)]
+    pub cfg: Box<Account<'info, ReceiptConfig>>,
+
+    /// Optional; the emitter's cursor, whose last linked receipt ends the chain.
+    pub cursor: Option<Account<'info, EmitterCursor>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyInclusion<'info> {
+    #[account(
+        seeds = [RECEIPT_SEED, receipt.emitter.as_ref(), &receipt_sequence_seed(receipt.sequence)],
+        bump = receipt.bump
     )]
-    pub receipt: Account<'info, Receipt>,
+    pub receipt: Box<Account<'info, Receipt>>,
+}
+
+#[account]
+pub struct ReceiptConfig {
+    pub admin: Pubkey,
+    /// 0 means unconfigured: the VAA record paths refuse to run unless
+    /// `allow_unset_chain` is set.
+    pub evm_chain: u16,
+    /// First (usually only) remote emitter whose VAAs the record paths accept.
+    pub emitter: RemoteEmitter,
+    pub bump: u8,
+    /// Minimum payload length for Finalized posts; 0 disables.
+    pub finalized_min_payload_len: u32,
+    /// Keeper allowed to close receipts; default key disables.
+    pub receipt_reaper: Pubkey,
+    /// Minimum receipt age before it can be closed.
+    pub min_age_seconds: i64,
+    /// Require fee_collector to be the Core Bridge PDA; disable only on local validators.
+    pub strict_fee_collector: bool,
+    /// Protocol fee (lamports) for Confirmed posts.
+    pub protocol_fee_confirmed: u64,
+    /// Protocol fee (lamports) for Finalized posts.
+    pub protocol_fee_finalized: u64,
+    /// Recipient of the protocol fee.
+    pub treasury: Pubkey,
+    /// Maximum VAA age accepted by the record path; 0 disables.
+    pub max_vaa_age_seconds: i64,
+    /// Slack added to every VAA timestamp comparison.
+    pub clock_skew_tolerance_seconds: i64,
+    /// Trusted off-chain verifier for `record_receipt_with_hash`; default key disables.
+    pub operator: Pubkey,
+    /// Asset the Core Bridge fee is paid in.
+    pub fee_asset: FeeAsset,
+    /// When set, every post bumps the batch's AttemptCounter.
+    pub track_attempts: bool,
+    /// Delay for queued config changes and admin calls; 0 lets
+    /// `update_receipt_config` and the admin setters apply directly.
+    pub timelock_seconds: i64,
+    /// Slots per emitter for `record_receipt_ring`; 0 disables ring mode.
+    pub ring_size: u32,
+    /// Max fees (bridge + protocol) per window through our emitter; 0 disables.
+    pub budget_per_window: u64,
+    pub budget_window_seconds: i64,
+    /// Skips keccak of outbound payloads in `MessagePosted` (large payloads).
+    pub skip_post_payload_hash: bool,
+    /// Lowest VAA `consistency_level` the record paths accept; 0 accepts any.
+    pub min_record_consistency: u8,
+    /// Halts `post_wormhole_message*`; inbound recording keeps working.
+    pub posting_paused: bool,
+    /// Hash for stored receipt payload hashes (`HASH_ALGO_*`).
+    pub hash_algo: u8,
+    /// Record paths require a `u16` length prefix matching the payload size.
+    /// Off by default: Portal receipt payloads are not length-prefixed.
+    pub enforce_length_prefix: bool,
+    /// Categories accepted by `record_receipt_from_vaa`; empty accepts any.
+    pub allowed_categories: [u16; ReceiptConfig::MAX_CATEGORIES],
+    pub allowed_category_count: u8,
+    /// Remote emitters accepted besides `emitter`.
+    pub extra_emitters: [RemoteEmitter; ReceiptConfig::MAX_EMITTERS - 1],
+    pub extra_emitter_count: u8,
+    /// Extra `msg!` tracing in the post and record paths (devnet debugging).
+    pub verbose: bool,
+    /// Items per batch record/close/post call; 1..=`MAX_BATCH_SIZE_CEILING`.
+    pub max_batch_size: u16,
+    /// Posts must use the message PDA instead of a client keypair.
+    pub require_pda_message: bool,
+    /// Keys EmitterCursor by `(emitter, category)` instead of `emitter`.
+    pub per_category_cursors: bool,
+    /// Treasuries `set_protocol_fee` may configure; all-default allows any.
+    pub treasury_allowlist: [Pubkey; ReceiptConfig::MAX_TREASURIES],
+    /// Slot of the last config-mutating admin instruction; 0 if none yet.
+    pub last_config_change_slot: u64,
+    /// Minimum slots between config-mutating admin instructions; 0 disables.
+    pub config_change_cooldown_slots: u64,
+    /// Human-readable name of `evm_chain` (e.g. "ethereum"), echoed in
+    /// `ReceiptRecorded`; zero-padded ASCII, all zeros if unset.
+    pub chain_label: [u8; 16],
+    /// Posts require a `guardian_set` account and are rejected while it
+    /// expires within `expiration_buffer_seconds`.
+    pub avoid_expiring_guardian_set: bool,
+    pub expiration_buffer_seconds: i64,
+    /// Payers allowed to post with `waive_protocol_fee`.
+    pub allowed_waivers: [Pubkey; ReceiptConfig::MAX_WAIVERS],
+    /// `record_receipt_from_vaa` verifies a trailing Groth16 proof before
+    /// creating the receipt; the record paths that carry no proof fail with
+    /// `InlineVerificationRequired`.
+    pub verify_inline: bool,
+    /// CPIs into the VAA record paths must come from `allowed_cpi_callers`.
+    pub enforce_cpi_callers: bool,
+    pub allowed_cpi_callers: [Pubkey; ReceiptConfig::MAX_CPI_CALLERS],
+    /// Our emitter PDA's address, cached at init; zero on older configs.
+    pub cached_emitter_address: LocalEmitter,
+    /// Core Bridge sequence tracker of our emitter, cached at init.
+    pub cached_sequence: Pubkey,
+    /// Lets the VAA record paths run with `evm_chain == 0`, which otherwise
+    /// reads as an unconfigured placeholder.
+    pub allow_unset_chain: bool,
+    /// Canonical bump of our `[b"emitter"]` PDA, cached at init; the post
+    /// paths constrain the emitter with it (`emitter_pda_bump`).
+    pub emitter_bump: u8,
+    /// Posts are rejected while the current slot is below this.
+    pub maintenance_until_slot: u64,
+    /// Allowlisted emitters are EVM addresses: each must have 12 leading zero
+    /// bytes. Off for native 32-byte emitters.
+    pub evm_emitters: bool,
+    /// Key allowed to change fees and fee waivers; unset means the admin.
+    pub fee_authority: Pubkey,
+    /// Each post's batch_id must exceed the previous one on its emitter's
+    /// OutboundCursor.
+    pub enforce_batch_monotonic: bool,
+    /// Also log each `ReceiptRecorded` as a compact `sol_log_data` blob for
+    /// bandwidth-limited indexers; the Anchor event is always emitted.
+    pub compact_receipt_logs: bool,
+    /// Close-only keeper: may close receipts and nothing else; default key
+    /// disables.
+    pub close_authority: Pubkey,
+    /// Slots a newly allowlisted emitter waits before the record paths accept it.
+    pub emitter_activation_delay_slots: u64,
+    /// Slot from which each allowlist entry is accepted, indexed like the
+    /// allowlist (`emitter`, then `extra_emitters`); 0 means active.
+    pub emitter_active_at_slot: [u64; ReceiptConfig::MAX_EMITTERS],
+    /// Source chain each allowlist entry is pinned to, indexed like
+    /// `emitter_active_at_slot`: its VAAs must carry that `emitter_chain`.
+    /// 0 leaves the entry unpinned. Set by `register_chain_emitter`; an
+    /// address may hold one entry per chain.
+    pub emitter_chains: [u16; ReceiptConfig::MAX_EMITTERS],
+    /// Posts awaiting their round trip, counted by `post_wormhole_message`'s
+    /// `batch_marker`; the receipt whose payload header names the marker's
+    /// sequence (the post made under that batch_id) releases it. While
+    /// `max_in_flight` is set, primary posts must pass a marker and the post
+    /// paths without one fail with `BatchMarkerRequired`.
+    pub in_flight: u32,
+    /// Cap on `in_flight`; new marked posts fail with `TooManyInFlight` at the
+    /// cap. 0 disables the cap, which also frees posting if round trips were lost.
+    pub max_in_flight: u32,
+    /// Leading payload bytes left out of stored `payload_hash`es, e.g. our
+    /// 8-byte routing header, so the hash matches the EVM side's commitment
+    /// to the body alone. 0 hashes the whole payload.
+    pub hash_prefix_skip: u16,
+    /// Core Bridge program the config was set up against; record paths reject
+    /// a different `wormhole_program`. Default (configs created before this
+    /// field) skips the check.
+    pub wormhole_program_id: Pubkey,
+    /// Minimum slots between posts per emitter, on every post path, tracked
+    /// on its OutboundRate; 0 disables the throttle.
+    pub min_post_interval_slots: u64,
+    /// Record paths reject VAAs with nonce 0 (`ZeroNonceNotAllowed`). Off, so
+    /// zero nonces are accepted, for emitters that never set one; zeroed
+    /// `reserved` bytes of older configs read the same way. The nonce is
+    /// stored as observed either way.
+    pub reject_zero_nonce: bool,
+    /// Whether `default_finality` is set.
+    pub has_default_finality: bool,
+    /// Finality flag for posts that pass none (0 = Confirmed, 1 = Finalized).
+    pub default_finality: u8,
+    /// Post paths require payloads framed as `FRAMING_MAGIC | schema_version |
+    /// body_len | body` (`InvalidFraming` otherwise). Off by default.
+    pub enforce_framing: bool,
+    /// `record_receipt_from_vaa` also counts guardian signatures over the
+    /// VAA body from Secp256k1 instructions in the transaction and requires
+    /// a quorum of the TrustedGuardianSet, rather than trusting the PostedVAA
+    /// alone. The other VAA record paths are refused while it is set.
+    pub verify_signatures_independently: bool,
+    /// Receipts an emitter may hold at once, counted on its
+    /// EmitterReceiptCount by every record path and released by closes, cap
+    /// or not; recording at the cap fails with `EmitterReceiptCapReached`. 0
+    /// is unlimited.
+    pub max_receipts_per_emitter: u64,
+    /// Lamports the Core Bridge fee may exceed a post's `expected_fee` by
+    /// before it fails with `FeeAboveSlippage`; the on-chain fee is what is
+    /// paid. Posts without `expected_fee` are not checked.
+    pub fee_slippage: u64,
+    /// Sequences `record_receipt_from_vaa` accepts below its cursor after a
+    /// source-chain reorg re-delivers them: up to this many below
+    /// `last_sequence` are recorded (if not already) without moving the
+    /// cursor, emitting `ReorgReceiptRecorded`. 0 keeps strict monotonicity.
+    /// Receipts inside the window cannot be closed, so a closed sequence is
+    /// never recorded twice.
+    pub reorg_tolerance: u64,
+    /// Seconds a receipt must have existed (since `posted_timestamp`) before
+    /// `attest_receipt` may mark it `Verified`, a challenge window for
+    /// watchers to dispute it first; younger receipts fail with
+    /// `VerifyTooSoon`. 0 disables the delay.
+    pub min_verify_age_seconds: i64,
+    /// VAA record paths store `Receipt::field_root` (see
+    /// `DecodedVaa::field_root`) for `verify_field`; off leaves it zero and
+    /// skips the hashing.
+    pub store_field_root: bool,
+    /// VAA record paths accept only emitters registered for the VAA's chain
+    /// with `register_chain_emitter`; unpinned allowlist entries then accept
+    /// nothing. Off, an unpinned entry accepts its emitter from any chain.
+    pub require_chain_pins: bool,
+    /// `record_receipt_from_vaa` requires the ExpectedDigest registered for
+    /// the VAA's batch_id, so every delivered result is checked against one;
+    /// the other VAA record paths are rejected.
+    pub require_expected_digest: bool,
+    /// Record paths require the HashIndex PDA of the payload hash, so every
+    /// receipt is indexed and cross-sequence duplicates are always caught.
+    pub require_hash_index: bool,
+    /// `record_receipt_from_vaa` requires the emitter's cursor, so each
+    /// receipt links to the previous one, and the sequence-keyed record paths
+    /// that cannot link are rejected.
+    pub link_receipts: bool,
+    /// Set by `init_event_ring`; every record path then requires the EventRing.
+    pub event_ring_enabled: bool,
+    /// VAA record paths require the payload to be exactly this many 32-byte
+    /// ABI words (`validate_abi_layout`); 0 disables the check.
+    pub abi_payload_words: u32,
+    /// Canonical bump of our `[SHADOW_EMITTER_SEED]` PDA, cached at init.
+    pub shadow_emitter_bump: u8,
+    /// Zeroed space for future fields. A new field goes in front of it and
+    /// shrinks `RESERVED_LEN` by its size, so `SIZE` stays the same and
+    /// existing configs need no realloc. When it runs out, `SIZE` grows and
+    /// existing configs are brought up with `migrate_config`.
+    pub reserved: [u8; ReceiptConfig::RESERVED_LEN],
+}
+impl ReceiptConfig {
+    pub const SIZE: usize = 32 + 2 + 32 + 1 + 4 + 32 + 8 + 1 + 8 + 8 + 32 + 8 + 8 + 32
+        + FeeAsset::SIZE + 1 + 8 + 4 + 8 + 8 + 1 + 1 + 1 + 1 + 1
+        + 2 * Self::MAX_CATEGORIES + 1
+        + 32 * (Self::MAX_EMITTERS - 1) + 1 + 1 + 2 + 1 + 1
+        + 32 * Self::MAX_TREASURIES + 8 + 8 + 16 + 1 + 8
+        + 32 * Self::MAX_WAIVERS + 1
+        + 1 + 32 * Self::MAX_CPI_CALLERS + 32 + 32 + 1
+        + 1 + 8 + 1 + 32 + 1 + 1 + 32 + 8 + 8 * Self::MAX_EMITTERS + 2 * Self::MAX_EMITTERS
+        + 4 + 4 + 2 + 32 + 8 + 1 + 1 + 1 + 8 + 1 + 1 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 1 + 1 + 4 + 1 + Self::RESERVED_LEN;
+    /// Bytes left in `reserved`.
+    pub const RESERVED_LEN: usize = 0;
+    pub const MAX_CATEGORIES: usize = 8;
+    pub const MAX_EMITTERS: usize = 8;
+    pub const MAX_TREASURIES: usize = 4;
+    pub const MAX_WAIVERS: usize = 4;
+    pub const MAX_CPI_CALLERS: usize = 4;
+
+    /// True if `key` may close receipts: the admin, the reaper or the close
+    /// authority (the latter two only when set).
+    pub fn can_close_receipts(&self, key: &Pubkey) -> bool {
+        *key == self.admin
+            || [self.receipt_reaper, self.close_authority]
+                .iter()
+                .any(|k| *k != Pubkey::default() && k == key)
+    }
+
+    /// Bump of the `role` emitter PDA for its seeds constraint: the one
+    /// cached at init, or derived for configs created before it was cached.
+    pub fn emitter_pda_bump(&self, role: u8) -> u8 {
+        let cached = if role == EMITTER_ROLE_SHADOW {
+            self.shadow_emitter_bump
+        } else {
+            self.emitter_bump
+        };
+        if cached != 0 {
+            return cached;
+        }
+        Pubkey::find_program_address(&[emitter_seed(role)], &crate::ID).1
+    }
+
+    /// Fails with `WormholeProgramMismatch` unless `wormhole_program` is the
+    /// pinned `wormhole_program_id` (when set).
+    pub fn check_wormhole_program(&self, wormhole_program: &Pubkey) -> Result<()> {
+        require!(
+            self.wormhole_program_id == Pubkey::default()
+                || self.wormhole_program_id == *wormhole_program,
+            ZkError::WormholeProgramMismatch
+        );
+        Ok(())
+    }
+
+    /// Stored `payload_hash` of `decoded`: `hash_algo` over the payload past
+    /// `hash_prefix_skip`; fails with `PrefixSkipTooLarge` on a shorter payload.
+    pub fn payload_hash(&self, decoded: &vaa::DecodedVaa) -> Result<[u8; 32]> {
+        decoded.payload_hash(self.hash_algo, self.hash_prefix_skip)
+    }
+
+    /// `Receipt::field_root` of `decoded` with stored `payload_hash`; zero
+    /// unless `store_field_root`.
+    pub fn field_root(&self, decoded: &vaa::DecodedVaa, payload_hash: &[u8; 32]) -> [u8; 32] {
+        if self.store_field_root {
+            decoded.field_root(payload_hash)
+        } else {
+            [0u8; 32]
+        }
+    }
+
+    /// `finality_flag`, or `default_finality` when `None`; fails with
+    /// `NoDefaultFinality` if neither is given.
+    pub fn resolve_finality(&self, finality_flag: Option<u8>) -> Result<u8> {
+        match finality_flag {
+            Some(flag) => Ok(flag),
+            None if self.has_default_finality => Ok(self.default_finality),
+            None => err!(ZkError::NoDefaultFinality),
+        }
+    }
+
+    /// Counts a post awaiting its round trip, failing with `TooManyInFlight`
+    /// once `max_in_flight` (0 = no cap) are outstanding.
+    pub fn note_post_in_flight(&mut self) -> Result<()> {
+        require!(
+            self.max_in_flight == 0 || self.in_flight < self.max_in_flight,
+            ZkError::TooManyInFlight
+        );
+        self.in_flight = self.in_flight.saturating_add(1);
+        Ok(())
+    }
+
+    /// Signer required by the fee instructions: `fee_authority`, or the admin
+    /// while it is unset.
+    pub fn effective_fee_authority(&self) -> Pubkey {
+        if self.fee_authority == Pubkey::default() {
+            self.admin
+        } else {
+            self.fee_authority
+        }
+    }
+
+    /// True if `payer` may waive the protocol fee.
+    pub fn waiver_allowed(&self, payer: &Pubkey) -> bool {
+        *payer != Pubkey::default() && self.allowed_waivers.contains(payer)
+    }
+
+    /// First slot a config change is allowed again, if `slot` is still within
+    /// the cooldown.
+    pub fn config_change_blocked_until(&self, slot: u64) -> Option<u64> {
+        if self.config_change_cooldown_slots == 0 || self.last_config_change_slot == 0 {
+            return None;
+        }
+        let next = self
+            .last_config_change_slot
+            .saturating_add(self.config_change_cooldown_slots);
+        (slot < next).then_some(next)
+    }
+
+    /// Our emitter address: the cached value, or derived for configs that
+    /// predate the cache.
+    pub fn local_emitter(&self) -> LocalEmitter {
+        if self.cached_emitter_address.0 != [0u8; 32] {
+            self.cached_emitter_address
+        } else {
+            local_emitter_address()
+        }
+    }
+
+    /// True if `treasury` is on the treasury allowlist, or the list is empty.
+    pub fn treasury_allowed(&self, treasury: &Pubkey) -> bool {
+        self.treasury_allowlist.iter().all(|t| *t == Pubkey::default())
+            || (*treasury != Pubkey::default() && self.treasury_allowlist.contains(treasury))
+    }
+
+    /// True if `emitter` is on the allowlist (`emitter` plus `extra_emitters`).
+    /// A one-entry allowlist is a single compare, so single-emitter
+    /// deployments pay nothing for the list.
+    pub fn emitter_allowed(&self, emitter: &[u8; 32]) -> bool {
+        let emitter = RemoteEmitter(*emitter);
+        if self.extra_emitter_count == 0 {
+            return self.emitter == emitter;
+        }
+        self.emitter == emitter
+            || self.extra_emitters[..self.extra_emitter_count as usize].contains(&emitter)
+    }
+
+    /// Position of `emitter` in the allowlist (`emitter` first), the index of
+    /// the per-entry arrays; `None` if not listed.
+    fn emitter_position(&self, emitter: &[u8; 32]) -> Option<usize> {
+        let emitter = RemoteEmitter(*emitter);
+        std::iter::once(&self.emitter)
+            .chain(&self.extra_emitters[..self.extra_emitter_count as usize])
+            .position(|e| *e == emitter)
+    }
+
+    /// Activation slot of an allowlisted `emitter`; `None` if not listed.
+    pub fn emitter_active_at(&self, emitter: &[u8; 32]) -> Option<u64> {
+        self.emitter_position(emitter).map(|i| self.emitter_active_at_slot[i])
+    }
+
+    /// Chain the first allowlist entry of `emitter` is pinned to (0 =
+    /// unpinned); `None` if not listed.
+    pub fn emitter_chain(&self, emitter: &[u8; 32]) -> Option<u16> {
+        self.emitter_position(emitter).map(|i| self.emitter_chains[i])
+    }
+
+    /// Position of the `(emitter, chain)` allowlist entry; `chain` 0 finds an
+    /// unpinned entry.
+    pub fn chain_emitter_position(&self, emitter: &[u8; 32], chain: u16) -> Option<usize> {
+        let emitter = RemoteEmitter(*emitter);
+        std::iter::once(&self.emitter)
+            .chain(&self.extra_emitters[..self.extra_emitter_count as usize])
+            .zip(&self.emitter_chains)
+            .position(|(e, c)| *e == emitter && *c == chain)
+    }
+
+    /// Entry accepting `emitter`'s VAAs from `chain`: the one pinned to
+    /// `chain`, else an unpinned one unless `require_chain_pins` is set.
+    pub fn emitter_entry(&self, emitter: &[u8; 32], chain: u16) -> Option<usize> {
+        self.chain_emitter_position(emitter, chain)
+            .filter(|_| chain != 0)
+            .or_else(|| {
+                self.chain_emitter_position(emitter, 0)
+                    .filter(|_| !self.require_chain_pins)
+            })
+    }
+
+    /// Appends `emitter` to `extra_emitters`, active from `active_at_slot`,
+    /// rejecting duplicates.
+    pub fn add_emitter(&mut self, emitter: RemoteEmitter, active_at_slot: u64) -> Result<()> {
+        require!(!self.emitter_allowed(&emitter.0), ZkError::EmitterAlreadyPresent);
+        self.push_emitter(emitter, active_at_slot, 0)
+    }
+
+    /// Appends the `(emitter, chain)` entry, active from `active_at_slot`,
+    /// rejecting a duplicate pair.
+    pub fn push_emitter(&mut self, emitter: RemoteEmitter, active_at_slot: u64, chain: u16) -> Result<()> {
+        check_allowlist_emitter(&self.local_emitter(), &emitter)?;
+        check_emitter_format(self.evm_emitters, &emitter)?;
+        require!(
+            self.chain_emitter_position(&emitter.0, chain).is_none(),
+            ZkError::EmitterAlreadyPresent
+        );
+        let count = self.extra_emitter_count as usize;
+        require!(count < Self::MAX_EMITTERS - 1, ZkError::TooManyEmitters);
+        self.extra_emitters[count] = emitter;
+        self.emitter_active_at_slot[count + 1] = active_at_slot;
+        self.emitter_chains[count + 1] = chain;
+        self.extra_emitter_count += 1;
+        Ok(())
+    }
+
+    /// Removes every entry of `emitter`, whatever its chain.
+    pub fn remove_emitter(&mut self, emitter: RemoteEmitter) -> Result<()> {
+        let first = self.emitter_position(&emitter.0).ok_or(error!(ZkError::EmitterNotFound))?;
+        self.remove_entry(first)?;
+        while let Some(i) = self.emitter_position(&emitter.0) {
+            self.remove_entry(i)?;
+        }
+        Ok(())
+    }
+
+    /// Removes allowlist entry `list_pos` (`emitter` first); removing
+    /// `self.emitter` promotes the first extra.
+    pub fn remove_entry(&mut self, list_pos: usize) -> Result<()> {
+        let count = self.extra_emitter_count as usize;
+        require!(list_pos <= count, ZkError::EmitterNotFound);
+        // `pos` indexes `extra_emitters`; `list_pos` indexes the whole list
+        // (`emitter` first), as `emitter_active_at_slot` does.
+        let pos = if list_pos == 0 {
+            require!(count > 0, ZkError::NoEmittersProvided);
+            self.emitter = self.extra_emitters[0];
+            0
+        } else {
+            list_pos - 1
+        };
+        self.extra_emitters.copy_within(pos + 1..count, pos);
+        self.extra_emitters[count - 1] = RemoteEmitter([0u8; 32]);
+        self.emitter_active_at_slot.copy_within(list_pos + 1..count + 1, list_pos);
+        self.emitter_active_at_slot[count] = 0;
+        self.emitter_chains.copy_within(list_pos + 1..count + 1, list_pos);
+        self.emitter_chains[count] = 0;
+        self.extra_emitter_count -= 1;
+        Ok(())
+    }
+
+    /// True if `category` is allowlisted, or the allowlist is empty.
+    pub fn category_allowed(&self, category: u16) -> bool {
+        let allowed = &self.allowed_categories[..self.allowed_category_count as usize];
+        allowed.is_empty() || allowed.contains(&category)
+    }
+
+    /// Applies the `Some` fields of `update`, validating each.
+    pub fn apply_update(&mut self, update: &ReceiptConfigUpdate) -> Result<()> {
+        if let Some(min_len) = update.finalized_min_payload_len {
+            self.finalized_min_payload_len = min_len;
+        }
+        if let Some(min_age) = update.min_age_seconds {
+            require!(min_age >= 0, ZkError::InvalidMinAge);
+            self.min_age_seconds = min_age;
+        }
+        if let Some(strict) = update.strict_fee_collector {
+            self.strict_fee_collector = strict;
+        }
+        if let Some(max_age) = update.max_vaa_age_seconds {
+            require!(max_age >= 0, ZkError::InvalidMaxVaaAge);
+            self.max_vaa_age_seconds = max_age;
+        }
+        if let Some(skew) = update.clock_skew_tolerance_seconds {
+            require!(skew >= 0, ZkError::InvalidClockSkew);
+            self.clock_skew_tolerance_seconds = skew;
+        }
+        if let Some(track) = update.track_attempts {
+            self.track_attempts = track;
+        }
+        if let Some(level) = update.min_record_consistency {
+            self.min_record_consistency = level;
+        }
+        if let Some(algo) = update.hash_algo {
+            require!(
+                algo == HASH_ALGO_KECCAK256 || algo == HASH_ALGO_SHA256,
+                ZkError::UnknownHashAlgo
+            );
+            self.hash_algo = algo;
+        }
+        if let Some(enforce) = update.enforce_length_prefix {
+            self.enforce_length_prefix = enforce;
+        }
+        if let Some(verbose) = update.verbose {
+            self.verbose = verbose;
+        }
+        if let Some(max) = update.max_batch_size {
+            require!(
+                (1..=MAX_BATCH_SIZE_CEILING).contains(&max),
+                ZkError::InvalidMaxBatchSize
+            );
+            self.max_batch_size = max;
+        }
+        if let Some(require_pda) = update.require_pda_message {
+            self.require_pda_message = require_pda;
+        }
+        if let Some(per_category) = update.per_category_cursors {
+            self.per_category_cursors = per_category;
+        }
+        if let Some(cooldown) = update.config_change_cooldown_slots {
+            self.config_change_cooldown_slots = cooldown;
+        }
+        if let Some(verify_inline) = update.verify_inline {
+            self.verify_inline = verify_inline;
+        }
+        if let Some(avoid) = update.avoid_expiring_guardian_set {
+            self.avoid_expiring_guardian_set = avoid;
+        }
+        if let Some(buffer) = update.expiration_buffer_seconds {
+            require!(buffer >= 0, ZkError::InvalidExpirationBuffer);
+            self.expiration_buffer_seconds = buffer;
+        }
+        if let Some(skip) = update.skip_post_payload_hash {
+            self.skip_post_payload_hash = skip;
+        }
+        if let Some(ring_size) = update.ring_size {
+            self.ring_size = ring_size;
+        }
+        if let Some(timelock) = update.timelock_seconds {
+            require!(timelock >= 0, ZkError::InvalidTimelock);
+            self.timelock_seconds = timelock;
+        }
+        if let Some(evm_chain) = update.evm_chain {
+            self.evm_chain = evm_chain;
+        }
+        if let Some(allow) = update.allow_unset_chain {
+            self.allow_unset_chain = allow;
+        }
+        if let Some(evm) = update.evm_emitters {
+            // Turning the check on validates the allowlist already stored.
+            let extras = &self.extra_emitters[..self.extra_emitter_count as usize];
+            for emitter in std::iter::once(&self.emitter).chain(extras) {
+                check_emitter_format(evm, emitter)?;
+            }
+            self.evm_emitters = evm;
+        }
+        if let Some(enforce) = update.enforce_batch_monotonic {
+            self.enforce_batch_monotonic = enforce;
+        }
+        if let Some(compact) = update.compact_receipt_logs {
+            self.compact_receipt_logs = compact;
+        }
+        if let Some(delay) = update.emitter_activation_delay_slots {
+            self.emitter_activation_delay_slots = delay;
+        }
+        if let Some(max) = update.max_in_flight {
+            self.max_in_flight = max;
+        }
+        if let Some(skip) = update.hash_prefix_skip {
+            self.hash_prefix_skip = skip;
+        }
+        if let Some(interval) = update.min_post_interval_slots {
+            self.min_post_interval_slots = interval;
+        }
+        if let Some(reject) = update.reject_zero_nonce {
+            self.reject_zero_nonce = reject;
+        }
+        if let Some(cap) = update.max_receipts_per_emitter {
+            self.max_receipts_per_emitter = cap;
+        }
+        if let Some(enforce) = update.enforce_framing {
+            self.enforce_framing = enforce;
+        }
+        if let Some(verify) = update.verify_signatures_independently {
+            self.verify_signatures_independently = verify;
+        }
+        if let Some(tolerance) = update.reorg_tolerance {
+            self.reorg_tolerance = tolerance;
+        }
+        if let Some(min_age) = update.min_verify_age_seconds {
+            require!(min_age >= 0, ZkError::InvalidMinAge);
+            self.min_verify_age_seconds = min_age;
+        }
+        if let Some(store) = update.store_field_root {
+            self.store_field_root = store;
+        }
+        if let Some(require) = update.require_chain_pins {
+            self.require_chain_pins = require;
+        }
+        if let Some(require) = update.require_expected_digest {
+            self.require_expected_digest = require;
+        }
+        if let Some(require) = update.require_hash_index {
+            self.require_hash_index = require;
+        }
+        if let Some(link) = update.link_receipts {
+            self.link_receipts = link;
+        }
+        if let Some(words) = update.abi_payload_words {
+            self.abi_payload_words = words;
+        }
+        Ok(())
+    }
+
+    /// Protocol fee for the given finality flag (0 = Confirmed, 1 = Finalized).
+    pub fn protocol_fee_for(&self, finality_flag: u8) -> u64 {
+        if finality_flag == 0 {
+            self.protocol_fee_confirmed
+        } else {
+            self.protocol_fee_finalized
+        }
+    }
+}
+
+/// Payers allowed to post through our emitter; unset slots are the default key.
+#[account]
+pub struct PosterConfig {
+    pub enforce: bool,
+    pub allowed_posters: [Pubkey; PosterConfig::MAX_POSTERS],
+    pub bump: u8,
+}
+impl PosterConfig {
+    pub const MAX_POSTERS: usize = 8;
+    pub const SIZE: usize = 1 + 32 * Self::MAX_POSTERS + 1;
+}
+
+/// Denomination of the Core Bridge fee.
+/// - `Sol`: system transfer from payer to fee_collector (today's Core Bridge).
+/// - `Spl`: token transfer; `remaining_accounts[0..3]` are the payer token
+///   account, the fee collector token account and the SPL Token program. Both
+///   token accounts must hold `mint`, and the second must be owned by the
+///   fee collector.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeeAsset {
+    Sol,
+    Spl { mint: Pubkey },
+}
+impl FeeAsset {
+    pub const SIZE: usize = 1 + 32;
+}
+
+/// A config update waiting out the timelock: `[PENDING_CHANGE_SEED]`.
+#[account]
+pub struct PendingChange {
+    pub update: ReceiptConfigUpdate,
+    pub effective_at: i64,
+    pub bump: u8,
+}
+impl PendingChange {
+    pub const SIZE: usize = ReceiptConfigUpdate::SIZE + 8 + 1;
+}
+
+/// An admin call waiting out the timelock:
+/// `[PENDING_ACTION_SEED, action_hash]`.
+#[account]
+pub struct PendingAction {
+    pub action_hash: [u8; 32],
+    pub queued_by: Pubkey,
+    pub effective_at: i64,
+    pub bump: u8,
+}
+impl PendingAction {
+    pub const SIZE: usize = 32 + 32 + 8 + 1;
+}
+
+/// Optional policy updates; `None` leaves a field unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ReceiptConfigUpdate {
+    pub finalized_min_payload_len: Option<u32>,
+    pub min_age_seconds: Option<i64>,
+    pub strict_fee_collector: Option<bool>,
+    pub max_vaa_age_seconds: Option<i64>,
+    pub clock_skew_tolerance_seconds: Option<i64>,
+    pub track_attempts: Option<bool>,
+    pub timelock_seconds: Option<i64>,
+    pub ring_size: Option<u32>,
+    pub skip_post_payload_hash: Option<bool>,
+    pub min_record_consistency: Option<u8>,
+    pub hash_algo: Option<u8>,
+    pub enforce_length_prefix: Option<bool>,
+    pub verbose: Option<bool>,
+    pub max_batch_size: Option<u16>,
+    pub require_pda_message: Option<bool>,
+    pub per_category_cursors: Option<bool>,
+    pub config_change_cooldown_slots: Option<u64>,
+    pub avoid_expiring_guardian_set: Option<bool>,
+    pub expiration_buffer_seconds: Option<i64>,
+    pub verify_inline: Option<bool>,
+    pub evm_chain: Option<u16>,
+    pub allow_unset_chain: Option<bool>,
+    pub evm_emitters: Option<bool>,
+    pub enforce_batch_monotonic: Option<bool>,
+    pub compact_receipt_logs: Option<bool>,
+    pub emitter_activation_delay_slots: Option<u64>,
+    pub max_in_flight: Option<u32>,
+    pub hash_prefix_skip: Option<u16>,
+    pub min_post_interval_slots: Option<u64>,
+    pub reject_zero_nonce: Option<bool>,
+    pub max_receipts_per_emitter: Option<u64>,
+    pub enforce_framing: Option<bool>,
+    pub verify_signatures_independently: Option<bool>,
+    pub reorg_tolerance: Option<u64>,
+    pub min_verify_age_seconds: Option<i64>,
+    pub store_field_root: Option<bool>,
+    pub require_chain_pins: Option<bool>,
+    pub require_expected_digest: Option<bool>,
+    pub require_hash_index: Option<bool>,
+    pub link_receipts: Option<bool>,
+    pub abi_payload_words: Option<u32>,
+}
+impl ReceiptConfigUpdate {
+    pub const SIZE: usize =
+        5 + 9 + 2 + 9 + 9 + 2 + 9 + 5 + 2 + 2 + 2 + 2 + 2 + 3 + 2 + 2
+        + 9 + 2 + 9 + 2 + 3 + 2 + 2 + 2 + 2 + 9 + 5 + 3 + 9 + 2 + 9 + 2 + 2 + 9 + 9 + 2 + 2 + 2 + 2 + 2
+        + 5;
+}
+
+/// Receipt account, Borsh-encoded in declaration order with no padding;
+/// integers are little-endian. Byte offsets include the 8-byte discriminator:
+///
+/// | Offset | Size | Field |
+/// |-------:|-----:|-------|
+/// | 0 | 8 | discriminator |
+/// | 8 | 32 | `emitter` |
+/// | 40 | 8 | `sequence` |
+/// | 48 | 32 | `vaa_account` |
+/// | 80 | 8 | `posted_timestamp` |
+/// | 88 | 1 | `bump` |
+/// | 89 | 32 | `payload_hash` |
+/// | 121 | 1 | `source` (variant index) |
+/// | 122 | 32 | `proof_ref` |
+/// | 154 | 4 | `origin_batch_id` |
+/// | 158 | 1 | `round_tripped` |
+/// | 159 | 8 | `posted_slot` |
+/// | 167 | 4 | `nonce` |
+/// | 171 | 1 | `consistency_level` |
+/// | 172 | 1 | `hash_algo` |
+/// | 173 | 2 | `category` |
+/// | 175 | 1 | `status` (variant index) |
+/// | 176 | 32 | `attested_by` |
+/// | 208 | 1 | `proof_verified` |
+/// | 209 | 32 | `external_ref` |
+/// | 241 | 1 | `tombstoned` |
+/// | 242 | 1 | `tombstone_reason` |
+/// | 243 | 8 | `reported_cost` |
+/// | 251 | 1 | `action` |
+/// | 252 | 32 | `vaa_body_hash` |
+/// | 284 | 2 | `hash_prefix_skip` |
+/// | 286 | 32 | `calldata_hash` |
+/// | 318 | 32 | `prev_hash` |
+/// | 350 | 32 | `field_root` |
+/// | 382 | 19 | `reserved` |
+///
+/// New fields are carved from the front of `reserved`, which shrinks by the
+/// same size so `SIZE` and every existing offset stay put, and bump
+/// `LAYOUT_VERSION`. A field larger than `reserved` is inserted in front of
+/// it and grows `SIZE`; older receipts are then brought up with
+/// `migrate_receipt`.
+#[account]
+pub struct Receipt {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub vaa_account: Pubkey,
+    pub posted_timestamp: i64,
+    pub bump: u8,
+    /// Hash under `hash_algo` of the VAA payload bytes alone (the `payload`
+    /// field, without its length prefix or any envelope field) from offset
+    /// `hash_prefix_skip` on; zero for direct receipts. See `vaa_body_hash`
+    /// for the whole observation.
+    pub payload_hash: [u8; 32],
+    pub source: ReceiptSource,
+    /// Verifier-supplied proof reference for external receipts; zero otherwise.
+    pub proof_ref: [u8; 32],
+    /// batch_id of our outbound post this receipt answers; 0 if none.
+    pub origin_batch_id: u32,
+    /// True when matched to a BatchMarker of ours.
+    pub round_tripped: bool,
+    /// Slot at which the receipt was recorded.
+    pub posted_slot: u64,
+    /// VAA envelope nonce; 0 for non-VAA receipts.
+    pub nonce: u32,
+    /// Consistency level observed on the VAA; 0 for non-VAA receipts.
+    pub consistency_level: u8,
+    /// `HASH_ALGO_*` used for `payload_hash`.
+    pub hash_algo: u8,
+    /// Client-defined category for routing; 0 outside `record_receipt_from_vaa`.
+    pub category: u16,
+    /// Set to `Verified` by `attest_receipt`; `Preallocated` until a
+    /// preallocated receipt is recorded.
+    pub status: ReceiptStatus,
+    /// Attestor key (Ed25519 pubkey or left-padded Ethereum address); zero until attested.
+    pub attested_by: [u8; 32],
+    /// Recorded with `cfg.verify_inline` after the payload's proof verified.
+    pub proof_verified: bool,
+    /// Optional off-chain correlation id (job or order id) given at record time;
+    /// informational only, zero when unused.
+    pub external_ref: [u8; 32],
+    /// Set by `tombstone_receipt` to invalidate a disputed receipt while
+    /// keeping it for audit; reversible, unlike closing.
+    pub tombstoned: bool,
+    /// Client-defined reason code for the tombstone; 0 when not tombstoned.
+    pub tombstone_reason: u8,
+    /// Relayer-reported source-chain delivery cost, given to
+    /// `record_receipt_from_vaa`. Untrusted and informational only; 0 elsewhere.
+    pub reported_cost: u64,
+    /// Payload type tag (`DecodedVaa::action`) for VAA receipts; 0 otherwise.
+    pub action: u8,
+    /// Keccak-256 of the whole signed VAA body (`DecodedVaa::body_hash`), the
+    /// canonical VAA hash: `timestamp u32 | nonce u32 | emitter_chain u16 |
+    /// emitter_address [u8; 32] | sequence u64 | consistency_level u8 |
+    /// payload`, integers big-endian. On the wire VAA this is every byte after
+    /// the signatures, `[6 + 66 * num_signatures..]`. Unlike `payload_hash`
+    /// it pins the exact message, envelope included; zero for non-VAA receipts.
+    pub vaa_body_hash: [u8; 32],
+    /// `cfg.hash_prefix_skip` when `payload_hash` was computed.
+    pub hash_prefix_skip: u16,
+    /// Commitment to the EVM calldata the receipt is meant to be executed
+    /// with, given by an admin or operator to `record_receipt_from_vaa`.
+    /// Asserted by the relayer, not derived from the VAA, so a consumer
+    /// checks it only against calldata it is about to submit; zero when not
+    /// given.
+    pub calldata_hash: [u8; 32],
+    /// `link_hash` of the emitter's previous receipt, taken from its
+    /// EmitterCursor when `record_receipt_from_vaa` is given one, so the
+    /// cursor-tracked receipts of an emitter form a hash chain checked by
+    /// `verify_chain`. Zero for the first linked receipt and for receipts
+    /// recorded without a cursor.
+    pub prev_hash: [u8; 32],
+    /// Merkle root over the VAA's emitter, sequence, nonce, timestamp and
+    /// `payload_hash` (`DecodedVaa::field_root` documents the leaves), so
+    /// `verify_field` can prove one of them; zero for non-VAA receipts and
+    /// when `cfg.store_field_root` was off at record time.
+    pub field_root: [u8; 32],
+    /// Zeroed space for future fields; see the layout note above.
+    pub reserved: [u8; Receipt::RESERVED_LEN],
+}
+impl Receipt {
+    pub const SIZE: usize =
+        32 + 8 + 32 + 8 + 1 + 32 + 1 + 32 + 4 + 1 + 8 + 4 + 1 + 1 + 2 + 1 + 32 + 1 + 32
+        + 1 + 1 + 8 + 1 + 32 + 2 + 32 + 32 + 32 + Self::RESERVED_LEN;
+    /// Bytes left in `reserved`; a new field takes its size from here.
+    pub const RESERVED_LEN: usize = 19;
+    /// Version of the byte layout documented above.
+    pub const LAYOUT_VERSION: u8 = 13;
+
+    /// Off-chain mirror encoding: `LAYOUT_VERSION || fields`, i.e. the account
+    /// layout above minus the Anchor discriminator, tagged with its version.
+    pub fn to_mirror_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(1 + Self::SIZE);
+        out.push(Self::LAYOUT_VERSION);
+        AnchorSerialize::serialize(self, &mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Inverse of `to_mirror_bytes`; only the current `LAYOUT_VERSION` is
+    /// accepted, and trailing bytes are rejected.
+    pub fn from_mirror_bytes(data: &[u8]) -> Result<Self> {
+        let (version, mut body) = data.split_first().ok_or(error!(ZkError::MirrorDecode))?;
+        require!(*version == Self::LAYOUT_VERSION, ZkError::MirrorVersionUnsupported);
+        let receipt = Self::deserialize(&mut body).map_err(|_| error!(ZkError::MirrorDecode))?;
+        require!(body.is_empty(), ZkError::MirrorDecode);
+        Ok(receipt)
+    }
+
+    /// Length of `to_export_bytes`.
+    pub const EXPORT_LEN: usize = 32 + 8 + 8 + 32 + 1 + 1 + 1 + 1;
+    /// Most records `export_receipts` fits in return data after its count byte.
+    pub const EXPORT_MAX: usize = (MAX_RETURN_DATA - 1) / Self::EXPORT_LEN;
+
+    /// Compact export record, integers little-endian:
+    ///
+    /// | Offset | Size | Field |
+    /// |-------:|-----:|-------|
+    /// | 0 | 32 | `emitter` |
+    /// | 32 | 8 | `sequence` |
+    /// | 40 | 8 | `posted_slot` |
+    /// | 48 | 32 | `payload_hash` |
+    /// | 80 | 1 | `source` (variant index) |
+    /// | 81 | 1 | `status` (variant index) |
+    /// | 82 | 1 | `tombstoned` |
+    /// | 83 | 1 | `action` |
+    pub fn to_export_bytes(&self) -> [u8; Self::EXPORT_LEN] {
+        let mut out = [0u8; Self::EXPORT_LEN];
+        out[..32].copy_from_slice(&self.emitter);
+        out[32..40].copy_from_slice(&self.sequence.to_le_bytes());
+        out[40..48].copy_from_slice(&self.posted_slot.to_le_bytes());
+        out[48..80].copy_from_slice(&self.payload_hash);
+        out[80] = self.source as u8;
+        out[81] = self.status as u8;
+        out[82] = u8::from(self.tombstoned);
+        out[83] = self.action;
+        out
+    }
+
+    /// Receipt identity: `emitter || sequence_be || payload_hash`.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        [&self.emitter[..], &self.sequence.to_be_bytes(), &self.payload_hash].concat()
+    }
+
+    /// Message an attestor signs for `attest_receipt`:
+    /// `ATTEST_RECEIPT_TAG || program_id || canonical_bytes`.
+    pub fn attestation_message(&self) -> Vec<u8> {
+        [ATTEST_RECEIPT_TAG, crate::ID.as_ref(), &self.canonical_bytes()].concat()
+    }
+
+    /// Chain link to this receipt, stored as the next receipt's `prev_hash`:
+    /// `keccak(canonical_bytes || prev_hash)`, so it commits to every earlier
+    /// link as well.
+    pub fn link_hash(&self) -> [u8; 32] {
+        keccak::hashv(&[&self.canonical_bytes(), &self.prev_hash]).to_bytes()
+    }
+
+    /// False for a freshly allocated (zeroed) or preallocated receipt account.
+    pub fn is_recorded(&self) -> bool {
+        self.emitter != [0u8; 32] && self.status != ReceiptStatus::Preallocated
+    }
+
+    /// Fails with `ReceiptTombstoned` if the receipt has been tombstoned, or
+    /// `ReceiptPreallocated` if it was preallocated and never recorded;
+    /// checks that consume a receipt as evidence call this first.
+    pub fn require_live(&self) -> Result<()> {
+        require!(!self.tombstoned, ZkError::ReceiptTombstoned);
+        require!(
+            self.status != ReceiptStatus::Preallocated,
+            ZkError::ReceiptPreallocated
+        );
+        Ok(())
+    }
+}
+
+/// Fixture for downstream tests: a `Wormhole`-sourced receipt for `emitter`
+/// and `sequence` at its canonical PDA bump, hashing `payload_hash` with
+/// keccak-256. Every other field is zero or its default, so the result is
+/// deterministic; adjust fields on the returned value as needed.
+#[cfg(feature = "test-helpers")]
+pub fn make_receipt(emitter: [u8; 32], sequence: u64, payload_hash: [u8; 32]) -> Receipt {
+    Receipt {
+        emitter,
+        sequence,
+        vaa_account: Pubkey::default(),
+        posted_timestamp: 0,
+        bump: receipt_pda(&emitter, sequence).1,
+        payload_hash,
+        source: ReceiptSource::Wormhole,
+        proof_ref: [0u8; 32],
+        origin_batch_id: 0,
+        round_tripped: false,
+        posted_slot: 0,
+        nonce: 0,
+        consistency_level: 0,
+        hash_algo: HASH_ALGO_KECCAK256,
+        hash_prefix_skip: 0,
+        category: 0,
+        status: ReceiptStatus::Unverified,
+        attested_by: [0u8; 32],
+        proof_verified: false,
+        external_ref: [0u8; 32],
+        tombstoned: false,
+        tombstone_reason: 0,
+        reported_cost: 0,
+        action: 0,
+        vaa_body_hash: [0u8; 32],
+        calldata_hash: [0u8; 32],
+        prev_hash: [0u8; 32],
+        field_root: [0u8; 32],
+        reserved: [0u8; Receipt::RESERVED_LEN],
+    }
+}
+
+/// Account data for `receipt` as the program stores it: the Anchor
+/// discriminator followed by the Borsh fields, `8 + Receipt::SIZE` bytes.
+/// Loads back with `Receipt::try_deserialize`.
+#[cfg(feature = "test-helpers")]
+pub fn receipt_account_data(receipt: &Receipt) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + Receipt::SIZE);
+    receipt.try_serialize(&mut data).expect("writing to a Vec cannot fail");
+    data
+}
+
+/// Ring of an emitter's most recent receipts: `[EMITTER_INDEX_SEED, emitter]`.
+/// Zero-copy and read/written in place through `AccountLoader`, so its size
+/// never costs a full deserialize.
+#[account(zero_copy)]
+pub struct EmitterIndex {
+    pub emitter: [u8; 32],
+    /// Slot the next entry is written to.
+    pub head: u32,
+    /// Valid entries, up to `CAPACITY`.
+    pub count: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub entries: [IndexEntry; EmitterIndex::CAPACITY],
+}
+impl EmitterIndex {
+    pub const CAPACITY: usize = 200;
+    pub const SIZE: usize = 32 + 4 + 4 + 1 + 7 + IndexEntry::SIZE * Self::CAPACITY;
+
+    /// Appends an entry, overwriting the oldest once full.
+    pub fn push(&mut self, sequence: u64, payload_hash: [u8; 32]) {
+        self.entries[self.head as usize] = IndexEntry { sequence, payload_hash };
+        self.head = (self.head + 1) % Self::CAPACITY as u32;
+        self.count = (self.count + 1).min(Self::CAPACITY as u32);
+    }
+}
+
+/// Append-only log of an emitter's receipts: `[RECEIPT_LOG_SEED, emitter]`.
+/// One account instead of a PDA per receipt; records are never overwritten.
+#[account(zero_copy)]
+pub struct ReceiptLog {
+    pub emitter: [u8; 32],
+    /// Records written so far, up to `capacity`.
+    pub count: u32,
+    /// `CAPACITY`, stored for clients reading the raw account.
+    pub capacity: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub records: [LogRecord; ReceiptLog::CAPACITY],
+}
+impl ReceiptLog {
+    /// Keeps the account under the 10 KiB CPI allocation limit.
+    pub const CAPACITY: usize = 160;
+    pub const SIZE: usize = 32 + 4 + 4 + 1 + 7 + LogRecord::SIZE * Self::CAPACITY;
+
+    /// Appends `record` and returns its index.
+    pub fn append(&mut self, record: LogRecord) -> Result<u32> {
+        let count = self.count as usize;
+        require!(count < Self::CAPACITY, ZkError::LogFull);
+        require!(
+            !self.records[..count].iter().any(|r| r.sequence == record.sequence),
+            ZkError::AlreadyInLog
+        );
+        self.records[count] = record;
+        self.count += 1;
+        Ok(count as u32)
+    }
+}
+
+/// The last `CAPACITY` `ReceiptRecorded` events, for pollers catching up
+/// after downtime: `[EVENT_RING_SEED]`. Entry `i` (counted over the ring's
+/// lifetime) lives at `entries[i % CAPACITY]`, so once `head` passes
+/// `CAPACITY` each write overwrites the oldest entry and indices below
+/// `head - CAPACITY` are gone; replay those from transaction logs.
+#[account(zero_copy)]
+pub struct EventRing {
+    /// Entries written over the ring's lifetime, i.e. the next entry's index.
+    pub head: u64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub entries: [EventRingEntry; EventRing::CAPACITY],
+}
+impl EventRing {
+    pub const CAPACITY: usize = 128;
+    /// Entries per `read_event_ring` call, within the 1 KiB return data limit.
+    pub const READ_MAX: usize = 16;
+    pub const SIZE: usize = 8 + 1 + 7 + EventRingEntry::SIZE * Self::CAPACITY;
+
+    pub fn push(&mut self, entry: EventRingEntry) {
+        self.entries[(self.head % Self::CAPACITY as u64) as usize] = entry;
+        self.head += 1;
+    }
+
+    /// Index of the oldest entry still held.
+    pub fn oldest(&self) -> u64 {
+        self.head.saturating_sub(Self::CAPACITY as u64)
+    }
+}
+
+#[zero_copy]
+pub struct EventRingEntry {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    /// `event_seq` of the `ReceiptRecorded` event.
+    pub event_seq: u64,
+    pub slot: u64,
+}
+impl EventRingEntry {
+    pub const SIZE: usize = 32 + 8 + 8 + 8;
+}
+
+#[zero_copy]
+pub struct LogRecord {
+    pub sequence: u64,
+    pub posted_timestamp: i64,
+    pub payload_hash: [u8; 32],
+    pub nonce: u32,
+    pub consistency_level: u8,
+    pub _padding: [u8; 3],
+}
+impl LogRecord {
+    pub const SIZE: usize = 8 + 8 + 32 + 4 + 1 + 3;
+}
+
+#[zero_copy]
+pub struct IndexEntry {
+    pub sequence: u64,
+    pub payload_hash: [u8; 32],
+}
+impl IndexEntry {
+    pub const SIZE: usize = 8 + 32;
+}
+
+/// Groth16 verifying key of one circuit for `cfg.verify_inline`:
+/// `[VERIFYING_KEY_SEED, circuit_id_le]`. Points are EIP-197 encoded;
+/// `ic[..ic_len]` holds one point per public input plus the constant term.
+#[account]
+pub struct VerifyingKey {
+    pub circuit_id: u32,
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic_len: u8,
+    pub ic: [[u8; 64]; VerifyingKey::MAX_PUBLIC_INPUTS + 1],
+    pub bump: u8,
+}
+impl VerifyingKey {
+    pub const MAX_PUBLIC_INPUTS: usize = 4;
+    pub const SIZE: usize = 4 + 64 + 128 * 3 + 1 + 64 * (Self::MAX_PUBLIC_INPUTS + 1) + 1;
+}
+
+/// Guardian set `verify_signatures_independently` checks VAAs against:
+/// `[TRUSTED_GUARDIAN_SET_SEED]`. Copied in by the admin from the Core
+/// Bridge's set `index`, so it is trusted as configured, not read from
+/// Wormhole state.
+#[account]
+pub struct TrustedGuardianSet {
+    pub index: u32,
+    pub key_count: u8,
+    /// Guardian Ethereum addresses; `keys[..key_count]` are in use.
+    pub keys: [[u8; 20]; TrustedGuardianSet::MAX_GUARDIANS],
+    pub bump: u8,
+    /// Slot of the last `set_trusted_guardian_set`.
+    pub updated_slot: u64,
+}
+impl TrustedGuardianSet {
+    pub const MAX_GUARDIANS: usize = 19;
+    pub const SIZE: usize = 4 + 1 + 20 * Self::MAX_GUARDIANS + 1 + 8;
+
+    /// Signatures needed, as in Core Bridge: more than two thirds.
+    pub fn quorum(&self) -> usize {
+        self.key_count as usize * 2 / 3 + 1
+    }
+}
+
+/// Payload hash our posting service expects back for a batch_id:
+/// `[EXPECTED_DIGEST_SEED, batch_id_le]`. Registered once, never overwritten.
+#[account]
+pub struct ExpectedDigest {
+    pub batch_id: u32,
+    /// Expected receipt `payload_hash` (under `cfg.hash_algo`, past
+    /// `cfg.hash_prefix_skip`).
+    pub digest: [u8; 32],
+    pub registered_by: Pubkey,
+    pub bump: u8,
+}
+impl ExpectedDigest {
+    pub const SIZE: usize = 4 + 32 + 32 + 1;
+}
+
+/// Last recorded sequence for an emitter: `[EMITTER_CURSOR_SEED, emitter]`, or
+/// `[EMITTER_CURSOR_SEED, emitter, category_le]` with `per_category_cursors`.
+#[account]
+pub struct EmitterCursor {
+    pub emitter: [u8; 32],
+    /// Category this cursor tracks; 0 for a per-emitter cursor.
+    pub category: u16,
+    pub last_sequence: u64,
+    pub bump: u8,
+    /// `Receipt::link_hash` of the last receipt recorded through this cursor,
+    /// the next one's `prev_hash`; zero before the first.
+    pub last_receipt_hash: [u8; 32],
+}
+impl EmitterCursor {
+    pub const SIZE: usize = 32 + 2 + 8 + 1 + 32;
+}
+
+/// Receipts currently held for an emitter, for `max_receipts_per_emitter`:
+/// `[EMITTER_COUNT_SEED, emitter]`. Every record path counts a new receipt
+/// and each close releases one. Both require it whether or not the cap is
+/// set, so enabling the cap later enforces it against the true count.
+#[account]
+pub struct EmitterReceiptCount {
+    pub emitter: [u8; 32],
+    pub count: u64,
+    pub bump: u8,
+    /// Receipts ever counted here; unlike `count`, closes don't lower it.
+    pub total_recorded: u64,
+    /// Receipts of this emitter closed since the counter was created.
+    pub total_closed: u64,
+}
+impl EmitterReceiptCount {
+    pub const SIZE: usize = 32 + 8 + 1 + 8 + 8;
+
+    /// Releases one held receipt on close.
+    fn note_closed(&mut self) {
+        self.count = self.count.saturating_sub(1);
+        self.total_closed = self.total_closed.saturating_add(1);
+    }
+}
+
+/// Per-emitter recording switch: `[EMITTER_STATE_SEED, emitter]`, created by
+/// `set_emitter_paused`. A missing account means not paused.
+#[account]
+pub struct EmitterState {
+    pub emitter: [u8; 32],
+    pub paused: bool,
+    pub bump: u8,
+    /// Payload actions (`DecodedVaa::action`) the emitter may send, set by
+    /// `set_emitter_actions`: action `a` is bit `a % 8` of byte `a / 8`. All
+    /// zero allows every action.
+    pub allowed_actions: [u8; 32],
+}
+impl EmitterState {
+    pub const SIZE: usize = 32 + 1 + 1 + 32;
+
+    pub fn action_allowed(&self, action: u8) -> bool {
+        self.allowed_actions == [0u8; 32]
+            || self.allowed_actions[action as usize / 8] & (1 << (action % 8)) != 0
+    }
+}
+
+/// Last batch_id posted through an emitter: `[OUTBOUND_CURSOR_SEED, emitter_pda]`.
+#[account]
+pub struct OutboundCursor {
+    pub last_batch_id: u32,
+    /// False until the first post (and after `reset_outbound_cursor`), so any
+    /// batch_id is accepted next.
+    pub has_posted: bool,
+    pub bump: u8,
+}
+impl OutboundCursor {
+    pub const SIZE: usize = 4 + 1 + 1;
+}
+
+/// Slot of the last `post_wormhole_message` through an emitter:
+/// `[OUTBOUND_RATE_SEED, emitter_pda]`.
+#[account]
+pub struct OutboundRate {
+    pub last_post_slot: u64,
+    pub bump: u8,
+}
+impl OutboundRate {
+    pub const SIZE: usize = 8 + 1;
+}
+
+/// Fees spent through an emitter in the current window: `[SPEND_BUDGET_SEED, emitter_pda]`.
+#[account]
+pub struct SpendBudget {
+    pub spent: u64,
+    pub window_start: i64,
+    pub bump: u8,
+}
+impl SpendBudget {
+    pub const SIZE: usize = 8 + 8 + 1;
+
+    /// Adds `amount` to the current window (rolling it over once `window_seconds`
+    /// have passed); returns what is left of `cap`. A window of 0 would roll
+    /// over on every draw, so it fails with `InvalidBudgetWindow`.
+    pub fn draw(&mut self, cap: u64, window_seconds: i64, amount: u64, now: i64) -> Result<u64> {
+        require!(window_seconds > 0, ZkError::InvalidBudgetWindow);
+        if now >= self.window_start.saturating_add(window_seconds) {
+            self.window_start = now;
+            self.spent = 0;
+        }
+        let spent = self
+            .spent
+            .checked_add(amount)
+            .filter(|s| *s <= cap)
+            .ok_or(error!(ZkError::BudgetExceeded))?;
+        self.spent = spent;
+        Ok(cap - spent)
+    }
+}
+
+/// Content-addressed pointer to a receipt: `[HASH_INDEX_SEED, payload_hash]`.
+#[account]
+pub struct HashIndex {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub bump: u8,
+}
+impl HashIndex {
+    pub const SIZE: usize = 32 + 8 + 1;
+}
+
+/// Post attempts for one batch_id: `[ATTEMPT_SEED, emitter_pda, batch_id_le]`.
+#[account]
+pub struct AttemptCounter {
+    pub attempts: u32,
+    pub last_attempt_slot: u64,
+    pub bump: u8,
+}
+impl AttemptCounter {
+    pub const SIZE: usize = 4 + 8 + 1;
+}
+
+/// Outbound post marker keyed by batch_id: `[BATCH_MARKER_SEED, batch_id_le]`.
+#[account]
+pub struct BatchMarker {
+    pub batch_id: u32,
+    /// Wormhole sequence assigned to the post.
+    pub sequence: u64,
+    pub posted_slot: u64,
+    pub round_tripped: bool,
+    pub bump: u8,
+}
+impl BatchMarker {
+    pub const SIZE: usize = 4 + 8 + 8 + 1 + 1;
+}
+
+/// Fails with `BatchMarkerPending` while `marker` tracks a post still
+/// awaiting its round trip, so a later post under the same `batch_id`
+/// cannot overwrite it. A fresh marker (bump 0) has tracked nothing yet.
+fn check_marker_reusable(marker: &BatchMarker) -> Result<()> {
+    require!(marker.bump == 0 || marker.round_tripped, ZkError::BatchMarkerPending);
+    Ok(())
+}
+
+/// Version of the `ConfigView` and `ConfigListsView` return contracts;
+/// bumped whenever the fields of either change.
+pub const CONFIG_VIEW_VERSION: u8 = 35;
+
+/// Return data of `read_config`: the scalar config fields. The allowlists
+/// are in `ConfigListsView`, so this view stays within `MAX_RETURN_DATA`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ConfigView {
+    /// `CONFIG_VIEW_VERSION` this view was encoded with.
+    pub version: u8,
+    pub admin: Pubkey,
+    pub operator: Pubkey,
+    pub receipt_reaper: Pubkey,
+    pub close_authority: Pubkey,
+    pub evm_chain: u16,
+    pub allow_unset_chain: bool,
+    pub chain_label: [u8; 16],
+    pub require_chain_pins: bool,
+    pub emitter_activation_delay_slots: u64,
+    pub evm_emitters: bool,
+    // Fees.
+    pub fee_authority: Pubkey,
+    pub fee_asset: FeeAsset,
+    pub protocol_fee_confirmed: u64,
+    pub protocol_fee_finalized: u64,
+    pub fee_slippage: u64,
+    pub treasury: Pubkey,
+    pub budget_per_window: u64,
+    pub budget_window_seconds: i64,
+    // Post policy.
+    pub posting_paused: bool,
+    pub maintenance_until_slot: u64,
+    pub enforce_batch_monotonic: bool,
+    pub in_flight: u32,
+    pub max_in_flight: u32,
+    pub min_post_interval_slots: u64,
+    /// `None` when unset.
+    pub default_finality: Option<u8>,
+    pub strict_fee_collector: bool,
+    pub finalized_min_payload_len: u32,
+    pub track_attempts: bool,
+    pub skip_post_payload_hash: bool,
+    pub require_pda_message: bool,
+    pub avoid_expiring_guardian_set: bool,
+    pub expiration_buffer_seconds: i64,
+    pub enforce_framing: bool,
+    // Record policy.
+    pub hash_algo: u8,
+    pub hash_prefix_skip: u16,
+    pub store_field_root: bool,
+    pub require_expected_digest: bool,
+    pub require_hash_index: bool,
+    pub link_receipts: bool,
+    pub event_ring_enabled: bool,
+    pub abi_payload_words: u32,
+    pub wormhole_program_id: Pubkey,
+    pub reject_zero_nonce: bool,
+    pub enforce_length_prefix: bool,
+    pub min_record_consistency: u8,
+    pub max_vaa_age_seconds: i64,
+    pub clock_skew_tolerance_seconds: i64,
+    pub min_age_seconds: i64,
+    pub min_verify_age_seconds: i64,
+    pub ring_size: u32,
+    pub max_batch_size: u16,
+    pub max_receipts_per_emitter: u64,
+    pub per_category_cursors: bool,
+    pub reorg_tolerance: u64,
+    pub verify_inline: bool,
+    pub verify_signatures_independently: bool,
+    pub compact_receipt_logs: bool,
+    pub enforce_cpi_callers: bool,
+    pub verbose: bool,
+    // Cached derivations.
+    pub local_emitter: [u8; 32],
+    pub cached_sequence: Pubkey,
+    pub emitter_bump: u8,
+    pub shadow_emitter_bump: u8,
+    // Admin controls.
+    pub timelock_seconds: i64,
+    pub config_change_cooldown_slots: u64,
+    pub last_config_change_slot: u64,
+}
+
+impl From<&ReceiptConfig> for ConfigView {
+    fn from(cfg: &ReceiptConfig) -> Self {
+        Self {
+            version: CONFIG_VIEW_VERSION,
+            admin: cfg.admin,
+            operator: cfg.operator,
+            receipt_reaper: cfg.receipt_reaper,
+            close_authority: cfg.close_authority,
+            evm_chain: cfg.evm_chain,
+            allow_unset_chain: cfg.allow_unset_chain,
+            chain_label: cfg.chain_label,
+            require_chain_pins: cfg.require_chain_pins,
+            emitter_activation_delay_slots: cfg.emitter_activation_delay_slots,
+            evm_emitters: cfg.evm_emitters,
+            fee_authority: cfg.fee_authority,
+            fee_asset: cfg.fee_asset,
+            protocol_fee_confirmed: cfg.protocol_fee_confirmed,
+            protocol_fee_finalized: cfg.protocol_fee_finalized,
+            fee_slippage: cfg.fee_slippage,
+            treasury: cfg.treasury,
+            budget_per_window: cfg.budget_per_window,
+            budget_window_seconds: cfg.budget_window_seconds,
+            posting_paused: cfg.posting_paused,
+            maintenance_until_slot: cfg.maintenance_until_slot,
+            enforce_batch_monotonic: cfg.enforce_batch_monotonic,
+            in_flight: cfg.in_flight,
+            max_in_flight: cfg.max_in_flight,
+            min_post_interval_slots: cfg.min_post_interval_slots,
+            default_finality: cfg.has_default_finality.then_some(cfg.default_finality),
+            strict_fee_collector: cfg.strict_fee_collector,
+            finalized_min_payload_len: cfg.finalized_min_payload_len,
+            track_attempts: cfg.track_attempts,
+            skip_post_payload_hash: cfg.skip_post_payload_hash,
+            require_pda_message: cfg.require_pda_message,
+            avoid_expiring_guardian_set: cfg.avoid_expiring_guardian_set,
+            expiration_buffer_seconds: cfg.expiration_buffer_seconds,
+            enforce_framing: cfg.enforce_framing,
+            hash_algo: cfg.hash_algo,
+            hash_prefix_skip: cfg.hash_prefix_skip,
+            store_field_root: cfg.store_field_root,
+            require_expected_digest: cfg.require_expected_digest,
+            require_hash_index: cfg.require_hash_index,
+            link_receipts: cfg.link_receipts,
+            event_ring_enabled: cfg.event_ring_enabled,
+            abi_payload_words: cfg.abi_payload_words,
+            wormhole_program_id: cfg.wormhole_program_id,
+            reject_zero_nonce: cfg.reject_zero_nonce,
+            enforce_length_prefix: cfg.enforce_length_prefix,
+            min_record_consistency: cfg.min_record_consistency,
+            max_vaa_age_seconds: cfg.max_vaa_age_seconds,
+            clock_skew_tolerance_seconds: cfg.clock_skew_tolerance_seconds,
+            min_age_seconds: cfg.min_age_seconds,
+            min_verify_age_seconds: cfg.min_verify_age_seconds,
+            ring_size: cfg.ring_size,
+            max_batch_size: cfg.max_batch_size,
+            max_receipts_per_emitter: cfg.max_receipts_per_emitter,
+            per_category_cursors: cfg.per_category_cursors,
+            reorg_tolerance: cfg.reorg_tolerance,
+            verify_inline: cfg.verify_inline,
+            verify_signatures_independently: cfg.verify_signatures_independently,
+            compact_receipt_logs: cfg.compact_receipt_logs,
+            enforce_cpi_callers: cfg.enforce_cpi_callers,
+            verbose: cfg.verbose,
+            local_emitter: cfg.local_emitter().0,
+            cached_sequence: cfg.cached_sequence,
+            emitter_bump: cfg.emitter_bump,
+            shadow_emitter_bump: cfg.shadow_emitter_bump,
+            timelock_seconds: cfg.timelock_seconds,
+            config_change_cooldown_slots: cfg.config_change_cooldown_slots,
+            last_config_change_slot: cfg.last_config_change_slot,
+        }
+    }
+}
+
+/// Return data of `read_config_lists`. Fixed-capacity arrays are returned
+/// as vectors of their populated entries.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ConfigListsView {
+    /// `CONFIG_VIEW_VERSION` this view was encoded with.
+    pub version: u8,
+    /// Emitter allowlist, `cfg.emitter` first.
+    pub emitters: Vec<[u8; 32]>,
+    /// Activation slot of each entry of `emitters`.
+    pub emitter_active_at_slot: Vec<u64>,
+    /// Pinned chain of each entry of `emitters`; 0 = unpinned.
+    pub emitter_chains: Vec<u16>,
+    pub allowed_categories: Vec<u16>,
+    pub treasury_allowlist: Vec<Pubkey>,
+    pub allowed_waivers: Vec<Pubkey>,
+    pub allowed_cpi_callers: Vec<Pubkey>,
+}
+
+impl From<&ReceiptConfig> for ConfigListsView {
+    fn from(cfg: &ReceiptConfig) -> Self {
+        let set = |keys: &[Pubkey]| -> Vec<Pubkey> {
+            keys.iter().copied().filter(|k| *k != Pubkey::default()).collect()
+        };
+        let extras = &cfg.extra_emitters[..cfg.extra_emitter_count as usize];
+        Self {
+            version: CONFIG_VIEW_VERSION,
+            emitters: std::iter::once(&cfg.emitter).chain(extras).map(|e| e.0).collect(),
+            emitter_active_at_slot: cfg.emitter_active_at_slot[..1 + extras.len()].to_vec(),
+            emitter_chains: cfg.emitter_chains[..1 + extras.len()].to_vec(),
+            allowed_categories: cfg.allowed_categories[..cfg.allowed_category_count as usize]
+                .to_vec(),
+            treasury_allowlist: set(&cfg.treasury_allowlist),
+            allowed_waivers: set(&cfg.allowed_waivers),
+            allowed_cpi_callers: set(&cfg.allowed_cpi_callers),
+        }
+    }
+}
+
+/// Return data of `emitter_status`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EmitterStatus {
+    /// The sequence tracker exists and is nonzero (we have posted).
+    pub initialized: bool,
+    /// Raw tracker value: the number of messages posted so far.
+    pub current_sequence: u64,
+}
+
+/// Outbound emitter: the Wormhole address of our `[b"emitter"]` PDA, used by
+/// the post path. Never compared against `RemoteEmitter`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LocalEmitter(pub [u8; 32]);
+
+/// Inbound emitter: the remote (EVM) emitter tracked in `ReceiptConfig`,
+/// whose VAAs the record paths accept. Same Borsh layout as `[u8; 32]`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RemoteEmitter(pub [u8; 32]);
+
+/// Return data of `derive_message_account`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MessageAccount {
+    pub address: Pubkey,
+    pub bump: u8,
+    /// Sequence the post is expected to receive, which seeds `address`.
+    pub sequence: u64,
+}
+
+/// Return data of `describe_receipt`.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ReceiptDescription {
+    pub receipt: Receipt,
+    /// Seconds since the receipt was recorded (never negative).
+    pub age_seconds: i64,
+    pub is_stale: bool,
+}
+
+/// Return data of `reconcile`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Reconciliation {
+    /// Wormhole sequence tracker (next outbound sequence); `None` if never posted.
+    pub outbound_next_sequence: Option<u64>,
+    /// `EmitterCursor.last_sequence`; `None` if no cursor exists.
+    pub inbound_last_sequence: Option<u64>,
+}
+
+/// Return data of `record_receipt_from_vaa` and `record_receipt_direct`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordOutcome {
+    NewlyRecorded,
+    AlreadyPresent,
+}
+
+/// Return data of `validate_record`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RecordValidation {
+    /// Error code the record would fail with; 0 if it would succeed.
+    pub code: u64,
+    /// What the record would return; `None` unless `code` is 0.
+    pub outcome: Option<RecordOutcome>,
+}
+
+/// Outcome category reported by `record_attempt`, in the order the record
+/// path checks them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordAttemptOutcome {
+    Recordable,
+    Duplicate,
+    EmitterNotAllowed,
+    /// Not a PostedVAA owned by our Wormhole program, or undecodable.
+    InvalidVaa,
+    EmitterMismatch,
+    ConsistencyTooLow,
+    Stale,
+}
+
+/// Attestation state of a receipt.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReceiptStatus {
+    Unverified,
+    /// An external attestor signed the receipt's canonical bytes.
+    Verified,
+    /// Allocated by `preallocate_receipts` and not yet recorded.
+    Preallocated,
+}
+
+/// How a receipt was recorded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReceiptSource {
+    /// Verified PostedVAA.
+    Wormhole,
+    /// Admin-recorded without a VAA.
+    Direct,
+    /// Off-chain verified, recorded by the operator with a caller-provided hash.
+    External,
+}
+
+/// Program-wide event counter for gap detection by indexers.
+#[account]
+pub struct EventCounter {
+    pub global_event_seq: u64,
+    pub bump: u8,
+}
+impl EventCounter {
+    pub const SIZE: usize = 8 + 1;
+
+    /// Increments the counter and returns the new value for the event.
+    pub fn next(&mut self) -> Result<u64> {
+        self.global_event_seq = self
+            .global_event_seq
+            .checked_add(1)
+            .ok_or(error!(ZkError::EventSeqOverflow))?;
+        Ok(self.global_event_seq)
+    }
+}
+
+/// State commitment built by `attest_state`. `total_recorded` and
+/// `total_closed` are the emitter's, from its EmitterReceiptCount.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StateAttestation {
+    /// Our Wormhole emitter address, naming the attesting program.
+    pub program_emitter: [u8; 32],
+    pub emitter: [u8; 32],
+    /// Cursor category; 0 for a per-emitter cursor.
+    pub category: u16,
+    pub last_sequence: u64,
+    pub total_recorded: u64,
+    pub total_closed: u64,
+    /// Slot the summary was taken at.
+    pub slot: u64,
+}
+impl StateAttestation {
+    /// Leading byte of `to_bytes`; bumped if the layout changes.
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 1 + 32 + 32 + 2 + 8 + 8 + 8 + 8;
+
+    /// `VERSION` then the fields in order, integers little-endian:
+    ///
+    /// | Offset | Size | Field |
+    /// |-------:|-----:|-------|
+    /// | 0 | 1 | `VERSION` |
+    /// | 1 | 32 | `program_emitter` |
+    /// | 33 | 32 | `emitter` |
+    /// | 65 | 2 | `category` |
+    /// | 67 | 8 | `last_sequence` |
+    /// | 75 | 8 | `total_recorded` |
+    /// | 83 | 8 | `total_closed` |
+    /// | 91 | 8 | `slot` |
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::LEN);
+        out.push(Self::VERSION);
+        AnchorSerialize::serialize(self, &mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+}
+
+/// Aggregate receipt counts for dashboards, kept current by the record and
+/// close instructions so nobody has to scan receipts.
+#[account]
+pub struct StatsAccount {
+    /// Receipts written by any record path, ring overwrites included.
+    pub total_recorded: u64,
+    /// The subset of `total_recorded` written by `record_receipt_direct`.
+    pub total_direct: u64,
+    pub total_closed: u64,
+    pub last_recorded_slot: u64,
+    pub bump: u8,
+}
+impl StatsAccount {
+    pub const SIZE: usize = 8 + 8 + 8 + 8 + 1;
+
+    pub fn note_recorded(&mut self, slot: u64, direct: bool) -> Result<()> {
+        self.total_recorded = self
+            .total_recorded
+            .checked_add(1)
+            .ok_or(error!(ZkError::StatsOverflow))?;
+        if direct {
+            self.total_direct = self
+                .total_direct
+                .checked_add(1)
+                .ok_or(error!(ZkError::StatsOverflow))?;
+        }
+        self.last_recorded_slot = slot;
+        Ok(())
+    }
+
+    pub fn note_closed(&mut self, count: u64) -> Result<()> {
+        self.total_closed = self
+            .total_closed
+            .checked_add(count)
+            .ok_or(error!(ZkError::StatsOverflow))?;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ReceiptRecorded {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub vaa: Pubkey,
+    /// VAA envelope nonce (the poster's batch_id); 0 for non-VAA receipts.
+    pub nonce: u32,
+    /// Client-defined category; 0 outside `record_receipt_from_vaa`.
+    pub category: u16,
+    /// `cfg.chain_label` at record time.
+    pub chain_label: [u8; 16],
+    /// Caller-supplied correlation id; zero when not given.
+    pub external_ref: [u8; 32],
+    /// Untrusted relayer-reported delivery cost; 0 when not given.
+    pub reported_cost: u64,
+    /// `Receipt::vaa_body_hash`; zero for non-VAA receipts.
+    pub vaa_body_hash: [u8; 32],
+    /// `Receipt::calldata_hash`; relayer-asserted, zero when not given.
+    pub calldata_hash: [u8; 32],
+    /// `EmitterReceiptCount::count` including this receipt; 0 when no
+    /// EmitterReceiptCount was passed or for log appends.
+    pub emitter_receipt_count: u64,
+    /// `Receipt::action`: the payload type tag, 0 for non-VAA receipts.
+    pub action: u8,
+    pub event_seq: u64,
+}
+impl ReceiptRecorded {
+    /// Leading byte of the compact encoding; bumped if its layout changes.
+    pub const COMPACT_VERSION: u8 = 5;
+    pub const COMPACT_LEN: usize = 1 + 32 + 8 + 32 + 4 + 2 + 16 + 32 + 8 + 32 + 32 + 8 + 1 + 8;
+
+    /// Tightly packed form logged via `sol_log_data` with
+    /// `cfg.compact_receipt_logs`: no discriminator, integers little-endian.
+    ///
+    /// | Offset | Size | Field |
+    /// |-------:|-----:|-------|
+    /// | 0 | 1 | `COMPACT_VERSION` |
+    /// | 1 | 32 | `emitter` |
+    /// | 33 | 8 | `sequence` |
+    /// | 41 | 32 | `vaa` |
+    /// | 73 | 4 | `nonce` |
+    /// | 77 | 2 | `category` |
+    /// | 79 | 16 | `chain_label` |
+    /// | 95 | 32 | `external_ref` |
+    /// | 127 | 8 | `reported_cost` |
+    /// | 135 | 32 | `vaa_body_hash` |
+    /// | 167 | 32 | `calldata_hash` |
+    /// | 199 | 8 | `emitter_receipt_count` |
+    /// | 207 | 1 | `action` |
+    /// | 208 | 8 | `event_seq` |
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::COMPACT_LEN);
+        out.push(Self::COMPACT_VERSION);
+        AnchorSerialize::serialize(self, &mut out).expect("writing to a Vec cannot fail");
+        out
+    }
+}
+
+#[event]
+pub struct ProtocolFeeApplied {
+    pub payer: Pubkey,
+    /// Finality tier: 0 = Confirmed, 1 = Finalized.
+    pub tier: u8,
+    pub amount: u64,
+    /// True when an allowed waiver skipped the fee (`amount` is 0).
+    pub waived: bool,
+    pub event_seq: u64,
+}
+
+/// A receipt recorded below its emitter's cursor within
+/// `cfg.reorg_tolerance`, after a source-chain reorg re-delivered it. The
+/// cursor stays at `cursor_sequence` and the receipt is not chain-linked.
+#[event]
+pub struct ReorgReceiptRecorded {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    /// `EmitterCursor.last_sequence`, unchanged by this record.
+    pub cursor_sequence: u64,
+    pub reorg_tolerance: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MessagePosted {
+    /// Emitter address as Wormhole records it (emitter PDA bytes).
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    /// VAA nonce.
+    pub batch_id: u32,
+    /// Keccak-256 of the payload; zero if `skip_post_payload_hash`.
+    pub payload_hash: [u8; 32],
+    /// True when `payload_hash` was supplied by the poster and not computed;
+    /// it is then unverified.
+    pub payload_hash_asserted: bool,
+    /// 0 = Confirmed, 1 = Finalized.
+    pub finality: u8,
+    /// Destination prepended to the payload (to the frame body, with
+    /// `enforce_framing`), if any.
+    pub destination: Option<[u8; 32]>,
+    /// `EMITTER_ROLE_*` of the emitter that posted.
+    pub emitter_role: u8,
+    /// `cfg.min_post_interval_slots` enforced on this post; 0 when disabled.
+    /// A batch is throttled as a single post.
+    pub min_post_interval_slots: u64,
+    /// Core Bridge fee read on-chain and paid for this post.
+    pub bridge_fee: u64,
+    pub event_seq: u64,
+}
+
+/// Emitted after every successful post path with the emitter's newest
+/// sequence, so monitors can alert when it stops advancing.
+#[event]
+pub struct SequenceAdvanced {
+    /// Emitter address as Wormhole records it (emitter PDA bytes).
+    pub emitter: [u8; 32],
+    /// Sequence of the last message posted, read back after the CPI.
+    pub new_sequence: u64,
+    pub slot: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct DualPosted {
+    /// Emitter address as Wormhole records it (emitter PDA bytes).
+    pub emitter: [u8; 32],
+    /// VAA nonce of both posts.
+    pub batch_id: u32,
+    pub confirmed_sequence: u64,
+    pub finalized_sequence: u64,
+    /// Keccak-256 of the payload; zero if `skip_post_payload_hash`.
+    pub payload_hash: [u8; 32],
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct EmitterScheduled {
+    pub emitter: [u8; 32],
+    /// First slot at which the record paths accept this emitter's VAAs.
+    pub active_at_slot: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OutboundCursorReset {
+    /// Emitter address as Wormhole records it (emitter PDA bytes).
+    pub emitter: [u8; 32],
+    pub previous_batch_id: u32,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct SpendBudgetDrawn {
+    pub spent: u64,
+    pub remaining: u64,
+    pub window_start: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct PostAttempted {
+    pub batch_id: u32,
+    pub attempt: u32,
+    pub slot: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RoundTripObserved {
+    pub batch_id: u32,
+    pub outbound_sequence: u64,
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub event_seq: u64,
+}
+
+/// `ReceiptRecorded` replayed by `reemit_receipt_event`; `event_seq` is new,
+/// the rest is read back from the receipt.
+#[event]
+pub struct ReceiptReplayed {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub vaa: Pubkey,
+    pub nonce: u32,
+    pub category: u16,
+    pub chain_label: [u8; 16],
+    pub external_ref: [u8; 32],
+    pub reported_cost: u64,
+    pub vaa_body_hash: [u8; 32],
+    pub calldata_hash: [u8; 32],
+    /// The emitter's current `EmitterReceiptCount::count`, not the one at
+    /// record time; 0 when no EmitterReceiptCount was passed.
+    pub emitter_receipt_count: u64,
+    /// `Receipt::action`: the payload type tag, 0 for non-VAA receipts.
+    pub action: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct StateAttested {
+    pub attestation: StateAttestation,
+    pub event_seq: u64,
+}
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct ReceiptTombstoned {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    /// False when the tombstone was lifted.
+    pub tombstoned: bool,
+    pub reason: u8,
+    pub event_seq: u64,
 }
 
-#[account]
-pub struct ReceiptConfig {
-    pub admin: Pubkey,
-    pub evm_chain: u16,
+#[event]
+pub struct ReceiptAttested {
     pub emitter: [u8; 32],
-    pub bump: u8,
+    pub sequence: u64,
+    pub attested_by: [u8; 32],
+    /// Seconds since the receipt's `posted_timestamp` when it was verified.
+    pub age_seconds: i64,
+    pub event_seq: u64,
 }
-impl ReceiptConfig {
-    pub const SIZE: usize = 32 + 2 + 32 + 1;
+
+#[event]
+pub struct CursorForcedBackward {
+    pub emitter: [u8; 32],
+    pub category: u16,
+    pub previous_sequence: u64,
+    pub sequence: u64,
+    pub forced_by: Pubkey,
+    pub event_seq: u64,
 }
 
-#[account]
-pub struct Receipt {
+/// Emitted just before `record_receipt_from_vaa` fails with
+/// `PayloadHashConflict`; only in the failed transaction's logs (see
+/// `emit_event!`).
+#[event]
+pub struct PayloadConflictDetected {
     pub emitter: [u8; 32],
     pub sequence: u64,
-    pub vaa_account: Pubkey,
-    pub posted_timestamp: i64,
-    pub bump: u8,
+    pub stored_hash: [u8; 32],
+    pub incoming_hash: [u8; 32],
+    pub vaa: Pubkey,
+    pub event_seq: u64,
 }
-impl Receipt {
-    pub const SIZE: usize = 32 + 8 + 32 + 8 + 1;
+
+/// Emitted just before `DuplicatePayloadAcrossSequences`, when the HashIndex
+/// already maps `payload_hash` to another sequence; only in the failed
+/// transaction's logs (see `emit_event!`).
+#[event]
+pub struct DuplicatePayloadDetected {
+    pub payload_hash: [u8; 32],
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub existing_emitter: [u8; 32],
+    pub existing_sequence: u64,
+    pub event_seq: u64,
 }
 
+/// Emitted just before `ConfigChangeCooldown`; only in the failed
+/// transaction's logs (see `emit_event!`).
 #[event]
-pub struct ReceiptRecorded {
+pub struct ConfigChangeThrottled {
+    pub last_change_slot: u64,
+    pub next_allowed_slot: u64,
+    pub slot: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct PostingPauseChanged {
+    pub paused: bool,
+    pub changed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct EmitterPauseChanged {
+    pub emitter: [u8; 32],
+    pub paused: bool,
+    pub changed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct EmitterActionsChanged {
+    pub emitter: [u8; 32],
+    /// New `EmitterState::allowed_actions`; all zero allows every action.
+    pub allowed_actions: [u8; 32],
+    pub changed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MaintenanceWindowSet {
+    pub until_slot: u64,
+    pub changed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+/// Emitted just before `MaintenanceWindow`; only in the failed transaction's
+/// logs (see `emit_event!`).
+#[event]
+pub struct PostRejectedMaintenance {
+    pub slot: u64,
+    pub until_slot: u64,
+    pub payer: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ChangeQueued {
+    pub effective_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ChangeApplied {
+    pub effective_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ChangeCancelled {
+    pub effective_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ActionQueued {
+    pub action_hash: [u8; 32],
+    pub queued_by: Pubkey,
+    pub effective_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ActionApplied {
+    pub action_hash: [u8; 32],
+    pub effective_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ActionCancelled {
+    pub action_hash: [u8; 32],
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RecordAttemptObserved {
     pub emitter: [u8; 32],
     pub sequence: u64,
-    pub vaa: Pubkey,
+    pub outcome: RecordAttemptOutcome,
+    /// Signer of the observation; monitoring should filter on its relayers.
+    pub reporter: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ReceiptEvicted {
+    pub emitter: [u8; 32],
+    pub evicted_sequence: u64,
+    /// Sequence now occupying the slot.
+    pub sequence: u64,
+    pub slot: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ReceiptsClosed {
+    pub count: u32,
+    pub closed_by: Pubkey,
+    pub event_seq: u64,
 }
 
+#[event]
+pub struct ReceiptClosed {
+    pub emitter: [u8; 32],
+    pub sequence: u64,
+    pub closed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct VerifyingKeyRemoved {
+    pub circuit_id: u32,
+    /// True for the pre-circuit-id key at `[VERIFYING_KEY_SEED]`.
+    pub legacy: bool,
+    pub removed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+/// Program errors. Anchor assigns `6000 + declaration index`, so variants are
+/// append-only: never reorder, remove or insert in the middle. `code()` returns
+/// the on-chain value clients match on.
+///
+/// | Code | Variant |
+/// |------|---------|
+/// | 6000 | `ConfigOwnerMismatch` |
+/// | 6001 | `BridgeDeserialize` |
+/// | 6002 | `NotAdmin` |
+/// | 6003 | `InvalidPostedVaaOwner` |
+/// | 6004 | `EmitterAddressMismatch` |
+/// | 6005 | `PayloadTooSmallForFinalized` |
+/// | 6006 | `NotAdminOrReaper` |
+/// | 6007 | `ReceiptTooYoung` |
+/// | 6008 | `InvalidMinAge` |
+/// | 6009 | `InvalidFinalityFlag` |
+/// | 6010 | `PostedVaaDecode` |
+/// | 6011 | `VaaEmitterMismatch` |
+/// | 6012 | `InvalidReceiptAccount` |
+/// | 6013 | `NoReceiptsProvided` |
+/// | 6014 | `DuplicateReceipt` |
+/// | 6015 | `FeeCollectorMismatch` |
+/// | 6016 | `FeeCollectorNotWritable` |
+/// | 6017 | `EventSeqOverflow` |
+/// | 6018 | `BatchAccountsMismatch` |
+/// | 6019 | `ReceiptAlreadyRecorded` |
+/// | 6020 | `MessageAccountNotFresh` |
+/// | 6021 | `TreasuryMismatch` |
+/// | 6022 | `SequenceAccountMismatch` |
+/// | 6023 | `SequenceDecode` |
+/// | 6024 | `VaaFromFuture` |
+/// | 6025 | `VaaTooOld` |
+/// | 6026 | `InvalidClockSkew` |
+/// | 6027 | `InvalidMaxVaaAge` |
+/// | 6028 | `NotOperator` |
+/// | 6029 | `BatchMarkerMismatch` |
+/// | 6030 | `FeeTokenAccountsMissing` |
+/// | 6031 | `InvalidTokenProgram` |
+/// | 6032 | `FeeMintMismatch` |
+/// | 6033 | `ReceiptMissing` |
+/// | 6034 | `ReceiptOutOfOrder` |
+/// | 6035 | `AttemptCounterRequired` |
+/// | 6036 | `ReceiptPdaMismatch` |
+/// | 6037 | `PosterNotAllowed` |
+/// | 6038 | `TooManyPosters` |
+/// | 6039 | `TimelockActive` |
+/// | 6040 | `TimelockNotElapsed` |
+/// | 6041 | `InvalidTimelock` |
+/// | 6042 | `RingModeDisabled` |
+/// | 6043 | `RingSlotHoldsNewer` |
+/// | 6044 | `ReceiptAccountEmpty` |
+/// | 6045 | `ReceiptLayoutMismatch` |
+/// | 6046 | `PostedVaaEmpty` |
+/// | 6047 | `PostedVaaBadMagic` |
+/// | 6048 | `InvalidSequenceRange` |
+/// | 6049 | `ReceiptGap` |
+/// | 6050 | `BudgetExceeded` |
+/// | 6051 | `SpendBudgetRequired` |
+/// | 6052 | `InvalidBudgetWindow` |
+/// | 6053 | `HashIndexMismatch` |
+/// | 6054 | `HashIndexCollision` |
+/// | 6055 | `ConsistencyTooLow` |
+/// | 6056 | `PostingPaused` |
+/// | 6057 | `InvalidEvmDestination` |
+/// | 6058 | `UnknownHashAlgo` |
+/// | 6059 | `PayloadLengthPrefixMismatch` |
+/// | 6060 | `CursorRegression` |
+/// | 6061 | `SequenceNotAfterCursor` |
+/// | 6062 | `FeeOverflow` |
+/// | 6063 | `CategoryNotAllowed` |
+/// | 6064 | `TooManyCategories` |
+/// | 6065 | `BridgeAccountsInconsistent` |
+/// | 6066 | `InclusionProofInvalid` |
+/// | 6067 | `NoEmittersProvided` |
+/// | 6068 | `TooManyEmitters` |
+/// | 6069 | `DuplicateMessageAccount` |
+/// | 6070 | `CannotRecordOwnEmitter` |
+/// | 6071 | `CursorEmitterMismatch` |
+/// | 6072 | `BatchTooLarge` |
+/// | 6073 | `InvalidMaxBatchSize` |
+/// | 6074 | `PdaMessageRequired` |
+/// | 6075 | `MessageNotSigner` |
+/// | 6076 | `AttestationInvalid` |
+/// | 6077 | `NoMessagesProvided` |
+/// | 6078 | `MessagePdaMismatch` |
+/// | 6079 | `EmitterAlreadyPresent` |
+/// | 6080 | `EmitterNotFound` |
+/// | 6081 | `ZeroEmitter` |
+/// | 6082 | `TreasuryNotAllowed` |
+/// | 6083 | `TooManyTreasuries` |
+/// | 6084 | `ConfigChangeCooldown` |
+/// | 6085 | `TestRoundTripMismatch` |
+/// | 6086 | `InvalidChainLabel` |
+/// | 6087 | `ReceiptNotMigratable` |
+/// | 6088 | `PayloadDigestMismatch` |
+/// | 6089 | `ExpectedDigestBatchMismatch` |
+/// | 6090 | `GuardianSetRequired` |
+/// | 6091 | `GuardianSetMismatch` |
+/// | 6092 | `GuardianSetDecode` |
+/// | 6093 | `GuardianSetExpiring` |
+/// | 6094 | `InvalidExpirationBuffer` |
+/// | 6095 | `MirrorDecode` |
+/// | 6096 | `MirrorVersionUnsupported` |
+/// | 6097 | `WaiverNotAllowed` |
+/// | 6098 | `TooManyWaivers` |
+/// | 6099 | `PayloadHashConflict` |
+/// | 6100 | `VerifyingKeyRequired` |
+/// | 6101 | `InvalidVerifyingKey` |
+/// | 6102 | `ProofInvalid` |
+/// | 6103 | `InlineVerificationRequired` |
+/// | 6104 | `CallerNotAllowed` |
+/// | 6105 | `InstructionsSysvarRequired` |
+/// | 6106 | `TooManyCpiCallers` |
+/// | 6107 | `PostedVaaUninitialized` |
+/// | 6108 | `LogFull` |
+/// | 6109 | `AlreadyInLog` |
+/// | 6110 | `LogIndexOutOfRange` |
+/// | 6111 | `VaaSequenceMismatch` |
+/// | 6112 | `UnsupportedBridgeLayout` |
+/// | 6113 | `StatsOverflow` |
+/// | 6114 | `ContiguousRunBroken` |
+/// | 6115 | `UnknownEmitterRole` |
+/// | 6116 | `ShadowBatchMarker` |
+/// | 6117 | `ChainNotConfigured` |
+/// | 6118 | `EmitterBumpMismatch` |
+/// | 6119 | `PublicInputCountMismatch` |
+/// | 6120 | `MaintenanceWindow` |
+/// | 6121 | `AbiLayoutInvalid` |
+/// | 6122 | `BatchProofInvalid` |
+/// | 6123 | `EmitterNotLeftPadded` |
+/// | 6124 | `ReceiptTombstoned` |
+/// | 6125 | `NotFeeAuthority` |
+/// | 6126 | `BatchIdNotIncreasing` |
+/// | 6127 | `OutboundCursorRequired` |
+/// | 6128 | `MessageAccountUnderfunded` |
+/// | 6129 | `NotCloseAuthorized` |
+/// | 6130 | `ConfigNotMigratable` |
+/// | 6131 | `VaaHashMismatch` |
+/// | 6132 | `EmitterNotYetActive` |
+/// | 6133 | `UnexpectedAction` |
+/// | 6134 | `TooManyInFlight` |
+/// | 6135 | `ReceiptNotFound` |
+/// | 6136 | `PrefixSkipTooLarge` |
+/// | 6137 | `WormholeProgramMismatch` |
+/// | 6138 | `ExpectedDigestRequired` |
+/// | 6139 | `PostTooSoon` |
+/// | 6140 | `OutboundRateRequired` |
+/// | 6141 | `ExportTooLarge` |
+/// | 6142 | `ZeroNonceNotAllowed` |
+/// | 6143 | `WrongSequenceForEmitter` |
+/// | 6144 | `NoDefaultFinality` |
+/// | 6145 | `ChainEmitterPairMismatch` |
+/// | 6146 | `DuplicatePayloadAcrossSequences` |
+/// | 6147 | `ReceiptPreallocated` |
+/// | 6148 | `NotBridgeConfigAccount` |
+/// | 6149 | `EmitterCountRequired` |
+/// | 6150 | `EmitterReceiptCapReached` |
+/// | 6151 | `InvalidFraming` |
+/// | 6152 | `ReturnDataTooLarge` |
+/// | 6153 | `TrustedGuardianSetRequired` |
+/// | 6154 | `GuardianSetIndexMismatch` |
+/// | 6155 | `InsufficientGuardianSignatures` |
+/// | 6156 | `InvalidGuardianSet` |
+/// | 6157 | `IndependentVerificationRequired` |
+/// | 6158 | `EmitterPaused` |
+/// | 6159 | `FeeAboveSlippage` |
+/// | 6160 | `ReceiptChainBroken` |
+/// | 6161 | `CursorNotMigratable` |
+/// | 6162 | `VerifyTooSoon` |
+/// | 6163 | `VerifyingKeyNotFound` |
+/// | 6164 | `CircuitIdMismatch` |
+/// | 6165 | `ActionNotAllowedForEmitter` |
+/// | 6166 | `FieldRootNotStored` |
+/// | 6167 | `FieldProofInvalid` |
+/// | 6168 | `BatchMarkerRequired` |
+/// | 6169 | `InvalidEmitterCountAccount` |
+/// | 6170 | `CursorRequired` |
+/// | 6171 | `ReceiptInReorgWindow` |
+/// | 6172 | `PendingActionMismatch` |
+/// | 6173 | `ExpectedDigestMissing` |
+/// | 6174 | `HashIndexRequired` |
+/// | 6175 | `ChainCursorRequired` |
+/// | 6176 | `UnlinkedRecordPath` |
+/// | 6177 | `PayloadHashAssertionRejected` |
+/// | 6178 | `AttestorNotAllowed` |
+/// | 6179 | `EventRingRequired` |
+/// | 6180 | `NotAReceiptAccount` |
+/// | 6181 | `ReceiptAlreadyMigrated` |
+/// | 6182 | `FeeTokenAccountNotCollector` |
+/// | 6183 | `BatchMarkerPending` |
+/// | 6184 | `BatchNotConfirmed` |
+/// | 6185 | `LegacyVerifyingKeyNotFound` |
 #[error_code]
 pub enum ZkError {
     #[msg("config owner is not the Wormhole Core program")]
@@ -290,4 +9840,986 @@ pub enum ZkError {
     #[msg("admin only")] NotAdmin,
     #[msg("invalid owner for PostedVaa account")] InvalidPostedVaaOwner,
     #[msg("emitter address mismatch")] EmitterAddressMismatch,
+    #[msg("payload too small for Finalized finality")] PayloadTooSmallForFinalized,
+    #[msg("admin or reaper only")] NotAdminOrReaper,
+    #[msg("receipt younger than min_age_seconds")] ReceiptTooYoung,
+    #[msg("min_age_seconds must be non-negative")] InvalidMinAge,
+    #[msg("finality_flag must be 0 (Confirmed) or 1 (Finalized)")] InvalidFinalityFlag,
+    #[msg("failed to decode PostedVaa account")] PostedVaaDecode,
+    #[msg("VAA emitter does not match emitter argument")] VaaEmitterMismatch,
+    #[msg("account is not a Receipt owned by this program")] InvalidReceiptAccount,
+    #[msg("no receipt accounts provided")] NoReceiptsProvided,
+    #[msg("duplicate receipt in list")] DuplicateReceipt,
+    #[msg("fee_collector is not the Wormhole fee collector PDA")] FeeCollectorMismatch,
+    #[msg("fee_collector must be writable")] FeeCollectorNotWritable,
+    #[msg("event sequence overflow")] EventSeqOverflow,
+    #[msg("remaining_accounts must be (posted_vaa, receipt) pairs per sequence")] BatchAccountsMismatch,
+    #[msg("receipt already recorded")] ReceiptAlreadyRecorded,
+    #[msg("message account must be a fresh, unused keypair")] MessageAccountNotFresh,
+    #[msg("treasury account missing or does not match config")] TreasuryMismatch,
+    #[msg("sequence account does not belong to Wormhole Core")] SequenceAccountMismatch,
+    #[msg("failed to decode Wormhole sequence account")] SequenceDecode,
+    #[msg("VAA timestamp is in the future")] VaaFromFuture,
+    #[msg("VAA is older than max_vaa_age_seconds")] VaaTooOld,
+    #[msg("clock_skew_tolerance_seconds must be non-negative")] InvalidClockSkew,
+    #[msg("max_vaa_age_seconds must be non-negative")] InvalidMaxVaaAge,
+    #[msg("operator or admin only")] NotOperator,
+    #[msg("batch marker bump unavailable")] BatchMarkerMismatch,
+    #[msg("SPL fee requires payer/collector token accounts and token program")] FeeTokenAccountsMissing,
+    #[msg("token program mismatch")] InvalidTokenProgram,
+    #[msg("fee token account mint mismatch")] FeeMintMismatch,
+    #[msg("receipt missing for claimed sequence")] ReceiptMissing,
+    #[msg("receipt posted_slot out of order")] ReceiptOutOfOrder,
+    #[msg("attempt counter account required when track_attempts is set")] AttemptCounterRequired,
+    #[msg("receipt account is not the PDA for (emitter, sequence)")] ReceiptPdaMismatch,
+    #[msg("payer is not an allowed poster")] PosterNotAllowed,
+    #[msg("at most 8 allowed posters")] TooManyPosters,
+    #[msg("config timelock active; queue the change")] TimelockActive,
+    #[msg("timelock has not elapsed")] TimelockNotElapsed,
+    #[msg("timelock_seconds must be >= 0")] InvalidTimelock,
+    #[msg("ring mode disabled (ring_size = 0)")] RingModeDisabled,
+    #[msg("ring slot already holds a newer or equal sequence")] RingSlotHoldsNewer,
+    #[msg("receipt account is empty (not recorded)")] ReceiptAccountEmpty,
+    #[msg("account data is not a Receipt")] ReceiptLayoutMismatch,
+    #[msg("PostedVaa account is empty")] PostedVaaEmpty,
+    #[msg("PostedVaa account does not start with the \"vaa\" magic")] PostedVaaBadMagic,
+    #[msg("start must not exceed end")] InvalidSequenceRange,
+    #[msg("gap in recorded sequences")] ReceiptGap,
+    #[msg("posting budget exceeded for this window")] BudgetExceeded,
+    #[msg("spend budget account required when budget_per_window is set")] SpendBudgetRequired,
+    #[msg("budget_window_seconds must be >= 0, and > 0 while budget_per_window is set")] InvalidBudgetWindow,
+    #[msg("hash_index is not the PDA for the payload hash")] HashIndexMismatch,
+    #[msg("payload hash already indexed to a different receipt")] HashIndexCollision,
+    #[msg("VAA consistency_level below min_record_consistency")] ConsistencyTooLow,
+    #[msg("outbound posting is paused")] PostingPaused,
+    #[msg("EVM destination must be a left-padded 20-byte address")] InvalidEvmDestination,
+    #[msg("Unknown hash_algo")] UnknownHashAlgo,
+    #[msg("Payload length does not match its u16 length prefix")] PayloadLengthPrefixMismatch,
+    #[msg("Cursor can only move forward without force_backward")] CursorRegression,
+    #[msg("Sequence is not past the emitter cursor")] SequenceNotAfterCursor,
+    #[msg("Fee estimate overflow")] FeeOverflow,
+    #[msg("Receipt category not in the allowlist")] CategoryNotAllowed,
+    #[msg("Too many categories")] TooManyCategories,
+    #[msg("Core Bridge config and fee_collector are not PDAs of wormhole_program")] BridgeAccountsInconsistent,
+    #[msg("Merkle inclusion proof does not match root")] InclusionProofInvalid,
+    #[msg("At least one emitter is required")] NoEmittersProvided,
+    #[msg("Too many emitters")] TooManyEmitters,
+    #[msg("Confirmed and Finalized posts need distinct message accounts")] DuplicateMessageAccount,
+    #[msg("Remote emitter cannot be our own emitter")] CannotRecordOwnEmitter,
+    #[msg("EmitterCursor does not belong to this emitter")] CursorEmitterMismatch,
+    #[msg("Batch exceeds max_batch_size")] BatchTooLarge,
+    #[msg("max_batch_size must be between 1 and the hard ceiling")] InvalidMaxBatchSize,
+    #[msg("Config requires the message PDA")] PdaMessageRequired,
+    #[msg("Keypair message account must sign")] MessageNotSigner,
+    #[msg("No valid attestor signature over the receipt in this transaction")] AttestationInvalid,
+    #[msg("No messages provided")] NoMessagesProvided,
+    #[msg("Message account is not the expected message PDA")] MessagePdaMismatch,
+    #[msg("Emitter is already on the allowlist")] EmitterAlreadyPresent,
+    #[msg("Emitter is not on the allowlist")] EmitterNotFound,
+    #[msg("Emitter address cannot be all zeros")] ZeroEmitter,
+    #[msg("Treasury is not on the treasury allowlist")] TreasuryNotAllowed,
+    #[msg("Too many treasuries for the allowlist")] TooManyTreasuries,
+    #[msg("Config change cooldown has not elapsed")] ConfigChangeCooldown,
+    #[msg("Test round trip: posted sequence or PostedVAA does not match")] TestRoundTripMismatch,
+    #[msg("Chain label must be zero-padded printable ASCII")] InvalidChainLabel,
+    #[msg("Account is not a receipt of this program")] ReceiptNotMigratable,
+    #[msg("Payload hash does not match the registered expected digest")] PayloadDigestMismatch,
+    #[msg("Expected digest is registered for a different batch_id")] ExpectedDigestBatchMismatch,
+    #[msg("guardian_set account required when avoid_expiring_guardian_set is set")] GuardianSetRequired,
+    #[msg("guardian_set is not the Core Bridge current guardian set")] GuardianSetMismatch,
+    #[msg("failed to decode guardian set account")] GuardianSetDecode,
+    #[msg("Current guardian set expires within the configured buffer")] GuardianSetExpiring,
+    #[msg("expiration_buffer_seconds must be non-negative")] InvalidExpirationBuffer,
+    #[msg("Receipt mirror bytes are truncated or malformed")] MirrorDecode,
+    #[msg("Receipt mirror bytes use an unsupported layout version")] MirrorVersionUnsupported,
+    #[msg("Payer is not allowed to waive the protocol fee")] WaiverNotAllowed,
+    #[msg("Too many fee waivers")] TooManyWaivers,
+    #[msg("Existing receipt has a different payload hash for this emitter/sequence")] PayloadHashConflict,
+    #[msg("verifying_key account required when verify_inline is set")] VerifyingKeyRequired,
+    #[msg("Verifying key needs 1..=MAX_PUBLIC_INPUTS+1 IC points")] InvalidVerifyingKey,
+    #[msg("Groth16 proof is malformed or does not verify")] ProofInvalid,
+    #[msg("verify_inline is set: record through record_receipt_from_vaa")] InlineVerificationRequired,
+    #[msg("CPI caller is not on the allowed_cpi_callers list")] CallerNotAllowed,
+    #[msg("instructions sysvar required to check the CPI caller")] InstructionsSysvarRequired,
+    #[msg("Too many CPI callers")] TooManyCpiCallers,
+    #[msg("posted_vaa is uninitialized (system-owned); post the VAA first")] PostedVaaUninitialized,
+    #[msg("Receipt log is full")] LogFull,
+    #[msg("Sequence is already in the receipt log")] AlreadyInLog,
+    #[msg("Receipt log index out of range")] LogIndexOutOfRange,
+    #[msg("PostedVAA sequence does not match the sequence argument")] VaaSequenceMismatch,
+    #[msg("Core Bridge BridgeData is in an unsupported layout")] UnsupportedBridgeLayout,
+    #[msg("Stats counter overflow")] StatsOverflow,
+    #[msg("VAAs do not form a contiguous same-emitter run")] ContiguousRunBroken,
+    #[msg("Unknown emitter role")] UnknownEmitterRole,
+    #[msg("Batch markers track the primary emitter only")] ShadowBatchMarker,
+    #[msg("evm_chain is unset (0); set it or enable allow_unset_chain")] ChainNotConfigured,
+    #[msg("Emitter PDA bump does not match the bump cached in config")] EmitterBumpMismatch,
+    #[msg("Public input count does not match the verifying key")] PublicInputCountMismatch,
+    #[msg("Posting is frozen for a Wormhole maintenance window")] MaintenanceWindow,
+    #[msg("Payload is not the expected number of 32-byte ABI words")] AbiLayoutInvalid,
+    #[msg("Batched Groth16 verification failed")] BatchProofInvalid,
+    #[msg("evm_emitters is set but the emitter is not a left-padded 20-byte address")] EmitterNotLeftPadded,
+    #[msg("Receipt is tombstoned")] ReceiptTombstoned,
+    #[msg("Signer is not the fee authority")] NotFeeAuthority,
+    #[msg("batch_id must exceed the last one posted by this emitter")] BatchIdNotIncreasing,
+    #[msg("OutboundCursor account required when enforce_batch_monotonic is set")] OutboundCursorRequired,
+    #[msg("Message account holds less than the rent-exempt minimum")] MessageAccountUnderfunded,
+    #[msg("admin, reaper or close authority only")] NotCloseAuthorized,
+    #[msg("account is not a receipt config of this program")] ConfigNotMigratable,
+    #[msg("vaa_hash does not match the PostedVAA body")] VaaHashMismatch,
+    #[msg("emitter is allowlisted but not active until its activation slot")] EmitterNotYetActive,
+    #[msg("Receipt payload is not of the expected action")] UnexpectedAction,
+    #[msg("max_in_flight posts are awaiting their round trip")] TooManyInFlight,
+    #[msg("no receipt recorded for this emitter and sequence")] ReceiptNotFound,
+    #[msg("hash_prefix_skip is longer than the payload")] PrefixSkipTooLarge,
+    #[msg("wormhole_program differs from the one the config trusts")] WormholeProgramMismatch,
+    #[msg("expected_digest is required by, and only created through, post_and_register_expected")] ExpectedDigestRequired,
+    #[msg("min_post_interval_slots have not passed since the last post")] PostTooSoon,
+    #[msg("OutboundRate account required while min_post_interval_slots is set")] OutboundRateRequired,
+    #[msg("too many receipts to export in one call")] ExportTooLarge,
+    #[msg("VAA nonce 0 is rejected by reject_zero_nonce")] ZeroNonceNotAllowed,
+    #[msg("sequence account is not the Wormhole tracker for this emitter")] WrongSequenceForEmitter,
+    #[msg("finality omitted and no default_finality configured")] NoDefaultFinality,
+    #[msg("VAA emitter_chain is not the chain registered for its emitter")] ChainEmitterPairMismatch,
+    #[msg("payload hash already recorded under a different sequence")] DuplicatePayloadAcrossSequences,
+    #[msg("receipt was preallocated and has not been recorded")] ReceiptPreallocated,
+    #[msg("account is not the Core Bridge config PDA")] NotBridgeConfigAccount,
+    #[msg("EmitterReceiptCount account required")] EmitterCountRequired,
+    #[msg("emitter has reached max_receipts_per_emitter")] EmitterReceiptCapReached,
+    #[msg("payload is not framed as FRAMING_MAGIC | schema_version | body_len | body")] InvalidFraming,
+    #[msg("return data exceeds MAX_RETURN_DATA")] ReturnDataTooLarge,
+    #[msg("trusted_guardian_set account required when verify_signatures_independently is set")] TrustedGuardianSetRequired,
+    #[msg("VAA guardian_set_index is not the trusted guardian set's")] GuardianSetIndexMismatch,
+    #[msg("fewer than a quorum of trusted guardians signed the VAA")] InsufficientGuardianSignatures,
+    #[msg("guardian set must have 1..=19 keys")] InvalidGuardianSet,
+    #[msg("verify_signatures_independently is set: record VAAs through record_receipt_from_vaa")] IndependentVerificationRequired,
+    #[msg("recording is paused for this emitter")] EmitterPaused,
+    #[msg("bridge fee exceeds expected_fee by more than cfg.fee_slippage")] FeeAboveSlippage,
+    #[msg("receipt prev_hash does not link to the previous receipt")] ReceiptChainBroken,
+    #[msg("account is not an emitter cursor of this program")] CursorNotMigratable,
+    #[msg("receipt is younger than min_verify_age_seconds")] VerifyTooSoon,
+    #[msg("no verifying key registered for the proof's circuit id")] VerifyingKeyNotFound,
+    #[msg("proof circuit id does not match the verifying key")] CircuitIdMismatch,
+    #[msg("payload action is not allowed for this emitter")] ActionNotAllowedForEmitter,
+    #[msg("receipt has no field_root; store_field_root was off")] FieldRootNotStored,
+    #[msg("VAA field proof does not match the receipt field_root")] FieldProofInvalid,
+    #[msg("max_in_flight is set: post through post_wormhole_message with a batch_marker")] BatchMarkerRequired,
+    #[msg("account is not an EmitterReceiptCount PDA of this program")] InvalidEmitterCountAccount,
+    #[msg("EmitterCursor account required while reorg_tolerance is set")] CursorRequired,
+    #[msg("receipt is inside the reorg window and could be recorded again once closed")] ReceiptInReorgWindow,
+    #[msg("pending action was queued for another call or signer")] PendingActionMismatch,
+    #[msg("expected_digest required while require_expected_digest is set")] ExpectedDigestMissing,
+    #[msg("hash_index required while require_hash_index is set")] HashIndexRequired,
+    #[msg("cursor required while link_receipts is set")] ChainCursorRequired,
+    #[msg("record path does not link receipts; use record_receipt_from_vaa while link_receipts is set")] UnlinkedRecordPath,
+    #[msg("payload_hash cannot be asserted when the payload is hashed on chain")] PayloadHashAssertionRejected,
+    #[msg("attestor must be the admin or operator key")] AttestorNotAllowed,
+    #[msg("event_ring required once init_event_ring has run")] EventRingRequired,
+    #[msg("account is owned by this program but is not a receipt")] NotAReceiptAccount,
+    #[msg("receipt is already at the current Receipt::SIZE")] ReceiptAlreadyMigrated,
+    #[msg("fee token account is not owned by the fee collector")] FeeTokenAccountNotCollector,
+    #[msg("batch marker still awaits its round trip; post under a new batch_id")] BatchMarkerPending,
+    #[msg("batch has not round-tripped; its attempt counter stays open")] BatchNotConfirmed,
+    #[msg("no legacy verifying key at [VERIFYING_KEY_SEED]")] LegacyVerifyingKeyNotFound,
+}
+
+impl ZkError {
+    /// Stable error code (`6000 + index`), as seen by clients.
+    pub fn code(&self) -> u32 {
+        u32::from(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zeroed_config() -> ReceiptConfig {
+        let data = vec![0u8; ReceiptConfig::SIZE];
+        ReceiptConfig::deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    fn decoded_vaa(sequence: u64) -> vaa::DecodedVaa {
+        vaa::DecodedVaa {
+            version: 1,
+            consistency_level: 1,
+            timestamp: 0,
+            signature_set: Pubkey::default(),
+            guardian_set_index: 0,
+            nonce: 0,
+            sequence,
+            emitter_chain: 2,
+            emitter_address: [7u8; 32],
+            payload: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn vaa_sequence_must_match_receipt_sequence() {
+        let decoded = decoded_vaa(42);
+        assert!(check_vaa_sequence(&decoded, 42).is_ok());
+        assert_eq!(
+            check_vaa_sequence(&decoded, 43).unwrap_err(),
+            ZkError::VaaSequenceMismatch.into()
+        );
+    }
+
+    #[test]
+    fn emitter_pda_bump_uses_the_cached_bump_or_derives_it() {
+        let mut cfg = zeroed_config();
+        for role in [EMITTER_ROLE_PRIMARY, EMITTER_ROLE_SHADOW] {
+            let derived = Pubkey::find_program_address(&[emitter_seed(role)], &crate::ID).1;
+            assert_eq!(cfg.emitter_pda_bump(role), derived);
+        }
+        cfg.emitter_bump = 254;
+        cfg.shadow_emitter_bump = 253;
+        assert_eq!(cfg.emitter_pda_bump(EMITTER_ROLE_PRIMARY), 254);
+        assert_eq!(cfg.emitter_pda_bump(EMITTER_ROLE_SHADOW), 253);
+    }
+
+    #[test]
+    fn reorg_window_covers_tolerance_below_cursor() {
+        let mut cfg = zeroed_config();
+        assert!(!in_reorg_window(&cfg, 100, 100));
+        cfg.reorg_tolerance = 5;
+        assert!(in_reorg_window(&cfg, 100, 100));
+        assert!(in_reorg_window(&cfg, 100, 95));
+        assert!(!in_reorg_window(&cfg, 100, 94));
+        assert!(!in_reorg_window(&cfg, 100, 101));
+    }
+
+    #[test]
+    fn chain_emitter_entries_are_keyed_by_chain_and_address() {
+        let mut cfg = zeroed_config();
+        cfg.emitter = RemoteEmitter([1u8; 32]);
+        cfg.push_emitter(RemoteEmitter([2u8; 32]), 0, 2).unwrap();
+        cfg.push_emitter(RemoteEmitter([2u8; 32]), 0, 4).unwrap();
+        assert!(cfg.push_emitter(RemoteEmitter([2u8; 32]), 0, 4).is_err());
+
+        assert_eq!(cfg.emitter_entry(&[2u8; 32], 2), Some(1));
+        assert_eq!(cfg.emitter_entry(&[2u8; 32], 4), Some(2));
+        assert_eq!(cfg.emitter_entry(&[2u8; 32], 6), None);
+        assert_eq!(cfg.emitter_entry(&[1u8; 32], 6), Some(0));
+        cfg.require_chain_pins = true;
+        assert_eq!(cfg.emitter_entry(&[1u8; 32], 6), None);
+
+        cfg.remove_entry(1).unwrap();
+        assert_eq!(cfg.emitter_entry(&[2u8; 32], 2), None);
+        assert_eq!(cfg.emitter_entry(&[2u8; 32], 4), Some(1));
+    }
+
+    #[test]
+    fn admin_action_hash_covers_name_and_instruction_args() {
+        let emitter = [3u8; 32];
+        let mut data = b"set_cursor".to_vec();
+        data.extend_from_slice(&emitter);
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&2u16.to_le_bytes());
+        assert_eq!(
+            admin_action_hash("set_cursor", &(emitter, 7u64, true, 2u16)).unwrap(),
+            keccak::hash(&data).to_bytes()
+        );
+        assert_ne!(
+            admin_action_hash("add_emitter", &emitter).unwrap(),
+            admin_action_hash("remove_emitter", &emitter).unwrap()
+        );
+    }
+
+    #[test]
+    fn every_admin_setter_goes_through_the_timelock() {
+        // Admin-checked instructions that deliberately skip `timelock_gate!`.
+        const UNGATED: &[&str] = &[
+            // The config timelock itself; `update_receipt_config` is rejected while it is set.
+            "update_receipt_config",
+            "queue_change",
+            "apply_change",
+            "cancel_change",
+            // Emergency stops.
+            "set_posting_paused",
+            "set_emitter_paused",
+            // Bootstraps for accounts that every instruction requires.
+            "init_stats",
+            "init_event_counter",
+            // Receipt recording and moderation, not config.
+            "record_receipt_direct",
+            "tombstone_receipt",
+            "tombstone_receipt_by_hash",
+        ];
+        let source = include_str!("lib.rs");
+        let start = source.find("pub mod zk_coprocessor_program {").unwrap();
+        let program = &source[start..start + source[start..].find("\n}\n").unwrap()];
+        let mut ungated = Vec::new();
+        for body in program.split("\n    pub fn ").skip(1) {
+            let name = &body[..body.find(|c: char| c != '_' && !c.is_alphanumeric()).unwrap()];
+            let admin_only =
+                body.contains("ZkError::NotAdmin)") || body.contains("ZkError::NotFeeAuthority)");
+            if admin_only && !body.contains("timelock_gate!(") {
+                ungated.push(name);
+            }
+        }
+        assert_eq!(ungated, UNGATED);
+    }
+
+    #[test]
+    fn expected_digest_checked_and_required_with_flag() {
+        let mut cfg = zeroed_config();
+        let decoded = decoded_vaa(1);
+        let hash = [7u8; 32];
+        let expected = ExpectedDigest {
+            batch_id: decoded.nonce,
+            digest: hash,
+            registered_by: Pubkey::default(),
+            bump: 255,
+        };
+        assert!(check_expected_digest(&cfg, None, &decoded, &hash).is_ok());
+        assert!(check_expected_digest(&cfg, Some(&expected), &decoded, &hash).is_ok());
+        assert_eq!(
+            check_expected_digest(&cfg, Some(&expected), &decoded, &[8u8; 32]).unwrap_err(),
+            ZkError::PayloadDigestMismatch.into()
+        );
+        let other_batch = ExpectedDigest { batch_id: decoded.nonce + 1, ..expected.clone() };
+        assert_eq!(
+            check_expected_digest(&cfg, Some(&other_batch), &decoded, &hash).unwrap_err(),
+            ZkError::ExpectedDigestBatchMismatch.into()
+        );
+        cfg.require_expected_digest = true;
+        assert_eq!(
+            check_expected_digest(&cfg, None, &decoded, &hash).unwrap_err(),
+            ZkError::ExpectedDigestMissing.into()
+        );
+    }
+
+    #[test]
+    fn attestation_message_is_tagged_with_program_and_purpose() {
+        let data = vec![0u8; Receipt::SIZE];
+        let receipt = Receipt::deserialize(&mut data.as_slice()).unwrap();
+        let message = receipt.attestation_message();
+        assert!(message.starts_with(ATTEST_RECEIPT_TAG));
+        assert_eq!(&message[ATTEST_RECEIPT_TAG.len()..][..32], crate::ID.as_ref());
+        assert_eq!(&message[ATTEST_RECEIPT_TAG.len() + 32..], &receipt.canonical_bytes()[..]);
+    }
+
+    #[test]
+    fn emitter_count_close_keeps_totals() {
+        let mut counter = EmitterReceiptCount {
+            emitter: [7; 32],
+            count: 1,
+            bump: 255,
+            total_recorded: 3,
+            total_closed: 2,
+        };
+        counter.note_closed();
+        assert_eq!((counter.count, counter.total_recorded, counter.total_closed), (0, 3, 3));
+    }
+
+    #[test]
+    fn framed_destination_goes_inside_the_frame() {
+        let dest = [9u8; 32];
+        let mut payload = FRAMING_MAGIC.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[1, 0, 3, 0xaa, 0xbb, 0xcc]);
+
+        let unframed = prepend_destination(payload.clone(), Some(dest), false, false).unwrap();
+        assert_eq!((&unframed[..32], &unframed[32..]), (&dest[..], &payload[..]));
+
+        let mut cfg = zeroed_config();
+        cfg.enforce_framing = true;
+        let framed = prepend_destination(payload.clone(), Some(dest), false, true).unwrap();
+        assert_eq!(&framed[..3], &payload[..3]);
+        assert_eq!(u16::from_be_bytes([framed[3], framed[4]]), 35);
+        assert_eq!(&framed[FRAMING_HEADER_LEN..FRAMING_HEADER_LEN + 32], &dest);
+        assert_eq!(&framed[FRAMING_HEADER_LEN + 32..], &payload[FRAMING_HEADER_LEN..]);
+        assert!(check_framing(&cfg, &framed).is_ok());
+    }
+
+    #[test]
+    fn abi_payload_words_sets_the_record_layout_check() {
+        let mut cfg = zeroed_config();
+        let update = ReceiptConfigUpdate { abi_payload_words: Some(2), ..Default::default() };
+        cfg.apply_update(&update).unwrap();
+        assert_eq!(cfg.abi_payload_words, 2);
+        assert!(validate_abi_layout(&[0u8; 64], cfg.abi_payload_words).is_ok());
+        assert!(validate_abi_layout(&[0u8; 63], cfg.abi_payload_words).is_err());
+        assert!(validate_abi_layout(&[0u8; 96], cfg.abi_payload_words).is_err());
+    }
+
+    #[test]
+    fn migrate_receipt_accepts_only_short_receipts_of_ours() {
+        let mut current = Receipt::DISCRIMINATOR.to_vec();
+        current.resize(8 + Receipt::SIZE, 0);
+        let old = &current[..8 + Receipt::SIZE - 1];
+        let mut cursor = EmitterCursor::DISCRIMINATOR.to_vec();
+        cursor.resize(old.len(), 0);
+
+        assert!(check_receipt_migratable(&crate::ID, old).is_ok());
+        assert_eq!(
+            check_receipt_migratable(&crate::ID, &[]).unwrap_err(),
+            ZkError::ReceiptNotFound.into()
+        );
+        assert_eq!(
+            check_receipt_migratable(&Pubkey::new_unique(), old).unwrap_err(),
+            ZkError::ReceiptNotMigratable.into()
+        );
+        assert_eq!(
+            check_receipt_migratable(&crate::ID, &cursor).unwrap_err(),
+            ZkError::NotAReceiptAccount.into()
+        );
+        assert_eq!(
+            check_receipt_migratable(&crate::ID, &current).unwrap_err(),
+            ZkError::ReceiptAlreadyMigrated.into()
+        );
+    }
+
+    #[test]
+    fn short_receipt_loads_only_after_migration() {
+        let mut data = Vec::new();
+        let receipt = Receipt::deserialize(&mut vec![0u8; Receipt::SIZE].as_slice()).unwrap();
+        receipt.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - 1);
+        assert!(Receipt::try_deserialize(&mut data.as_slice()).is_err());
+        // `migrate_receipt` reallocs with zero-fill.
+        data.resize(8 + Receipt::SIZE, 0);
+        assert!(Receipt::try_deserialize(&mut data.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn finality_flag_accepts_only_documented_values() {
+        assert!(finality_from_flag(0).is_ok());
+        assert!(finality_from_flag(1).is_ok());
+        for flag in 2..=u8::MAX {
+            assert_eq!(finality_from_flag(flag).err(), Some(ZkError::InvalidFinalityFlag.into()));
+        }
+    }
+
+    #[test]
+    fn receipt_pda_seeds_sequence_big_endian() {
+        let emitter = [7u8; 32];
+        for sequence in [0u64, 1, 256, u64::MAX] {
+            assert_eq!(receipt_sequence_seed(sequence), sequence.to_be_bytes());
+            let expected = Pubkey::find_program_address(
+                &[RECEIPT_SEED, &emitter, &sequence.to_be_bytes()],
+                &crate::ID,
+            );
+            assert_eq!(receipt_pda(&emitter, sequence), expected);
+        }
+        // A little-endian client derives a different account.
+        let le = Pubkey::find_program_address(
+            &[RECEIPT_SEED, &emitter, &1u64.to_le_bytes()],
+            &crate::ID,
+        );
+        assert_ne!(receipt_pda(&emitter, 1).0, le.0);
+    }
+
+    #[test]
+    fn emitter_address_is_the_emitter_pda_key() {
+        let (pda, _) = Pubkey::find_program_address(&[b"emitter"], &crate::ID);
+        assert_eq!(local_emitter_address().0, pda.to_bytes());
+
+        let mut cfg = zeroed_config();
+        assert_eq!(cfg.local_emitter().0, pda.to_bytes());
+        cfg.cached_emitter_address = LocalEmitter([3u8; 32]);
+        assert_eq!(cfg.local_emitter().0, [3u8; 32]);
+    }
+
+    #[test]
+    fn own_emitter_is_rejected_as_remote() {
+        let local = local_emitter_address();
+        assert_eq!(
+            check_not_local_emitter(&local, &RemoteEmitter(local.0)).unwrap_err(),
+            ZkError::CannotRecordOwnEmitter.into()
+        );
+        let shadow = shadow_emitter_address();
+        assert_eq!(
+            check_not_local_emitter(&local, &RemoteEmitter(shadow.0)).unwrap_err(),
+            ZkError::CannotRecordOwnEmitter.into()
+        );
+        assert!(check_not_local_emitter(&local, &RemoteEmitter([1u8; 32])).is_ok());
+    }
+
+    #[test]
+    fn failed_emitter_count_leaves_the_counter_untouched() {
+        let key = Pubkey::new_unique();
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let mut data = Vec::new();
+        EmitterReceiptCount {
+            emitter: [0u8; 32],
+            count: 4,
+            bump: 0,
+            total_recorded: u64::MAX,
+            total_closed: 0,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let mut counter = Account::<EmitterReceiptCount>::try_from(&info).unwrap();
+        let cfg = zeroed_config();
+
+        assert_eq!(
+            note_emitter_receipt(&cfg, Some(&mut counter), None, &[7u8; 32]).unwrap_err(),
+            ZkError::EmitterCountRequired.into()
+        );
+        assert_eq!(
+            note_emitter_receipt(&cfg, Some(&mut counter), Some(254), &[7u8; 32]).unwrap_err(),
+            ZkError::StatsOverflow.into()
+        );
+        assert_eq!((counter.emitter, counter.count, counter.bump), ([0u8; 32], 4, 0));
+
+        counter.total_recorded = 0;
+        let count = note_emitter_receipt(&cfg, Some(&mut counter), Some(254), &[7u8; 32]).unwrap();
+        assert_eq!(count, 5);
+        assert_eq!((counter.emitter, counter.total_recorded, counter.bump), ([7u8; 32], 1, 254));
+        // Uncapped, the count is still required so a later cap sees every receipt.
+        assert_eq!(
+            note_emitter_receipt(&cfg, None, None, &[7u8; 32]).unwrap_err(),
+            ZkError::EmitterCountRequired.into()
+        );
+        assert_eq!(
+            release_emitter_receipt(None, None, &[7u8; 32]).unwrap_err(),
+            ZkError::EmitterCountRequired.into()
+        );
+        release_emitter_receipt(Some(&mut counter), Some(254), &[7u8; 32]).unwrap();
+        assert_eq!((counter.count, counter.total_closed), (4, 1));
+    }
+
+    #[test]
+    fn failed_record_advance_moves_no_cursor_counter_or_stats() {
+        let key = Pubkey::new_unique();
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let mut data = Vec::new();
+        EmitterReceiptCount {
+            emitter: [7u8; 32],
+            count: 4,
+            bump: 254,
+            total_recorded: 4,
+            total_closed: 0,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let mut count = Account::<EmitterReceiptCount>::try_from(&info).unwrap();
+        let mut cfg = zeroed_config();
+        let mut cursor = EmitterCursor {
+            emitter: [7u8; 32],
+            category: 0,
+            last_sequence: 10,
+            bump: 254,
+            last_receipt_hash: [0u8; 32],
+        };
+        let mut stats = StatsAccount {
+            total_recorded: 4,
+            total_direct: 0,
+            total_closed: 0,
+            last_recorded_slot: 1,
+            bump: 254,
+        };
+        let mut counter = EventCounter { global_event_seq: u64::MAX - 1, bump: 254 };
+
+        let mut advance = |cfg: &ReceiptConfig,
+                           cursor: &mut EmitterCursor,
+                           stats: &mut StatsAccount,
+                           counter: &mut EventCounter| {
+            advance_record_state(
+                cfg,
+                Some(cursor),
+                Some(&mut count),
+                Some(254),
+                stats,
+                counter,
+                (&[7u8; 32], 11, 2, 2),
+            )
+        };
+        // The event counter would overflow on the second reserved sequence.
+        assert_eq!(
+            advance(&cfg, &mut cursor, &mut stats, &mut counter).unwrap_err(),
+            ZkError::EventSeqOverflow.into()
+        );
+        assert_eq!(cursor.last_sequence, 10);
+        assert_eq!((stats.total_recorded, stats.last_recorded_slot), (4, 1));
+        assert_eq!(counter.global_event_seq, u64::MAX - 1);
+        // The emitter is full, the last check before any write.
+        counter.global_event_seq = u64::MAX - 2;
+        cfg.max_receipts_per_emitter = 4;
+        assert_eq!(
+            advance(&cfg, &mut cursor, &mut stats, &mut counter).unwrap_err(),
+            ZkError::EmitterReceiptCapReached.into()
+        );
+        assert_eq!(cursor.last_sequence, 10);
+        assert_eq!((stats.total_recorded, counter.global_event_seq), (4, u64::MAX - 2));
+
+        cfg.max_receipts_per_emitter = 0;
+        assert_eq!(
+            advance(&cfg, &mut cursor, &mut stats, &mut counter).unwrap(),
+            (u64::MAX - 1, 5)
+        );
+        assert_eq!(cursor.last_sequence, 11);
+        assert_eq!((stats.total_recorded, stats.last_recorded_slot), (5, 2));
+        assert_eq!(counter.global_event_seq, u64::MAX);
+        assert_eq!((count.count, count.total_recorded), (5, 5));
+    }
+
+    #[test]
+    fn account_sizes_match_their_serialized_layout() {
+        fn len<T: AnchorSerialize>(value: &T) -> usize {
+            let mut out = Vec::new();
+            value.serialize(&mut out).unwrap();
+            out.len()
+        }
+        let receipt = Receipt::deserialize(&mut vec![0u8; Receipt::SIZE].as_slice()).unwrap();
+        assert_eq!(len(&receipt), Receipt::SIZE);
+        assert_eq!(len(&zeroed_config()), ReceiptConfig::SIZE);
+        let counter = EmitterReceiptCount {
+            emitter: [0u8; 32],
+            count: 0,
+            bump: 0,
+            total_recorded: 0,
+            total_closed: 0,
+        };
+        assert_eq!(len(&counter), EmitterReceiptCount::SIZE);
+        assert_eq!(std::mem::size_of::<EmitterIndex>(), EmitterIndex::SIZE);
+    }
+
+    #[test]
+    fn config_views_fit_return_data_with_every_list_full() {
+        let mut cfg = zeroed_config();
+        cfg.extra_emitter_count = (ReceiptConfig::MAX_EMITTERS - 1) as u8;
+        cfg.extra_emitters = [RemoteEmitter([1u8; 32]); ReceiptConfig::MAX_EMITTERS - 1];
+        cfg.allowed_category_count = ReceiptConfig::MAX_CATEGORIES as u8;
+        cfg.treasury_allowlist = [Pubkey::new_unique(); ReceiptConfig::MAX_TREASURIES];
+        cfg.allowed_waivers = [Pubkey::new_unique(); ReceiptConfig::MAX_WAIVERS];
+        cfg.allowed_cpi_callers = [Pubkey::new_unique(); ReceiptConfig::MAX_CPI_CALLERS];
+        cfg.has_default_finality = true;
+
+        let lists = ConfigListsView::from(&cfg);
+        assert_eq!(lists.emitters.len(), ReceiptConfig::MAX_EMITTERS);
+        assert_eq!(lists.allowed_categories.len(), ReceiptConfig::MAX_CATEGORIES);
+        assert_eq!(lists.treasury_allowlist.len(), ReceiptConfig::MAX_TREASURIES);
+        assert_eq!(lists.allowed_waivers.len(), ReceiptConfig::MAX_WAIVERS);
+        assert_eq!(lists.allowed_cpi_callers.len(), ReceiptConfig::MAX_CPI_CALLERS);
+        fn encode<T: AnchorSerialize>(value: &T) -> Vec<u8> {
+            let mut out = Vec::new();
+            value.serialize(&mut out).unwrap();
+            out
+        }
+        for bytes in [encode(&ConfigView::from(&cfg)), encode(&lists)] {
+            assert!(bytes.len() <= MAX_RETURN_DATA, "{} bytes", bytes.len());
+        }
+    }
+
+    #[test]
+    fn mirror_bytes_round_trip_and_reject_other_versions() {
+        let mut receipt = Receipt::deserialize(&mut vec![0u8; Receipt::SIZE].as_slice()).unwrap();
+        receipt.emitter = [7u8; 32];
+        receipt.sequence = 42;
+        receipt.payload_hash = [9u8; 32];
+        let bytes = receipt.to_mirror_bytes();
+        assert_eq!((bytes[0], bytes.len()), (Receipt::LAYOUT_VERSION, 1 + Receipt::SIZE));
+        let back = Receipt::from_mirror_bytes(&bytes).unwrap();
+        assert_eq!(back.to_mirror_bytes(), bytes);
+
+        let mut old = bytes.clone();
+        old[0] = Receipt::LAYOUT_VERSION - 1;
+        assert_eq!(
+            Receipt::from_mirror_bytes(&old).unwrap_err(),
+            ZkError::MirrorVersionUnsupported.into()
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Receipt::from_mirror_bytes(&trailing).unwrap_err(),
+            ZkError::MirrorDecode.into()
+        );
+        assert_eq!(
+            Receipt::from_mirror_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            ZkError::MirrorDecode.into()
+        );
+        assert_eq!(Receipt::from_mirror_bytes(&[]).unwrap_err(), ZkError::MirrorDecode.into());
+    }
+
+    #[test]
+    fn single_emitter_allowlist_and_extras() {
+        let mut cfg = zeroed_config();
+        cfg.emitter = RemoteEmitter([1u8; 32]);
+        assert!(cfg.emitter_allowed(&[1u8; 32]));
+        assert!(!cfg.emitter_allowed(&[2u8; 32]));
+        // Stale slots past the count are not on the list.
+        cfg.extra_emitters[0] = RemoteEmitter([2u8; 32]);
+        assert!(!cfg.emitter_allowed(&[2u8; 32]));
+        cfg.extra_emitter_count = 1;
+        assert!(cfg.emitter_allowed(&[1u8; 32]));
+        assert!(cfg.emitter_allowed(&[2u8; 32]));
+        assert!(!cfg.emitter_allowed(&[3u8; 32]));
+    }
+
+    #[test]
+    fn emitter_index_wraps_after_capacity() {
+        let key = Pubkey::new_unique();
+        let owner = crate::ID;
+        let mut lamports = 0;
+        let mut data = EmitterIndex::DISCRIMINATOR.to_vec();
+        data.resize(8 + EmitterIndex::SIZE, 0);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        let loader = AccountLoader::<EmitterIndex>::try_from(&info).unwrap();
+        let mut index = loader.load_mut().unwrap();
+        index.push(1, [1u8; 32]);
+        assert_eq!((index.head, index.count), (1, 1));
+        for sequence in 2..=EmitterIndex::CAPACITY as u64 + 1 {
+            index.push(sequence, [0u8; 32]);
+        }
+        assert_eq!((index.head, index.count), (1, EmitterIndex::CAPACITY as u32));
+        // The oldest entry was overwritten by the newest.
+        assert_eq!(index.entries[0].sequence, EmitterIndex::CAPACITY as u64 + 1);
+        assert_eq!(index.entries[1].sequence, 2);
+    }
+
+    #[test]
+    fn bridge_data_must_be_the_config_pda_in_a_known_layout() {
+        let wormhole = Pubkey::new_unique();
+        let core = CorePdas::derive(wormhole, false);
+        let bridge = core.bridge;
+        let mut lamports = 0;
+        let read = |key: &Pubkey, owner: &Pubkey, mut data: Vec<u8>, lamports: &mut u64| {
+            let info = AccountInfo::new(key, false, false, lamports, &mut data, owner, false, 0);
+            read_bridge_data(&info, &core).map(|bridge| bridge.fee())
+        };
+        let mut data = vec![0u8; BRIDGE_DATA_LEN];
+        data[16..24].copy_from_slice(&100u64.to_le_bytes());
+
+        assert_eq!(read(&bridge, &wormhole, data.clone(), &mut lamports).unwrap(), 100);
+        let mut persistent = data.clone();
+        persistent.resize(BRIDGE_DATA_FEE_PERSISTENT_LEN, 0xff);
+        assert_eq!(read(&bridge, &wormhole, persistent, &mut lamports).unwrap(), 100);
+
+        assert_eq!(
+            read(&bridge, &Pubkey::new_unique(), data.clone(), &mut lamports).unwrap_err(),
+            ZkError::ConfigOwnerMismatch.into()
+        );
+        assert_eq!(
+            read(&Pubkey::new_unique(), &wormhole, data.clone(), &mut lamports).unwrap_err(),
+            ZkError::NotBridgeConfigAccount.into()
+        );
+        // A Core guardian set (index, key count, 20-byte keys, creation and
+        // expiration times) fails on its address before its length is read.
+        let (guardian_set, _) =
+            Pubkey::find_program_address(&[GUARDIAN_SET_SEED, &0u32.to_be_bytes()], &wormhole);
+        for guardians in [1usize, 19] {
+            let sized = vec![0u8; 4 + 4 + 20 * guardians + 8];
+            assert_eq!(
+                read(&guardian_set, &wormhole, sized, &mut lamports).unwrap_err(),
+                ZkError::NotBridgeConfigAccount.into()
+            );
+        }
+        for len in [0, 16, 28, 40] {
+            let mut sized = data.clone();
+            sized.resize(len, 0);
+            assert_eq!(
+                read(&bridge, &wormhole, sized, &mut lamports).unwrap_err(),
+                ZkError::UnsupportedBridgeLayout.into()
+            );
+        }
+    }
+
+    #[test]
+    fn fee_collector_is_pinned_only_while_strict() {
+        let wormhole = Pubkey::new_unique();
+        let (collector, _) = Pubkey::find_program_address(&[FEE_COLLECTOR_SEED], &wormhole);
+        let lenient = CorePdas::derive(wormhole, false);
+        let strict = CorePdas::derive(wormhole, true);
+        assert_eq!(lenient.fee_collector, None);
+        assert_eq!(strict.fee_collector, Some(collector));
+        assert_eq!(strict.bridge, lenient.bridge);
+
+        let (other_key, owner) = (Pubkey::new_unique(), wormhole);
+        let mut lamports = [0u64; 3];
+        let mut data: [Vec<u8>; 3] = Default::default();
+        let [l0, l1, l2] = &mut lamports;
+        let [d0, d1, d2] = &mut data;
+        let config = AccountInfo::new(&lenient.bridge, false, false, l0, d0, &owner, false, 0);
+        let pinned = AccountInfo::new(&collector, false, false, l1, d1, &owner, false, 0);
+        let other = AccountInfo::new(&other_key, false, true, l2, d2, &owner, false, 0);
+
+        assert!(check_fee_collector(&pinned, &strict).is_ok());
+        assert!(check_bridge_accounts(&config, &pinned, &strict).is_ok());
+        assert_eq!(
+            check_fee_collector(&other, &strict).unwrap_err(),
+            ZkError::FeeCollectorMismatch.into()
+        );
+        assert_eq!(
+            check_bridge_accounts(&config, &other, &strict).unwrap_err(),
+            ZkError::BridgeAccountsInconsistent.into()
+        );
+        // Lenient posts accept any writable collector and reject a read-only one.
+        assert!(check_fee_collector(&other, &lenient).is_ok());
+        assert!(check_bridge_accounts(&config, &other, &lenient).is_ok());
+        assert_eq!(
+            check_fee_collector(&pinned, &lenient).unwrap_err(),
+            ZkError::FeeCollectorNotWritable.into()
+        );
+        assert_eq!(
+            check_bridge_accounts(&other, &pinned, &lenient).unwrap_err(),
+            ZkError::BridgeAccountsInconsistent.into()
+        );
+    }
+
+    #[test]
+    fn sequence_tracker_must_belong_to_the_emitter() {
+        let wormhole = Pubkey::new_unique();
+        let source = Pubkey::new_unique();
+        let (tracker, _) =
+            Pubkey::find_program_address(&[SEQUENCE_SEED, source.as_ref()], &wormhole);
+        let other = Pubkey::new_unique();
+        let owner = crate::ID;
+        let (mut l1, mut l2, mut l3) = (0, 0, 0);
+        let (mut d1, mut d2, mut d3) = (vec![], vec![], vec![]);
+        let emitter = AccountInfo::new(&source, false, false, &mut l1, &mut d1, &owner, false, 0);
+        let right = AccountInfo::new(&tracker, false, true, &mut l2, &mut d2, &wormhole, false, 0);
+        let wrong = AccountInfo::new(&other, false, true, &mut l3, &mut d3, &wormhole, false, 0);
+
+        assert!(check_sequence_for_emitter(&right, &emitter, &wormhole).is_ok());
+        assert_eq!(
+            check_sequence_for_emitter(&wrong, &emitter, &wormhole).unwrap_err(),
+            ZkError::WrongSequenceForEmitter.into()
+        );
+    }
+
+    #[test]
+    fn return_data_over_the_limit_errors() {
+        assert!(set_return_bytes(&[0u8; MAX_RETURN_DATA]).is_ok());
+        assert_eq!(
+            set_return_bytes(&[0u8; MAX_RETURN_DATA + 1]).unwrap_err(),
+            ZkError::ReturnDataTooLarge.into()
+        );
+    }
+
+    #[test]
+    fn message_pda_is_keyed_by_sequence_and_role() {
+        let (pda, bump) = derive_message_pda(false, 5);
+        let seeds: &[&[u8]] = &[MESSAGE_SEED, &5u64.to_le_bytes(), &[bump]];
+        assert_eq!(Pubkey::create_program_address(seeds, &crate::ID).unwrap(), pda);
+        assert_ne!(derive_message_pda(false, 6).0, pda);
+        let (shadow, _) = derive_message_pda(true, 5);
+        assert_eq!(
+            shadow,
+            Pubkey::find_program_address(&[SHADOW_MESSAGE_SEED, &5u64.to_le_bytes()], &crate::ID).0
+        );
+    }
+
+    #[test]
+    fn apply_update_rejects_out_of_range_values() {
+        let rejected = |update: ReceiptConfigUpdate| {
+            zeroed_config().apply_update(&update).unwrap_err()
+        };
+        let none = ReceiptConfigUpdate::default;
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { min_age_seconds: Some(-1), ..none() }),
+            ZkError::InvalidMinAge.into()
+        );
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { max_vaa_age_seconds: Some(-1), ..none() }),
+            ZkError::InvalidMaxVaaAge.into()
+        );
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { clock_skew_tolerance_seconds: Some(-1), ..none() }),
+            ZkError::InvalidClockSkew.into()
+        );
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { hash_algo: Some(9), ..none() }),
+            ZkError::UnknownHashAlgo.into()
+        );
+        for max in [0, MAX_BATCH_SIZE_CEILING + 1] {
+            assert_eq!(
+                rejected(ReceiptConfigUpdate { max_batch_size: Some(max), ..none() }),
+                ZkError::InvalidMaxBatchSize.into()
+            );
+        }
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { expiration_buffer_seconds: Some(-1), ..none() }),
+            ZkError::InvalidExpirationBuffer.into()
+        );
+        assert_eq!(
+            rejected(ReceiptConfigUpdate { timelock_seconds: Some(-1), ..none() }),
+            ZkError::InvalidTimelock.into()
+        );
+
+        let mut cfg = zeroed_config();
+        let update = ReceiptConfigUpdate {
+            max_batch_size: Some(MAX_BATCH_SIZE_CEILING),
+            hash_algo: Some(HASH_ALGO_SHA256),
+            ..Default::default()
+        };
+        cfg.apply_update(&update).unwrap();
+        assert_eq!((cfg.max_batch_size, cfg.hash_algo), (MAX_BATCH_SIZE_CEILING, HASH_ALGO_SHA256));
+    }
+
+    #[test]
+    fn evm_emitters_checks_the_stored_allowlist() {
+        let mut cfg = zeroed_config();
+        cfg.emitter = RemoteEmitter([1u8; 32]);
+        let update = ReceiptConfigUpdate { evm_emitters: Some(true), ..Default::default() };
+        assert_eq!(cfg.apply_update(&update).unwrap_err(), ZkError::EmitterNotLeftPadded.into());
+        assert!(!cfg.evm_emitters);
+
+        let mut padded = [0u8; 32];
+        padded[12..].copy_from_slice(&[1u8; 20]);
+        cfg.emitter = RemoteEmitter(padded);
+        cfg.apply_update(&update).unwrap();
+        assert!(cfg.evm_emitters);
+    }
+
+    #[cfg(feature = "test-helpers")]
+    #[test]
+    fn receipt_fixture_loads_as_an_account() {
+        let receipt = make_receipt([7u8; 32], 42, [9u8; 32]);
+        assert_eq!(receipt.bump, receipt_pda(&[7u8; 32], 42).1);
+        let data = receipt_account_data(&receipt);
+        assert_eq!(data.len(), 8 + Receipt::SIZE);
+        let loaded = Receipt::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!(loaded.canonical_bytes(), receipt.canonical_bytes());
+        assert_eq!(receipt_account_data(&loaded), data);
+    }
+
+    #[test]
+    fn token_account_reads_mint_and_owner() {
+        let (key, token) = (Pubkey::new_unique(), SPL_TOKEN_PROGRAM_ID);
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = [mint.to_bytes(), owner.to_bytes()].concat();
+        data.resize(SPL_TOKEN_ACCOUNT_LEN, 0);
+        let mut short = data[..82].to_vec();
+        let (mut l1, mut l2) = (0, 0);
+        let account = AccountInfo::new(&key, false, true, &mut l1, &mut data, &token, false, 0);
+        let mint_like = AccountInfo::new(&key, false, true, &mut l2, &mut short, &token, false, 0);
+
+        assert_eq!(read_token_account(&account).unwrap(), (mint, owner));
+        assert_eq!(read_token_account(&mint_like).unwrap_err(), ZkError::FeeMintMismatch.into());
+    }
+
+    #[test]
+    fn spend_budget_binds_within_a_window() {
+        let mut budget = SpendBudget { spent: 0, window_start: 0, bump: 0 };
+        assert_eq!(
+            budget.draw(100, 0, 10, 1_000).unwrap_err(),
+            ZkError::InvalidBudgetWindow.into()
+        );
+        assert_eq!(budget.draw(100, 60, 60, 1_000).unwrap(), 40);
+        assert_eq!(budget.draw(100, 60, 41, 1_059).unwrap_err(), ZkError::BudgetExceeded.into());
+        assert_eq!(budget.draw(100, 60, 40, 1_059).unwrap(), 0);
+        // The next window starts over.
+        assert_eq!(budget.draw(100, 60, 30, 1_060).unwrap(), 70);
+        assert_eq!((budget.spent, budget.window_start), (30, 1_060));
+    }
+
+    #[test]
+    fn pending_batch_marker_is_not_reused() {
+        let mut marker =
+            BatchMarker { batch_id: 1, sequence: 0, posted_slot: 0, round_tripped: false, bump: 0 };
+        assert!(check_marker_reusable(&marker).is_ok());
+        marker.bump = 254;
+        assert_eq!(
+            check_marker_reusable(&marker).unwrap_err(),
+            ZkError::BatchMarkerPending.into()
+        );
+        marker.round_tripped = true;
+        assert!(check_marker_reusable(&marker).is_ok());
+    }
 }
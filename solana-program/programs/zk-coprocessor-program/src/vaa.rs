@@ -0,0 +1,276 @@
+//! PostedVAA decoding shared by every instruction that reads VAA accounts.
+//! Layout (Core Bridge `PostedVAAData`, Borsh/little-endian after the magic):
+//! b"vaa" | version u8 | consistency_level u8 | timestamp u32 | signature_set |
+//! guardian_set_index u32 (legacy "submission_time") | nonce u32 | sequence u64 |
+//! emitter_chain u16 | emitter_address [u8; 32] | payload (u32 len + bytes).
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{hash, keccak};
+
+use crate::{ZkError, HASH_ALGO_KECCAK256, HASH_ALGO_SHA256};
+
+const MAGIC: &[u8] = b"vaa";
+
+/// Envelope and payload of a PostedVAA account; field order is the wire order.
+#[derive(AnchorDeserialize)]
+pub struct DecodedVaa {
+    pub version: u8,
+    pub consistency_level: u8,
+    /// Guardian-observed time (unix seconds).
+    pub timestamp: u32,
+    pub signature_set: Pubkey,
+    pub guardian_set_index: u32,
+    pub nonce: u32,
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: Vec<u8>,
+}
+
+impl DecodedVaa {
+    /// Hash under `algo` (`HASH_ALGO_*`) of the payload minus its first
+    /// `prefix_skip` bytes, as stored on receipts. Fails with
+    /// `PrefixSkipTooLarge` if the payload is shorter than `prefix_skip`.
+    pub fn payload_hash(&self, algo: u8, prefix_skip: u16) -> Result<[u8; 32]> {
+        hash_payload(&self.payload, algo, prefix_skip)
+    }
+
+    /// Leading payload byte, the message type tag of our payload schema (`1`
+    /// for the Portal's `publishReceipt`); 0 for an empty payload.
+    pub fn action(&self) -> u8 {
+        self.payload.first().copied().unwrap_or(0)
+    }
+
+    /// Canonical VAA hash: keccak-256 of the signed body `timestamp | nonce |
+    /// emitter_chain | emitter_address | sequence | consistency_level |
+    /// payload` (integers big-endian), the id Core Bridge keys PostedVAA
+    /// accounts by.
+    pub fn body_hash(&self) -> [u8; 32] {
+        keccak::hashv(&[
+            &self.timestamp.to_be_bytes()[..],
+            &self.nonce.to_be_bytes(),
+            &self.emitter_chain.to_be_bytes(),
+            &self.emitter_address,
+            &self.sequence.to_be_bytes(),
+            &[self.consistency_level],
+            &self.payload,
+        ])
+        .to_bytes()
+    }
+
+    /// Merkle root over the VAA's fields, stored as `Receipt::field_root` so
+    /// one field can later be proven with `verify_field`. Leaves, in order:
+    ///
+    /// | Index | Field | Value bytes |
+    /// |------:|-------|-------------|
+    /// | 0 | `FIELD_EMITTER` | `emitter_address` (32) |
+    /// | 1 | `FIELD_SEQUENCE` | `sequence` u64 BE |
+    /// | 2 | `FIELD_NONCE` | `nonce` u32 BE |
+    /// | 3 | `FIELD_TIMESTAMP` | `timestamp` u32 BE |
+    /// | 4 | `FIELD_PAYLOAD_HASH` | the receipt's `payload_hash` (32) |
+    ///
+    /// Leaf `i` is `field_leaf(i, value)`, padded to 8 leaves with zero
+    /// leaves; parents are `keccak(left || right)`.
+    pub fn field_root(&self, payload_hash: &[u8; 32]) -> [u8; 32] {
+        let sequence = self.sequence.to_be_bytes();
+        let nonce = self.nonce.to_be_bytes();
+        let timestamp = self.timestamp.to_be_bytes();
+        let values: [&[u8]; FIELD_COUNT] =
+            [&self.emitter_address, &sequence, &nonce, &timestamp, payload_hash];
+        let mut level = [[0u8; 32]; 1 << FIELD_PROOF_LEN];
+        for (i, value) in values.iter().enumerate() {
+            level[i] = field_leaf(i as u8, value);
+        }
+        let mut width = level.len();
+        while width > 1 {
+            for i in 0..width / 2 {
+                level[i] = keccak::hashv(&[&level[2 * i], &level[2 * i + 1]]).to_bytes();
+            }
+            width /= 2;
+        }
+        level[0]
+    }
+}
+
+pub const FIELD_EMITTER: u8 = 0;
+pub const FIELD_SEQUENCE: u8 = 1;
+pub const FIELD_NONCE: u8 = 2;
+pub const FIELD_TIMESTAMP: u8 = 3;
+pub const FIELD_PAYLOAD_HASH: u8 = 4;
+/// Leaves of `DecodedVaa::field_root` that hold a field.
+pub const FIELD_COUNT: usize = 5;
+/// Siblings in a field proof: the depth of the 8-leaf tree.
+pub const FIELD_PROOF_LEN: usize = 3;
+
+/// Byte length of field `index`'s value; `None` past `FIELD_COUNT`.
+pub fn field_len(index: u8) -> Option<usize> {
+    match index {
+        FIELD_EMITTER | FIELD_PAYLOAD_HASH => Some(32),
+        FIELD_SEQUENCE => Some(8),
+        FIELD_NONCE | FIELD_TIMESTAMP => Some(4),
+        _ => None,
+    }
+}
+
+/// `keccak(index || value)`; the index keeps equal values in different
+/// fields apart.
+pub fn field_leaf(index: u8, value: &[u8]) -> [u8; 32] {
+    keccak::hashv(&[&[index], value]).to_bytes()
+}
+
+/// Root reached from field `index` holding `value` through `proof`, the
+/// siblings from the leaf up; at each level the index bit picks the side,
+/// as in `verify_inclusion`: 0 hashes `node || sibling`, 1 `sibling || node`.
+pub fn field_proof_root(index: u8, value: &[u8], proof: &[[u8; 32]; FIELD_PROOF_LEN]) -> [u8; 32] {
+    let mut node = field_leaf(index, value);
+    let mut bits = index;
+    for sibling in proof {
+        node = if bits & 1 == 0 {
+            keccak::hashv(&[&node, sibling])
+        } else {
+            keccak::hashv(&[sibling, &node])
+        }
+        .to_bytes();
+        bits >>= 1;
+    }
+    node
+}
+
+/// `DecodedVaa::payload_hash` over raw payload bytes, e.g. a payload about to
+/// be posted.
+pub fn hash_payload(payload: &[u8], algo: u8, prefix_skip: u16) -> Result<[u8; 32]> {
+    let body = payload
+        .get(prefix_skip as usize..)
+        .ok_or(error!(ZkError::PrefixSkipTooLarge))?;
+    match algo {
+        HASH_ALGO_KECCAK256 => Ok(keccak::hash(body).to_bytes()),
+        HASH_ALGO_SHA256 => Ok(hash::hash(body).to_bytes()),
+        _ => err!(ZkError::UnknownHashAlgo),
+    }
+}
+
+/// Decodes a PostedVAA account. Does not check the account owner.
+pub fn decode_posted_vaa(account: &AccountInfo) -> Result<DecodedVaa> {
+    let data = account.try_borrow_data()?;
+    decode(&data)
+}
+
+/// Decodes PostedVAA account data: empty, wrong magic and truncated data
+/// map to distinct errors.
+pub fn decode(data: &[u8]) -> Result<DecodedVaa> {
+    require!(!data.is_empty(), ZkError::PostedVaaEmpty);
+    require!(data.starts_with(MAGIC), ZkError::PostedVaaBadMagic);
+    let mut body: &[u8] = &data[MAGIC.len()..];
+    DecodedVaa::deserialize(&mut body).map_err(|_| error!(ZkError::PostedVaaDecode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posted_vaa_data(payload: &[u8]) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        data.push(1); // version
+        data.push(32); // consistency_level
+        data.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        data.extend_from_slice(&[5u8; 32]); // signature_set
+        data.extend_from_slice(&3u32.to_le_bytes()); // guardian_set_index
+        data.extend_from_slice(&9u32.to_le_bytes()); // nonce
+        data.extend_from_slice(&42u64.to_le_bytes()); // sequence
+        data.extend_from_slice(&2u16.to_le_bytes()); // emitter_chain
+        data.extend_from_slice(&[7u8; 32]); // emitter_address
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn decode_reads_every_field_in_wire_order() {
+        let decoded = decode(&posted_vaa_data(&[1, 2, 3])).unwrap();
+        assert_eq!((decoded.version, decoded.consistency_level), (1, 32));
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+        assert_eq!(decoded.signature_set, Pubkey::new_from_array([5u8; 32]));
+        assert_eq!((decoded.guardian_set_index, decoded.nonce), (3, 9));
+        assert_eq!((decoded.sequence, decoded.emitter_chain), (42, 2));
+        assert_eq!(decoded.emitter_address, [7u8; 32]);
+        assert_eq!(decoded.payload, vec![1, 2, 3]);
+        assert_eq!(decoded.action(), 1);
+    }
+
+    #[test]
+    fn decode_errors_are_distinct() {
+        assert_eq!(decode(&[]).err(), Some(ZkError::PostedVaaEmpty.into()));
+        let mut bad_magic = posted_vaa_data(&[1]);
+        bad_magic[0] = b'x';
+        assert_eq!(decode(&bad_magic).err(), Some(ZkError::PostedVaaBadMagic.into()));
+        let data = posted_vaa_data(&[1, 2, 3]);
+        assert_eq!(
+            decode(&data[..data.len() - 1]).err(),
+            Some(ZkError::PostedVaaDecode.into())
+        );
+    }
+
+    #[test]
+    fn body_hash_covers_the_signed_body_big_endian() {
+        let decoded = decode(&posted_vaa_data(&[1, 2, 3])).unwrap();
+        let mut body = 1_700_000_000u32.to_be_bytes().to_vec();
+        body.extend_from_slice(&9u32.to_be_bytes());
+        body.extend_from_slice(&2u16.to_be_bytes());
+        body.extend_from_slice(&[7u8; 32]);
+        body.extend_from_slice(&42u64.to_be_bytes());
+        body.push(32);
+        body.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(decoded.body_hash(), keccak::hash(&body).to_bytes());
+    }
+
+    #[test]
+    fn payload_hash_skips_prefix_and_rejects_unknown_algos() {
+        let payload = [1u8, 2, 3, 4];
+        assert_eq!(
+            hash_payload(&payload, HASH_ALGO_KECCAK256, 1).unwrap(),
+            keccak::hash(&payload[1..]).to_bytes()
+        );
+        assert_eq!(
+            hash_payload(&payload, HASH_ALGO_SHA256, 0).unwrap(),
+            hash::hash(&payload).to_bytes()
+        );
+        assert_eq!(
+            hash_payload(&payload, HASH_ALGO_KECCAK256, 4).unwrap(),
+            keccak::hash(&[]).to_bytes()
+        );
+        assert_eq!(
+            hash_payload(&payload, HASH_ALGO_KECCAK256, 5).err(),
+            Some(ZkError::PrefixSkipTooLarge.into())
+        );
+        assert_eq!(hash_payload(&payload, 9, 0).err(), Some(ZkError::UnknownHashAlgo.into()));
+    }
+
+    #[test]
+    fn field_proofs_reach_the_field_root() {
+        let decoded = decode(&posted_vaa_data(&[1, 2, 3])).unwrap();
+        let payload_hash = [4u8; 32];
+        let root = decoded.field_root(&payload_hash);
+
+        // Leaves padded to 8 with zero leaves, as `field_root` builds them.
+        let sequence = decoded.sequence.to_be_bytes();
+        let nonce = decoded.nonce.to_be_bytes();
+        let timestamp = decoded.timestamp.to_be_bytes();
+        let values: [&[u8]; FIELD_COUNT] =
+            [&decoded.emitter_address, &sequence, &nonce, &timestamp, &payload_hash];
+        let mut leaves = [[0u8; 32]; 1 << FIELD_PROOF_LEN];
+        for (i, value) in values.iter().enumerate() {
+            leaves[i] = field_leaf(i as u8, value);
+            assert_eq!(field_len(i as u8), Some(value.len()));
+        }
+        assert_eq!(field_len(FIELD_COUNT as u8), None);
+        let parent = |l: &[u8; 32], r: &[u8; 32]| keccak::hashv(&[l, r]).to_bytes();
+        let level1: Vec<[u8; 32]> = leaves.chunks(2).map(|p| parent(&p[0], &p[1])).collect();
+        let level2: Vec<[u8; 32]> = level1.chunks(2).map(|p| parent(&p[0], &p[1])).collect();
+
+        // FIELD_SEQUENCE is leaf 1: a right child, then a left child twice.
+        let proof = [leaves[0], level1[1], level2[1]];
+        assert_eq!(field_proof_root(FIELD_SEQUENCE, &sequence, &proof), root);
+        assert_ne!(field_proof_root(FIELD_SEQUENCE, &43u64.to_be_bytes(), &proof), root);
+        assert_ne!(field_proof_root(FIELD_NONCE, &sequence, &proof), root);
+    }
+}